@@ -1,18 +1,32 @@
-use austral_lib::{ast::ModuleDef, compiler::compile_to_binary};
-use chumsky::Parser;
-use melior::{dialect::DialectRegistry, Context};
-use std::{
-    fs,
-    path::Path,
-    process::{Child, Command, Stdio},
+use austral_lib::{
+    ast::{ModuleDecl, ModuleDef},
+    compiler::{
+        compile_for_target, link_module, module_to_assembly, module_to_llvm_ir, module_to_object,
+        TargetOptions,
+    },
+    doc,
 };
+use chumsky::Parser;
+use melior::{dialect::DialectRegistry, utility::register_all_llvm_translations, Context};
+use std::{fs, path::Path, process};
 
 #[derive(clap::Parser, Debug)]
-#[clap(name = "austral", about = "Austral compiler")]
+#[clap(
+    name = "austral",
+    about = "Austral compiler",
+    subcommand_negates_reqs = true
+)]
 struct AustralCli {
+    #[command(subcommand)]
+    command: Option<AustralCommand>,
+
     /// File to compile
     #[arg(required = true)]
-    input_file: String,
+    input_file: Option<String>,
+
+    /// Interface of a module imported by the file to compile
+    #[arg(short = 'i', long = "interface")]
+    interfaces: Vec<String>,
 
     /// Emit object file
     #[arg(short = 'o', long = "output")]
@@ -25,6 +39,10 @@ struct AustralCli {
     #[arg(short = 's', long = "assembly", default_value_t = false)]
     emit_assembler: bool,
 
+    /// Emit an object file instead of linking a binary, which is needed for other targets
+    #[arg(short = 'c', long = "object", default_value_t = false)]
+    emit_object: bool,
+
     /// Emit LLVM IR
     #[arg(short = 'l', long = "llvm", default_value_t = false)]
     emit_llvm: bool,
@@ -36,12 +54,66 @@ struct AustralCli {
     /// Print AST
     #[arg(short = 'a', long = "ast", default_value_t = false)]
     print_ast: bool,
+
+    /// Target triple to compile for, instead of the host
+    #[arg(long = "target")]
+    target: Option<String>,
+
+    /// Target CPU, instead of the host's or a generic one
+    #[arg(long = "cpu")]
+    cpu: Option<String>,
+}
+
+impl AustralCli {
+    fn target_options(&self) -> TargetOptions {
+        let mut target = match &self.target {
+            Some(triple) => TargetOptions::new(triple),
+            None => TargetOptions::host(),
+        };
+        // The host features describe the host CPU, not the one requested.
+        if let Some(cpu) = &self.cpu {
+            target.cpu = cpu.clone();
+            target.features.clear();
+        }
+
+        if self.lib {
+            target.for_library()
+        } else {
+            target
+        }
+    }
+}
+
+#[derive(clap::Subcommand, Debug)]
+enum AustralCommand {
+    /// Generate HTML and Markdown documentation from module interfaces
+    Doc {
+        /// Module interface files to document
+        #[arg(required = true)]
+        input_files: Vec<String>,
+
+        /// Directory to write the pages into
+        #[arg(short = 'o', long = "output", default_value = "doc")]
+        output: String,
+    },
 }
 
 fn main() {
     let args: AustralCli = clap::Parser::parse();
 
-    let input_file = fs::read_to_string(args.input_file).unwrap();
+    if let Some(AustralCommand::Doc {
+        input_files,
+        output,
+    }) = &args.command
+    {
+        if let Err(error) = generate_docs(input_files, Path::new(output)) {
+            eprintln!("error: {error}");
+            process::exit(1);
+        }
+        return;
+    }
+
+    let input_file = fs::read_to_string(args.input_file.as_deref().unwrap()).unwrap();
 
     let tokens = austral_lib::lexer::lex(input_file.as_str())
         .map(|(token, _span)| token)
@@ -55,15 +127,41 @@ fn main() {
         return;
     }
 
+    let interfaces = match args
+        .interfaces
+        .iter()
+        .map(|path| read_interface(path))
+        .collect::<Result<Vec<_>, _>>()
+    {
+        Ok(interfaces) => interfaces,
+        Err(error) => {
+            eprintln!("error: {error}");
+            process::exit(1);
+        }
+    };
+
+    if let Err(error) = austral_lib::compiler::check(&ast, &interfaces) {
+        eprintln!("error: {error}");
+        process::exit(1);
+    }
+
     let context = Context::new();
     context.append_dialect_registry(&{
         let dialect_registry = DialectRegistry::new();
         melior::utility::register_all_dialects(&dialect_registry);
         dialect_registry
     });
+    register_all_llvm_translations(&context);
     context.load_all_available_dialects();
 
-    let mut compiled_module = austral_lib::compiler::compile(&context, &ast, &[]);
+    let target = args.target_options();
+    let mut compiled_module = match compile_for_target(&context, &ast, &interfaces, &target) {
+        Ok(module) => module,
+        Err(error) => {
+            eprintln!("error: {error}");
+            process::exit(1);
+        }
+    };
 
     if args.emit_mlir {
         let mlir_code = compiled_module.as_operation();
@@ -71,38 +169,48 @@ fn main() {
         return;
     }
 
-    if args.emit_llvm || args.emit_assembler {
+    if let Err(error) =
         austral_lib::backend::pass_manager::run_pass_manager(&context, &mut compiled_module)
-            .unwrap();
-        let optimized_code = compiled_module.as_operation();
-
-        let echo_mlir = echo(&optimized_code.to_string()).unwrap();
-
-        let mlir_traslate = Command::new("/opt/homebrew/opt/llvm/bin/mlir-translate")
-            .args(["--mlir-to-llvmir", "-"])
-            .stdin(Stdio::from(echo_mlir.stdout.unwrap()))
-            .spawn();
+    {
+        eprintln!("error: {error}");
+        process::exit(1);
+    }
 
-        let output = mlir_traslate.unwrap().wait_with_output().unwrap();
-        let llvm_ir = String::from_utf8_lossy(&output.stdout);
-        if args.emit_llvm {
-            println!("{}", llvm_ir.to_string());
-            return;
+    if args.emit_llvm {
+        match module_to_llvm_ir(&compiled_module) {
+            Ok(llvm_ir) => print!("{llvm_ir}"),
+            Err(error) => {
+                eprintln!("error: {error}");
+                process::exit(1);
+            }
         }
+        return;
+    }
 
-        // if args.emit_assembler {
-        //     let echo_llvmir = echo(&llvm_ir.to_string()).unwrap();
-        //     let clang_asm = Command::new("clang")
-        //         .args(["-S", "-o", "out.s", "-x", "ir", "-"])
-        //         .stdin(Stdio::from(echo_llvmir.stdout.unwrap()))
-        //         .spawn();
-
-        //     let clang_output = clang_asm.unwrap().wait_with_output().unwrap();
-        //     let asm = String::from_utf8_lossy(&clang_output.stdout);
-        //     println!("{}", asm.to_string());
-        //     return;
-        // }
+    if args.emit_assembler {
+        match module_to_assembly(&compiled_module, &target) {
+            Ok(assembly) => print!("{assembly}"),
+            Err(error) => {
+                eprintln!("error: {error}");
+                process::exit(1);
+            }
+        }
+        return;
+    }
 
+    if args.emit_object {
+        let output = args.output.as_deref().unwrap_or("a.o");
+        let object = match module_to_object(&compiled_module, &target) {
+            Ok(object) => object,
+            Err(error) => {
+                eprintln!("error: {error}");
+                process::exit(1);
+            }
+        };
+        if let Err(error) = fs::write(output, object) {
+            eprintln!("error: {output}: {error}");
+            process::exit(1);
+        }
         return;
     }
 
@@ -112,12 +220,47 @@ fn main() {
         String::from("a.out")
     });
 
-    compile_to_binary(&input_file, args.lib, Path::new(&output)).unwrap();
+    if let Err(error) = link_module(&compiled_module, args.lib, Path::new(&output), &target) {
+        eprintln!("error: {error}");
+        process::exit(1);
+    }
 }
 
-fn echo(text: &str) -> Result<Child, std::io::Error> {
-    Command::new("echo")
-        .arg(&format!("{}", text))
-        .stdout(Stdio::piped())
-        .spawn()
+/// Parse a module interface, returning a diagnostic if it can't be read or parsed.
+fn read_interface(path: &str) -> Result<ModuleDecl, String> {
+    let input_file = fs::read_to_string(path).map_err(|error| format!("{path}: {error}"))?;
+    let tokens = austral_lib::lexer::lex(input_file.as_str())
+        .map(|(token, _span)| token.map_err(|error| format!("{path}: {error}")))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let result = ModuleDecl::parser().parse(&tokens).into_result();
+    result.map_err(|errors| {
+        errors
+            .iter()
+            .map(|error| match error.found() {
+                Some(token) => format!("{path}: unexpected {token:?} at token {}", error.span()),
+                None => format!("{path}: unexpected end of input"),
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    })
+}
+
+/// Render the pages of the interfaces in `input_files` into `output`.
+fn generate_docs(input_files: &[String], output: &Path) -> Result<(), String> {
+    let modules = input_files
+        .iter()
+        .map(|path| read_interface(path))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    fs::create_dir_all(output).map_err(|error| format!("{}: {error}", output.display()))?;
+    for format in [doc::Format::Html, doc::Format::Markdown] {
+        for page in doc::render(&modules, format) {
+            let path = output.join(page.file_name);
+            fs::write(&path, page.contents)
+                .map_err(|error| format!("{}: {error}", path.display()))?;
+        }
+    }
+
+    Ok(())
 }
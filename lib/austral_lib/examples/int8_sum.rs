@@ -17,6 +17,6 @@ fn main() {
     });
     context.load_all_available_dialects();
 
-    let prog = austral_lib::compiler::compile(&context, &ast, &[]);
+    let prog = austral_lib::compiler::compile(&context, &ast, &[]).unwrap();
     println!("{}", prog.as_operation());
 }
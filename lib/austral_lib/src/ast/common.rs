@@ -2,6 +2,7 @@ use super::{Extra, FnCallArgs};
 use crate::lexer::Token;
 use chumsky::prelude::*;
 use serde::{Deserialize, Serialize};
+use std::fmt::{self, Display, Formatter};
 
 #[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
 pub struct DocString {
@@ -15,11 +16,48 @@ impl DocString {
         }
     }
 
+    /// Build a docstring from the contents of a triple-quoted string, which are normalized so that
+    /// the indentation of the declaration they document isn't part of the text:
+    ///
+    ///   - Line endings become `\n` and trailing whitespace is removed from every line.
+    ///   - Leading whitespace is removed from the first line, which may follow the quotes.
+    ///   - The indentation common to every other non-blank line is removed from them.
+    ///   - Blank lines at the start and the end are removed.
+    pub fn dedent(raw: &str) -> Self {
+        let mut lines = raw.lines().map(str::trim_end);
+        let first = lines.next().unwrap_or_default().trim_start();
+        let rest = lines.collect::<Vec<_>>();
+
+        let indent = rest
+            .iter()
+            .filter(|line| !line.is_empty())
+            .map(|line| line.len() - line.trim_start_matches([' ', '\t']).len())
+            .min()
+            .unwrap_or_default();
+        let lines = std::iter::once(first)
+            .chain(
+                rest.iter()
+                    .map(|line| line.get(indent..).unwrap_or_default()),
+            )
+            .collect::<Vec<_>>();
+
+        let start = lines.iter().position(|line| !line.is_empty());
+        let end = lines.iter().rposition(|line| !line.is_empty());
+        let contents = match start.zip(end) {
+            Some((start, end)) => lines[start..=end].join("\n"),
+            None => String::new(),
+        };
+
+        Self { contents }
+    }
+
+    pub fn contents(&self) -> &str {
+        &self.contents
+    }
+
     pub fn parser<'a>() -> impl Clone + Parser<'a, &'a [Token<'a>], Self, Extra<'a>> {
         any().try_map(|token, span| match token {
-            Token::TripleString(contents) => Ok(Self {
-                contents: contents.to_string(),
-            }),
+            Token::TripleString(contents) => Ok(Self::dedent(&contents)),
             _ => Err(Rich::custom(span, "expected a docstring")),
         })
     }
@@ -45,6 +83,12 @@ impl Ident {
     }
 }
 
+impl Display for Ident {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.name)
+    }
+}
+
 #[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
 pub struct Universe(pub crate::lexer::Universe);
 
@@ -105,3 +149,33 @@ impl Pragma {
             .map(|(name, args)| Self { name, args })
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn dedent_doc_string() {
+        assert_eq!(
+            DocString::dedent("\n    Summary.\n\n      Indented.\n    Last.  \n    ").contents(),
+            "Summary.\n\n  Indented.\nLast."
+        );
+        assert_eq!(
+            DocString::dedent(" Summary.\r\n        Details.\r\n        ").contents(),
+            "Summary.\nDetails."
+        );
+        assert_eq!(DocString::dedent("Inline.").contents(), "Inline.");
+        assert_eq!(DocString::dedent("\n  \n\t").contents(), "");
+    }
+
+    #[test]
+    fn parse_doc_string() {
+        let tokens = [Token::TripleString("\n    Docstring.\n    ".into())];
+
+        assert_eq!(
+            DocString::parser().parse(&tokens).into_result(),
+            Ok(DocString::new("Docstring."))
+        );
+    }
+}
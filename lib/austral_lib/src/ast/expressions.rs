@@ -187,7 +187,8 @@ pub enum CmpExpr {
 
 impl CmpExpr {
     pub fn parser<'a>() -> impl Clone + Parser<'a, &'a [Token<'a>], Self, Extra<'a>> {
-        todo()
+        let cache = Rc::new(ParserCache::default());
+        Self::recursive_parser(cache)
     }
 
     fn recursive_parser<'a>(
@@ -589,19 +590,22 @@ impl IntrinExpr {
 
                 parser.define(
                     just(Token::Embed)
-                        .ignore_then(group((
-                            TypeSpec::parser(),
-                            just(Token::Comma).ignore_then(literal_str().map(Cow::into_owned)),
-                            just(Token::Comma)
-                                .ignore_then(
-                                    Expression::recursive_parser(cache.clone())
-                                        .separated_by(just(Token::Comma))
-                                        .allow_trailing()
-                                        .collect::<Vec<_>>(),
-                                )
-                                .or_not()
-                                .map(Option::unwrap_or_default),
-                        )))
+                        .ignore_then(
+                            group((
+                                TypeSpec::parser(),
+                                just(Token::Comma).ignore_then(literal_str().map(Cow::into_owned)),
+                                just(Token::Comma)
+                                    .ignore_then(
+                                        Expression::recursive_parser(cache.clone())
+                                            .separated_by(just(Token::Comma))
+                                            .allow_trailing()
+                                            .collect::<Vec<_>>(),
+                                    )
+                                    .or_not()
+                                    .map(Option::unwrap_or_default),
+                            ))
+                            .delimited_by(just(Token::LParen), just(Token::RParen)),
+                        )
                         .map(|(r#type, exp, args)| Self::Embed { r#type, exp, args }),
                 );
                 parser
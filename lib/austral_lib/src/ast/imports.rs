@@ -5,8 +5,8 @@ use serde::{Deserialize, Serialize};
 
 #[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
 pub struct ImportStmt {
-    module: Vec<Ident>,
-    symbols: Vec<ImportedSymbol>,
+    pub module: Vec<Ident>,
+    pub symbols: Vec<ImportedSymbol>,
 }
 
 impl ImportStmt {
@@ -32,8 +32,8 @@ impl ImportStmt {
 
 #[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
 pub struct ImportedSymbol {
-    import_name: Ident,
-    rename_into: Option<Ident>,
+    pub import_name: Ident,
+    pub rename_into: Option<Ident>,
 }
 
 impl ImportedSymbol {
@@ -8,13 +8,13 @@ pub type InstanceDef = InstanceBase<MethodDef>;
 
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 pub struct InstanceBase<TMethod> {
-    doc_string: Option<DocString>,
-    pragmas: Vec<Pragma>,
+    pub doc_string: Option<DocString>,
+    pub pragmas: Vec<Pragma>,
 
-    type_params: Vec<TypeParam>,
-    name: Ident,
-    arg: TypeSpec,
-    methods: Vec<TMethod>,
+    pub type_params: Vec<TypeParam>,
+    pub name: Ident,
+    pub arg: TypeSpec,
+    pub methods: Vec<TMethod>,
 }
 
 impl InstanceBase<MethodDecl> {
@@ -34,10 +34,13 @@ impl InstanceBase<MethodDecl> {
                 .map(Option::unwrap_or_default),
             just(Token::Instance).ignore_then(Ident::parser()),
             TypeSpec::parser().delimited_by(just(Token::LParen), just(Token::RParen)),
-            just(Token::Is)
-                .ignore_then(MethodDecl::parser().repeated().collect::<Vec<_>>())
-                .then_ignore(just(Token::End))
-                .then_ignore(just(Token::Semi)),
+            choice((
+                just(Token::Semi).to(Vec::new()),
+                just(Token::Is)
+                    .ignore_then(MethodDecl::parser().repeated().collect::<Vec<_>>())
+                    .then_ignore(just(Token::End))
+                    .then_ignore(just(Token::Semi)),
+            )),
         ))
         .map(
             |(doc_string, pragmas, type_params, name, arg, methods)| Self {
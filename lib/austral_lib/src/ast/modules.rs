@@ -1,6 +1,7 @@
 use super::{
     ConstantDecl, ConstantDef, DocString, Extra, FunctionDecl, FunctionDef, Ident, ImportStmt,
-    InstanceDecl, InstanceDef, RecordDecl, TypeClassDecl, TypeClassDef, TypeDecl, UnionDecl,
+    InstanceDecl, InstanceDef, Pragma, RecordDecl, TypeClassDecl, TypeClassDef, TypeDecl,
+    UnionDecl,
 };
 use crate::lexer::Token;
 use chumsky::prelude::*;
@@ -28,6 +29,7 @@ impl Module {
 pub struct ModuleBase<TModuleItem> {
     pub doc_string: Option<DocString>,
     pub imports: Vec<ImportStmt>,
+    pub pragmas: Vec<Pragma>,
     pub name: Ident,
     pub contents: Vec<TModuleItem>,
 }
@@ -37,6 +39,7 @@ impl ModuleBase<ModuleDeclItem> {
         group((
             DocString::parser().or_not(),
             ImportStmt::parser().repeated().collect(),
+            Pragma::parser().repeated().collect(),
             just(Token::Module)
                 .ignore_then(Ident::parser())
                 .then_ignore(just(Token::Is)),
@@ -46,9 +49,10 @@ impl ModuleBase<ModuleDeclItem> {
         .then_ignore(just(Token::Module))
         .then_ignore(just(Token::Period))
         .then_ignore(end())
-        .map(|(doc_string, imports, name, contents)| Self {
+        .map(|(doc_string, imports, pragmas, name, contents)| Self {
             doc_string,
             imports,
+            pragmas,
             name,
             contents,
         })
@@ -60,6 +64,7 @@ impl ModuleBase<ModuleDefItem> {
         group((
             DocString::parser().or_not(),
             ImportStmt::parser().repeated().collect(),
+            Pragma::parser().repeated().collect(),
             just(Token::Module)
                 .ignore_then(just(Token::Body))
                 .ignore_then(Ident::parser())
@@ -71,9 +76,10 @@ impl ModuleBase<ModuleDefItem> {
         .then_ignore(just(Token::Body))
         .then_ignore(just(Token::Period))
         .then_ignore(end())
-        .map(|(doc_string, imports, name, contents)| Self {
+        .map(|(doc_string, imports, pragmas, name, contents)| Self {
             doc_string,
             imports,
+            pragmas,
             name,
             contents,
         })
@@ -5,13 +5,13 @@ use serde::{Deserialize, Serialize};
 
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 pub struct RecordDecl {
-    doc_string: Option<DocString>,
-    pragmas: Vec<Pragma>,
+    pub doc_string: Option<DocString>,
+    pub pragmas: Vec<Pragma>,
 
-    name: Ident,
-    type_params: Vec<TypeParam>,
-    universe: Universe,
-    slots: Vec<Slot>,
+    pub name: Ident,
+    pub type_params: Vec<TypeParam>,
+    pub universe: Universe,
+    pub slots: Vec<Slot>,
 }
 
 impl RecordDecl {
@@ -29,12 +29,7 @@ impl RecordDecl {
                 .map(Option::unwrap_or_default),
             just(Token::Colon).ignore_then(Universe::parser()),
             just(Token::Is)
-                .ignore_then(
-                    Slot::parser()
-                        .then_ignore(just(Token::Semi))
-                        .repeated()
-                        .collect::<Vec<_>>(),
-                )
+                .ignore_then(Slot::parser().repeated().collect::<Vec<_>>())
                 .then_ignore(just(Token::End).then_ignore(just(Token::Semi))),
         ))
         .map(
@@ -52,10 +47,10 @@ impl RecordDecl {
 
 #[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
 pub struct Slot {
-    doc_string: Option<DocString>,
+    pub doc_string: Option<DocString>,
 
-    name: Ident,
-    r#type: TypeSpec,
+    pub name: Ident,
+    pub r#type: TypeSpec,
 }
 
 impl Slot {
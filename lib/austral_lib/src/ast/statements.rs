@@ -209,12 +209,14 @@ impl CaseWhen {
                         Binding::parser()
                             .separated_by(just(Token::Comma))
                             .allow_trailing()
-                            .collect::<Vec<_>>(),
+                            .collect::<Vec<_>>()
+                            .delimited_by(just(Token::LParen), just(Token::RParen))
+                            .or_not()
+                            .map(Option::unwrap_or_default),
                         just(Token::Do).ignore_then(
                             Statement::recursive_parser(cache)
                                 .repeated()
-                                .collect::<Vec<_>>()
-                                .delimited_by(just(Token::Is), just(Token::End)),
+                                .collect::<Vec<_>>(),
                         ),
                     ))
                     .map(|(ident, bindings, block)| Self {
@@ -259,8 +261,7 @@ impl ForStmt {
                         just(Token::Do).ignore_then(
                             Statement::recursive_parser(cache)
                                 .repeated()
-                                .collect::<Vec<_>>()
-                                .delimited_by(just(Token::Is), just(Token::End)),
+                                .collect::<Vec<_>>(),
                         ),
                     ))
                     .then_ignore(just(Token::End))
@@ -300,32 +301,38 @@ impl IfStmt {
                 let mut parser = Recursive::declare();
                 let _ = cache.if_stmt.set(parser.clone());
 
-                parser.define(
+                let statements = Statement::recursive_parser(cache.clone())
+                    .repeated()
+                    .collect::<Vec<_>>();
+
+                // `else if` chains share a single `end if;`, so each link is parsed as a nested
+                // `if` statement in the `else` branch of the previous one.
+                let branch = recursive(|branch| {
                     group((
-                        just(Token::If).ignore_then(Expression::parser()),
-                        just(Token::Then).ignore_then(
-                            Statement::recursive_parser(cache.clone())
-                                .repeated()
-                                .collect::<Vec<_>>()
-                                .delimited_by(just(Token::Is), just(Token::End)),
-                        ),
+                        Expression::parser(),
+                        just(Token::Then).ignore_then(statements.clone()),
                         just(Token::Else)
-                            .ignore_then(
-                                Statement::recursive_parser(cache.clone())
-                                    .repeated()
-                                    .collect::<Vec<_>>()
-                                    .delimited_by(just(Token::Is), just(Token::End)),
-                            )
+                            .ignore_then(choice((
+                                just(Token::If)
+                                    .ignore_then(branch)
+                                    .map(|x| vec![Statement::If(x)]),
+                                statements,
+                            )))
                             .or_not(),
                     ))
-                    .then_ignore(just(Token::End))
-                    .then_ignore(just(Token::If))
-                    .then_ignore(just(Token::Semi))
                     .map(|(value, contents, r#else)| Self {
                         value,
                         contents,
                         r#else,
-                    }),
+                    })
+                });
+
+                parser.define(
+                    just(Token::If)
+                        .ignore_then(branch)
+                        .then_ignore(just(Token::End))
+                        .then_ignore(just(Token::If))
+                        .then_ignore(just(Token::Semi)),
                 );
                 parser
             }
@@ -407,8 +414,7 @@ impl WhileStmt {
                         .then(
                             Statement::recursive_parser(cache.clone())
                                 .repeated()
-                                .collect::<Vec<_>>()
-                                .delimited_by(just(Token::Is), just(Token::End)),
+                                .collect::<Vec<_>>(),
                         )
                         .then_ignore(just(Token::End))
                         .then_ignore(just(Token::While))
@@ -564,4 +570,83 @@ mod statement_parser_tests {
 
         assert_eq!(actual, expected);
     }
+
+    #[test]
+    fn test_if_stmt() {
+        let input = vec![
+            Token::If,
+            Token::Ident("a"),
+            Token::Then,
+            Token::Ident("b"),
+            Token::Semi,
+            Token::Else,
+            Token::If,
+            Token::Ident("c"),
+            Token::Then,
+            Token::Ident("d"),
+            Token::Semi,
+            Token::Else,
+            Token::Ident("e"),
+            Token::Semi,
+            Token::End,
+            Token::If,
+            Token::Semi,
+        ];
+
+        let path = |name| {
+            Expression::Atomic(AtomicExpr::Path(PathExpr {
+                first: Ident::new(name),
+                extra: vec![],
+            }))
+        };
+        let expected = Statement::If(IfStmt {
+            value: path("a"),
+            contents: vec![Statement::Discard(path("b"))],
+            r#else: Some(vec![Statement::If(IfStmt {
+                value: path("c"),
+                contents: vec![Statement::Discard(path("d"))],
+                r#else: Some(vec![Statement::Discard(path("e"))]),
+            })]),
+        });
+
+        let actual = Statement::parser().parse(&input).unwrap();
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_for_stmt() {
+        let input = vec![
+            Token::For,
+            Token::Ident("i"),
+            Token::From,
+            Token::Decimal(0),
+            Token::To,
+            Token::Decimal(10),
+            Token::Do,
+            Token::Ident("foo"),
+            Token::Semi,
+            Token::End,
+            Token::For,
+            Token::Semi,
+        ];
+
+        let expected = Statement::For(ForStmt {
+            name: Ident::new("i"),
+            range: Range {
+                start: Expression::Atomic(AtomicExpr::ConstInt(0)),
+                end: Expression::Atomic(AtomicExpr::ConstInt(10)),
+            },
+            contents: vec![Statement::Discard(Expression::Atomic(AtomicExpr::Path(
+                PathExpr {
+                    first: Ident::new("foo"),
+                    extra: vec![],
+                },
+            )))],
+        });
+
+        let actual = Statement::parser().parse(&input).unwrap();
+
+        assert_eq!(actual, expected);
+    }
 }
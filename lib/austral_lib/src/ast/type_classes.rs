@@ -8,12 +8,12 @@ pub type TypeClassDef = TypeClassBase<MethodDef>;
 
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 pub struct TypeClassBase<TMethod> {
-    doc_string: Option<DocString>,
-    pragmas: Vec<Pragma>,
+    pub doc_string: Option<DocString>,
+    pub pragmas: Vec<Pragma>,
 
-    name: Ident,
-    type_param: TypeParam,
-    methods: Vec<TMethod>,
+    pub name: Ident,
+    pub type_param: TypeParam,
+    pub methods: Vec<TMethod>,
 }
 
 impl TypeClassBase<MethodDecl> {
@@ -2,14 +2,15 @@ use super::{DocString, Extra, Ident, Pragma, Universe};
 use crate::lexer::Token;
 use chumsky::prelude::*;
 use serde::{Deserialize, Serialize};
+use std::fmt::{self, Display, Formatter};
 
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 pub struct TypeDecl {
-    doc_string: Option<DocString>,
-    pragmas: Vec<Pragma>,
+    pub doc_string: Option<DocString>,
+    pub pragmas: Vec<Pragma>,
 
-    name: Ident,
-    universe: Universe,
+    pub name: Ident,
+    pub universe: Universe,
 }
 
 impl TypeDecl {
@@ -116,3 +117,29 @@ impl TypeSpec {
         })
     }
 }
+
+impl Display for TypeSpec {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let write_pair = |f: &mut Formatter<'_>, prefix: &str, lhs: &Self, rhs: &Self| {
+            write!(f, "{prefix}[{lhs}, {rhs}]")
+        };
+
+        match self {
+            Self::Simple { name } => write!(f, "{name}"),
+            Self::Generic { name, type_params } => {
+                write!(f, "{name}[")?;
+                for (i, param) in type_params.iter().enumerate() {
+                    if i != 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "{param}")?;
+                }
+                f.write_str("]")
+            }
+            Self::BorrowRead { lhs, rhs } => write_pair(f, "&", lhs, rhs),
+            Self::BorrowWrite { lhs, rhs } => write_pair(f, "&!", lhs, rhs),
+            Self::SpanRead { lhs, rhs } => write_pair(f, "Span", lhs, rhs),
+            Self::SpanWrite { lhs, rhs } => write_pair(f, "Span!", lhs, rhs),
+        }
+    }
+}
@@ -1,16 +1,17 @@
-use super::{DocString, Extra, Ident, Pragma, Slot, TypeParam};
+use super::{DocString, Extra, Ident, Pragma, Slot, TypeParam, Universe};
 use crate::lexer::Token;
 use chumsky::prelude::*;
 use serde::{Deserialize, Serialize};
 
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 pub struct UnionDecl {
-    doc_string: Option<DocString>,
-    pragmas: Vec<Pragma>,
+    pub doc_string: Option<DocString>,
+    pub pragmas: Vec<Pragma>,
 
-    name: Ident,
-    type_params: Vec<TypeParam>,
-    cases: Vec<Case>,
+    pub name: Ident,
+    pub type_params: Vec<TypeParam>,
+    pub universe: Universe,
+    pub cases: Vec<Case>,
 }
 
 impl UnionDecl {
@@ -26,27 +27,31 @@ impl UnionDecl {
                 .delimited_by(just(Token::LBracket), just(Token::RBracket))
                 .or_not()
                 .map(Option::unwrap_or_default),
+            just(Token::Colon).ignore_then(Universe::parser()),
             just(Token::Is)
                 .ignore_then(Case::parser().repeated().collect::<Vec<_>>())
                 .then_ignore(just(Token::End))
                 .then_ignore(just(Token::Semi)),
         ))
-        .map(|(doc_string, pragmas, name, type_params, cases)| Self {
-            doc_string,
-            pragmas,
-            name,
-            type_params,
-            cases,
-        })
+        .map(
+            |(doc_string, pragmas, name, type_params, universe, cases)| Self {
+                doc_string,
+                pragmas,
+                name,
+                type_params,
+                universe,
+                cases,
+            },
+        )
     }
 }
 
 #[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
 pub struct Case {
-    doc_string: Option<DocString>,
+    pub doc_string: Option<DocString>,
 
-    name: Ident,
-    fields: Vec<Slot>,
+    pub name: Ident,
+    pub fields: Vec<Slot>,
 }
 
 impl Case {
@@ -54,12 +59,11 @@ impl Case {
         group((
             DocString::parser().or_not(),
             just(Token::Case).ignore_then(Ident::parser()),
-            just(Token::Is)
-                .ignore_then(Slot::parser().repeated().collect::<Vec<_>>())
-                .or_not()
-                .map(Option::unwrap_or_default),
+            choice((
+                just(Token::Semi).to(Vec::new()),
+                just(Token::Is).ignore_then(Slot::parser().repeated().at_least(1).collect()),
+            )),
         ))
-        .then_ignore(just(Token::Semi))
         .map(|(doc_string, name, fields)| Self {
             doc_string,
             name,
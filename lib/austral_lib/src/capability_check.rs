@@ -0,0 +1,463 @@
+use crate::{
+    ast::{
+        ArithExpr, AtomicExpr, Binding, CmpExpr, CompoundExpr, Expression, FnCallArgs, FunctionDef,
+        Ident, IntrinExpr, LetStmtTarget, LogicExpr, ModuleDef, ModuleDefItem, Param, PathExpr,
+        PathSegment, Pragma, Statement, TypeSpec,
+    },
+    error::{CapabilityError as Error, CapabilityResult as Result},
+    pragma_check::{FOREIGN_IMPORT, UNSAFE_MODULE},
+};
+use std::collections::{HashMap, HashSet};
+
+/// Modules which give access to unsafe operations, and can therefore only be imported by unsafe
+/// modules.
+const UNSAFE_MODULES: &[&[&str]] = &[&["Austral", "Memory"]];
+
+/// Type constructors whose values can't be dereferenced outside unsafe modules.
+const POINTER_TYPES: &[&str] = &["Pointer", "Address"];
+
+/// The functions of the unsafe memory API, which are in scope of every module.
+pub const MEMORY_FUNCTIONS: &[&str] = &[
+    "nullPointer",
+    "nullCheck",
+    "load",
+    "store",
+    "positiveOffset",
+    "negativeOffset",
+];
+
+/// The names in scope of a function body.
+#[derive(Clone)]
+struct Scope<'a> {
+    /// The functions of the module, which shadow those of the memory API.
+    functions: &'a HashSet<&'a str>,
+    /// The declared types of the variables in scope, which tell pointer dereferences apart from
+    /// reference dereferences.
+    locals: HashMap<&'a str, &'a TypeSpec>,
+}
+
+impl<'a> Scope<'a> {
+    fn new(functions: &'a HashSet<&'a str>) -> Self {
+        Self {
+            functions,
+            locals: HashMap::new(),
+        }
+    }
+}
+
+/// An operation which is only allowed inside unsafe modules.
+#[derive(Debug, PartialEq)]
+enum UnsafeOp {
+    Embed,
+    PointerAccess,
+}
+
+/// Check that the module only uses unsafe features if it's marked with `pragma Unsafe_Module`,
+/// and that its entrypoint (if any) has a valid signature.
+pub fn check_module_def(module: &ModuleDef) -> Result<()> {
+    let is_unsafe = is_unsafe_module(&module.pragmas);
+
+    if !is_unsafe {
+        for import in &module.imports {
+            let path = import
+                .module
+                .iter()
+                .map(|x| x.name.as_str())
+                .collect::<Vec<_>>();
+            if UNSAFE_MODULES.contains(&path.as_slice()) {
+                return Err(Error::UnsafeImport(path.join(".")));
+            }
+        }
+    }
+
+    let functions = module
+        .contents
+        .iter()
+        .filter_map(|item| match item {
+            ModuleDefItem::Function(def) => Some(def.name.name.as_str()),
+            _ => None,
+        })
+        .collect::<HashSet<_>>();
+
+    for item in &module.contents {
+        match item {
+            ModuleDefItem::Function(def) => {
+                if def.name.name == "main" {
+                    check_main(def)?;
+                }
+                if !is_unsafe {
+                    if def.pragmas.iter().any(|x| x.name.name == FOREIGN_IMPORT) {
+                        return Err(Error::ForeignImport(def.name.name.clone()));
+                    }
+                    check_body(&functions, &def.name, &def.params, &def.body)?;
+                }
+            }
+            ModuleDefItem::Instance(def) if !is_unsafe => {
+                for method in &def.methods {
+                    check_body(&functions, &method.name, &method.params, &method.body)?;
+                }
+            }
+            ModuleDefItem::TypeClass(def) if !is_unsafe => {
+                for method in &def.methods {
+                    check_body(&functions, &method.name, &method.params, &method.body)?;
+                }
+            }
+            ModuleDefItem::Constant(def) if !is_unsafe => {
+                if let Some(op) = find_in_expression(&Scope::new(&functions), &def.value) {
+                    return Err(make_error(&def.name, op));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
+
+/// Return whether a module's pragmas mark it as unsafe.
+pub fn is_unsafe_module(pragmas: &[Pragma]) -> bool {
+    pragmas.iter().any(|x| x.name.name == UNSAFE_MODULE)
+}
+
+/// The entrypoint either takes no arguments or takes the root capability, and always returns an
+/// exit code.
+fn check_main(def: &FunctionDef) -> Result<()> {
+    let is_named = |r#type: &TypeSpec, expected: &str| matches!(r#type, TypeSpec::Simple { name } if name.name == expected);
+
+    let valid_params = match def.params.as_slice() {
+        [] => true,
+        [param] => is_named(&param.r#type, "RootCapability"),
+        _ => false,
+    };
+
+    if !def.type_params.is_empty() || !valid_params || !is_named(&def.ret_type, "ExitCode") {
+        return Err(Error::InvalidMainSignature);
+    }
+
+    Ok(())
+}
+
+fn check_body(
+    functions: &HashSet<&str>,
+    name: &Ident,
+    params: &[Param],
+    body: &[Statement],
+) -> Result<()> {
+    let mut scope = Scope::new(functions);
+    for param in params {
+        scope.locals.insert(&param.name.name, &param.r#type);
+    }
+    match find_in_statements(&scope, body) {
+        Some(op) => Err(make_error(name, op)),
+        None => Ok(()),
+    }
+}
+
+fn make_error(name: &Ident, op: UnsafeOp) -> Error {
+    match op {
+        UnsafeOp::Embed => Error::Embed(name.name.clone()),
+        UnsafeOp::PointerAccess => Error::PointerAccess(name.name.clone()),
+    }
+}
+
+fn find_in_statements<'a>(scope: &Scope<'a>, stmts: &'a [Statement]) -> Option<UnsafeOp> {
+    let mut scope = scope.clone();
+    stmts.iter().find_map(|stmt| {
+        let op = find_in_statement(&scope, stmt);
+        if let Statement::Let(stmt) = stmt {
+            match &stmt.target {
+                LetStmtTarget::Simple { name, r#type } => {
+                    scope.locals.insert(&name.name, r#type);
+                }
+                LetStmtTarget::Destructure(bindings) => declare_bindings(&mut scope, bindings),
+            }
+        }
+        op
+    })
+}
+
+fn find_in_statement<'a>(scope: &Scope<'a>, stmt: &'a Statement) -> Option<UnsafeOp> {
+    match stmt {
+        Statement::Assign(stmt) => {
+            find_in_path(scope, &stmt.target).or_else(|| find_in_expression(scope, &stmt.value))
+        }
+        Statement::Borrow(stmt) => {
+            let mut scope = scope.clone();
+            scope.locals.remove(stmt.name.name.as_str());
+            find_in_statements(&scope, &stmt.body)
+        }
+        Statement::Case(stmt) => find_in_expression(scope, &stmt.value).or_else(|| {
+            stmt.variants.iter().find_map(|variant| {
+                let mut scope = scope.clone();
+                declare_bindings(&mut scope, &variant.bindings);
+                find_in_statements(&scope, &variant.block)
+            })
+        }),
+        Statement::Discard(expr) | Statement::Return(expr) => find_in_expression(scope, expr),
+        Statement::For(stmt) => find_in_expression(scope, &stmt.range.start)
+            .or_else(|| find_in_expression(scope, &stmt.range.end))
+            .or_else(|| {
+                let mut scope = scope.clone();
+                scope.locals.remove(stmt.name.name.as_str());
+                find_in_statements(&scope, &stmt.contents)
+            }),
+        Statement::If(stmt) => find_in_expression(scope, &stmt.value)
+            .or_else(|| find_in_statements(scope, &stmt.contents))
+            .or_else(|| {
+                stmt.r#else
+                    .as_deref()
+                    .and_then(|stmts| find_in_statements(scope, stmts))
+            }),
+        Statement::Let(stmt) => find_in_expression(scope, &stmt.value),
+        Statement::While(stmt) => find_in_expression(scope, &stmt.value)
+            .or_else(|| find_in_statements(scope, &stmt.contents)),
+    }
+}
+
+fn declare_bindings<'a>(scope: &mut Scope<'a>, bindings: &'a [Binding]) {
+    for binding in bindings {
+        let name = binding.rename.as_ref().unwrap_or(&binding.name);
+        scope.locals.insert(&name.name, &binding.r#type);
+    }
+}
+
+fn find_in_expression(scope: &Scope, expr: &Expression) -> Option<UnsafeOp> {
+    let find_in_atomic = |expr| find_in_atomic(scope, expr);
+    match expr {
+        Expression::Atomic(expr) => find_in_atomic(expr),
+        Expression::Compound(expr) => match expr {
+            CompoundExpr::Cmp(
+                CmpExpr::Eq(lhs, rhs)
+                | CmpExpr::NotEq(lhs, rhs)
+                | CmpExpr::Lt(lhs, rhs)
+                | CmpExpr::LtEq(lhs, rhs)
+                | CmpExpr::Gt(lhs, rhs)
+                | CmpExpr::GtEq(lhs, rhs),
+            )
+            | CompoundExpr::Logic(LogicExpr::And(lhs, rhs) | LogicExpr::Or(lhs, rhs))
+            | CompoundExpr::Arith(
+                ArithExpr::Add(lhs, rhs)
+                | ArithExpr::Sub(lhs, rhs)
+                | ArithExpr::Mul(lhs, rhs)
+                | ArithExpr::Div(lhs, rhs),
+            ) => find_in_atomic(lhs).or_else(|| find_in_atomic(rhs)),
+            CompoundExpr::Logic(LogicExpr::Not(expr))
+            | CompoundExpr::Arith(ArithExpr::Neg(expr)) => find_in_atomic(expr),
+            CompoundExpr::Select(expr) => find_in_expression(scope, &expr.condition)
+                .or_else(|| find_in_expression(scope, &expr.value_true))
+                .or_else(|| find_in_expression(scope, &expr.value_false)),
+            CompoundExpr::Cast(expr) => find_in_atomic(&expr.value),
+        },
+    }
+}
+
+fn find_in_atomic(scope: &Scope, expr: &AtomicExpr) -> Option<UnsafeOp> {
+    match expr {
+        AtomicExpr::FnCall(expr) if is_memory_function(scope, &expr.target) => {
+            Some(UnsafeOp::PointerAccess)
+        }
+        AtomicExpr::FnCall(expr) => match &expr.args {
+            FnCallArgs::Empty => None,
+            FnCallArgs::Positional(args) => {
+                args.iter().find_map(|arg| find_in_expression(scope, arg))
+            }
+            FnCallArgs::Named(args) => args.values().find_map(|arg| find_in_expression(scope, arg)),
+        },
+        AtomicExpr::Path(expr) | AtomicExpr::RefPath(expr) => find_in_path(scope, expr),
+        AtomicExpr::Paren(expr) => find_in_expression(scope, expr),
+        AtomicExpr::Intrinsic(IntrinExpr::Embed { .. }) => Some(UnsafeOp::Embed),
+        AtomicExpr::Deref(expr) if is_pointer(scope, expr) => Some(UnsafeOp::PointerAccess),
+        AtomicExpr::Deref(expr) => find_in_atomic(scope, expr),
+        AtomicExpr::ConstNil
+        | AtomicExpr::ConstBool(_)
+        | AtomicExpr::ConstChar(_)
+        | AtomicExpr::ConstInt(_)
+        | AtomicExpr::ConstFloat(_)
+        | AtomicExpr::ConstStr(_)
+        | AtomicExpr::SizeOf(_)
+        | AtomicExpr::BorrowRead(_)
+        | AtomicExpr::BorrowWrite(_)
+        | AtomicExpr::ReBorrow(_) => None,
+    }
+}
+
+fn find_in_path(scope: &Scope, expr: &PathExpr) -> Option<UnsafeOp> {
+    expr.extra.iter().find_map(|segment| match segment {
+        PathSegment::SlotAccess(_) => None,
+        PathSegment::PtrSlotAccess(_) => Some(UnsafeOp::PointerAccess),
+        PathSegment::ArrayIndex(expr) => find_in_expression(scope, expr),
+    })
+}
+
+/// Return whether an expression is a variable declared as a `Pointer` or an `Address`.
+fn is_pointer(scope: &Scope, expr: &AtomicExpr) -> bool {
+    let AtomicExpr::Path(PathExpr { first, extra }) = expr else {
+        return false;
+    };
+
+    extra.is_empty()
+        && matches!(
+            scope.locals.get(first.name.as_str()),
+            Some(TypeSpec::Generic { name, .. }) if POINTER_TYPES.contains(&name.name.as_str())
+        )
+}
+
+/// Return whether a call goes to the memory API rather than to a function of the module.
+fn is_memory_function(scope: &Scope, target: &Ident) -> bool {
+    let name = target.name.as_str();
+    MEMORY_FUNCTIONS.contains(&name) && !scope.functions.contains(name)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::lexer::lex;
+    use chumsky::Parser;
+
+    fn check(source: &str) -> Result<()> {
+        let tokens = lex(source)
+            .map(|(token, _)| token.unwrap())
+            .collect::<Vec<_>>();
+        let module = ModuleDef::parser().parse(&tokens).into_result().unwrap();
+
+        check_module_def(&module)
+    }
+
+    #[test]
+    fn unsafe_module() {
+        let source = include_str!("../../../programs/suites/001-trivial/002-embed/Test.aum");
+        assert_eq!(check(source), Ok(()));
+
+        let source = source.replacen("pragma Unsafe_Module;", "", 1);
+        assert_eq!(
+            check(&source),
+            Err(Error::ForeignImport("Put_Character".to_string()))
+        );
+    }
+
+    #[test]
+    fn embed_outside_unsafe_module() {
+        let result = check(
+            r#"module body Test is
+                function main(): ExitCode is
+                    let c: Nat8 := @embed(Nat8, "$1 + $2", 90, 7);
+                    return ExitSuccess();
+                end;
+            end module body."#,
+        );
+        assert_eq!(result, Err(Error::Embed("main".to_string())));
+    }
+
+    #[test]
+    fn pointer_access_outside_unsafe_module() {
+        let result = check(
+            "module body Test is
+                function f(): Unit is
+                    p->x := 1;
+                    return nil;
+                end;
+            end module body.",
+        );
+        assert_eq!(result, Err(Error::PointerAccess("f".to_string())));
+    }
+
+    #[test]
+    fn pointer_dereference_outside_unsafe_module() {
+        let result = check(
+            "module body Test is
+                function f(p: Pointer[Int32], r: &[Int32, Static]): Int32 is
+                    let x: Int32 := !r;
+                    return !p;
+                end;
+            end module body.",
+        );
+        assert_eq!(result, Err(Error::PointerAccess("f".to_string())));
+
+        let result = check(
+            "module body Test is
+                function f(r: &[Int32, Static]): Int32 is
+                    let a: Address[Int32] := g();
+                    let x: Int32 := !r;
+                    return !a;
+                end;
+            end module body.",
+        );
+        assert_eq!(result, Err(Error::PointerAccess("f".to_string())));
+
+        let result = check(
+            "module body Test is
+                function f(r: &[Int32, Static]): Int32 is
+                    return !r;
+                end;
+            end module body.",
+        );
+        assert_eq!(result, Ok(()));
+    }
+
+    #[test]
+    fn memory_functions_outside_unsafe_module() {
+        let result = check(
+            "module body Test is
+                function f(p: Pointer[Int32]): Int32 is
+                    return load(positiveOffset(p, 1));
+                end;
+            end module body.",
+        );
+        assert_eq!(result, Err(Error::PointerAccess("f".to_string())));
+
+        // A function of the module shadows the one of the memory API.
+        let result = check(
+            "module body Test is
+                function load(x: Int32): Int32 is
+                    return x;
+                end;
+
+                function f(): Int32 is
+                    return load(1);
+                end;
+            end module body.",
+        );
+        assert_eq!(result, Ok(()));
+    }
+
+    #[test]
+    fn unsafe_import() {
+        let result = check(
+            "import Austral.Memory (Pointer);
+
+            module body Test is
+            end module body.",
+        );
+        assert_eq!(
+            result,
+            Err(Error::UnsafeImport("Austral.Memory".to_string()))
+        );
+    }
+
+    #[test]
+    fn main_signature() {
+        let source = include_str!("../../../programs/suites/001-trivial/007-root-cap/Test.aum");
+        assert_eq!(check(source), Ok(()));
+
+        let source = include_str!("../../../programs/suites/001-trivial/001-null-program/Test.aum");
+        assert_eq!(check(source), Ok(()));
+
+        let result = check(
+            "module body Test is
+                function main(x: Int32): ExitCode is
+                    return ExitSuccess();
+                end;
+            end module body.",
+        );
+        assert_eq!(result, Err(Error::InvalidMainSignature));
+
+        let result = check(
+            "module body Test is
+                function main(): Int32 is
+                    return 0;
+                end;
+            end module body.",
+        );
+        assert_eq!(result, Err(Error::InvalidMainSignature));
+    }
+}
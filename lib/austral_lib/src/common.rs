@@ -17,17 +17,18 @@ impl Identifier {
     }
 }
 
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct QIdent {
     pub source: ModuleName,
     pub original: Identifier,
     pub local: Identifier,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct ModuleName(pub String);
 
 #[derive(Clone, Debug)]
-pub struct DeclId(i32);
+pub struct DeclId(pub i32);
 
 pub enum ComparisonOperator {
     Equal,
@@ -0,0 +1,547 @@
+use super::{
+    expressions::{
+        build_bool, build_expr, build_integer, build_string, build_union_value, infer_type,
+        is_signed, pointee, process_expr,
+    },
+    operations::{
+        build_call, build_element_ptr, build_extract_value, build_llvm_load, build_llvm_store,
+    },
+    runtime::{build_check, build_write_str, STDOUT},
+    statements::{FunctionContext, Local},
+    types::{build_type, string_type, union_decl},
+    BuildContext,
+};
+use crate::{
+    ast::{AtomicExpr, Expression, FnCallArgs, FnCallExpr, Ident, TypeSpec},
+    capability_check::MEMORY_FUNCTIONS,
+    error::{CodegenError, CodegenResult},
+    r#type::Ty,
+    type_system::primitive_type,
+};
+use melior::{
+    dialect::{
+        arith::{self, CmpfPredicate, CmpiPredicate},
+        llvm, scf,
+    },
+    ir::{
+        attribute::FloatAttribute, operation::OperationBuilder, r#type::IntegerType, Block,
+        Location, Region, Type, Value, ValueLike,
+    },
+};
+use std::collections::HashMap;
+
+/// The functions of the unsafe memory API, unless a function of the same name is in scope.
+pub(super) fn is_memory_builtin(ctx: &BuildContext<'_>, name: &str) -> bool {
+    MEMORY_FUNCTIONS.contains(&name) && !ctx.functions.contains_key(name)
+}
+
+pub(super) fn build_memory_builtin<'c, 'b>(
+    ctx: &'c BuildContext<'c>,
+    function: &FunctionContext<'c, 'b>,
+    block: &'b Block<'c>,
+    expr: &FnCallExpr,
+    target_type: Option<Type<'c>>,
+    locals: &HashMap<&str, Local<'c, 'b>>,
+) -> CodegenResult<Value<'c, 'b>> {
+    let name = expr.target.name.as_str();
+    let args = positional_arguments(expr)?;
+
+    let [pointer, rest @ ..] = args else {
+        if name != "nullPointer" {
+            return Err(CodegenError::ArgumentCount(name.to_string()));
+        }
+
+        // An address of the expected type, which is null.
+        return Ok(block
+            .append_operation(
+                OperationBuilder::new("llvm.mlir.zero", Location::unknown(ctx))
+                    .add_results(&[llvm::r#type::opaque_pointer(ctx)])
+                    .build()
+                    .unwrap(),
+            )
+            .result(0)
+            .unwrap()
+            .into());
+    };
+    let pointer_type = infer_type(ctx, pointer, locals).ok_or(CodegenError::NotAPointer)?;
+    let pointee = pointee(&pointer_type).ok_or(CodegenError::NotAPointer)?;
+    let pointer = process_expr(pointer);
+    let pointer = build_expr(ctx, function, block, &pointer, None, locals)?;
+
+    let value = match (name, rest) {
+        ("load", []) => build_llvm_load(ctx, block, pointer, build_type(ctx, pointee)?),
+        ("store", [value]) => {
+            let value = process_expr(value);
+            let value = build_expr(
+                ctx,
+                function,
+                block,
+                &value,
+                Some(build_type(ctx, pointee)?),
+                locals,
+            )?;
+            build_llvm_store(ctx, block, value, pointer);
+            build_bool(ctx, block, false)
+        }
+        // `Option[Pointer[T]]` is represented by a pointer which is null for `None`, just like
+        // an address.
+        ("nullCheck", []) => {
+            debug_assert!(target_type.is_none_or(|x| x == pointer.r#type()));
+            pointer
+        }
+        ("positiveOffset" | "negativeOffset", [offset]) => {
+            let index_type = IntegerType::new(ctx, 64).into();
+            let offset = process_expr(offset);
+            let mut offset = build_expr(ctx, function, block, &offset, Some(index_type), locals)?;
+            if name == "negativeOffset" {
+                let k0 = build_integer(ctx, block, 0, index_type);
+                offset = block
+                    .append_operation(arith::subi(k0, offset, Location::unknown(ctx)))
+                    .result(0)
+                    .unwrap()
+                    .into();
+            }
+            build_element_ptr(ctx, block, pointer, build_type(ctx, pointee)?, offset)
+        }
+        _ => return Err(CodegenError::ArgumentCount(name.to_string())),
+    };
+
+    Ok(value)
+}
+
+/// The arguments of a builtin, which are all positional.
+fn positional_arguments(expr: &FnCallExpr) -> CodegenResult<&[Expression]> {
+    match &expr.args {
+        FnCallArgs::Empty => Ok(&[][..]),
+        FnCallArgs::Positional(args) => Ok(args.as_slice()),
+        FnCallArgs::Named(_) => Err(CodegenError::PositionalArguments(expr.target.name.clone())),
+    }
+}
+
+/// The target type of a conversion function, like `toNat8`, unless a function of the same name is
+/// in scope.
+pub(super) fn conversion_target(ctx: &BuildContext<'_>, name: &str) -> Option<TypeSpec> {
+    if ctx.functions.contains_key(name) {
+        return None;
+    }
+
+    let type_name = name.strip_prefix("to")?;
+    match primitive_type(type_name)? {
+        Ty::Integer(..) | Ty::SingleFloat | Ty::DoubleFloat => Some(TypeSpec::Simple {
+            name: Ident::new(type_name),
+        }),
+        _ => None,
+    }
+}
+
+pub(super) fn option_type(r#type: TypeSpec) -> TypeSpec {
+    TypeSpec::Generic {
+        name: Ident::new("Option"),
+        type_params: vec![r#type],
+    }
+}
+
+/// Convert a number to another numeric type, returning `None` when the value has no
+/// representation in the target type. Floats are truncated towards zero when converted to
+/// integers.
+pub(super) fn build_conversion<'c, 'b>(
+    ctx: &'c BuildContext<'c>,
+    function: &FunctionContext<'c, 'b>,
+    block: &'b Block<'c>,
+    value: Value<'c, 'b>,
+    source: Option<&TypeSpec>,
+    target: &TypeSpec,
+) -> CodegenResult<Value<'c, 'b>> {
+    let location = Location::unknown(ctx);
+    let append = |operation| -> Value<'c, 'b> {
+        block.append_operation(operation).result(0).unwrap().into()
+    };
+    let target_type = build_type(ctx, target)?;
+    let source_signed = is_signed(source);
+    let target_signed = is_signed(Some(target));
+
+    let (fits, result) = match (
+        IntegerType::try_from(value.r#type()),
+        IntegerType::try_from(target_type),
+    ) {
+        (Ok(source_int), Ok(target_int)) => {
+            // Compare in the wider of the two types, where both the value and the bounds of the
+            // target are exact.
+            let (source_bits, target_bits) = (source_int.width(), target_int.width());
+            let wide_type = IntegerType::new(ctx, source_bits.max(target_bits)).into();
+            let wide = match (source_bits < target_bits, source_signed) {
+                (true, true) => append(arith::extsi(value, wide_type, location)),
+                (true, false) => append(arith::extui(value, wide_type, location)),
+                (false, _) => value,
+            };
+
+            let (min, max) = if target_signed {
+                (
+                    i64::MIN >> (64 - target_bits),
+                    i64::MAX >> (64 - target_bits),
+                )
+            } else {
+                (0, (u64::MAX >> (64 - target_bits)) as i64)
+            };
+            let fits_below = if source_signed {
+                let k_min = build_integer(ctx, block, min, wide_type);
+                append(arith::cmpi(ctx, CmpiPredicate::Sge, wide, k_min, location))
+            } else {
+                build_bool(ctx, block, true)
+            };
+            let fits_above = if target_bits < source_bits
+                || (target_bits == source_bits && !source_signed && target_signed)
+            {
+                let k_max = build_integer(ctx, block, max, wide_type);
+                let predicate = if source_signed {
+                    CmpiPredicate::Sle
+                } else {
+                    CmpiPredicate::Ule
+                };
+                append(arith::cmpi(ctx, predicate, wide, k_max, location))
+            } else {
+                build_bool(ctx, block, true)
+            };
+
+            let result = if target_bits < source_bits {
+                append(arith::trunci(wide, target_type, location))
+            } else {
+                wide
+            };
+            (
+                append(arith::andi(fits_below, fits_above, location)),
+                result,
+            )
+        }
+        (Ok(_), Err(_)) => {
+            let result = if source_signed {
+                append(arith::sitofp(value, target_type, location))
+            } else {
+                append(arith::uitofp(value, target_type, location))
+            };
+            (build_bool(ctx, block, true), result)
+        }
+        (Err(_), Ok(target_int)) => {
+            // The bounds are powers of two, which are exact in either float type. NaN fails the
+            // ordered comparisons.
+            let bits = target_int.width() as i32;
+            let float = |x: f64| -> Value<'c, 'b> {
+                append(arith::constant(
+                    ctx,
+                    FloatAttribute::new(ctx, x, value.r#type()).into(),
+                    location,
+                ))
+            };
+            let (lower, upper, result) = if target_signed {
+                (
+                    (CmpfPredicate::Oge, -(2f64.powi(bits - 1))),
+                    2f64.powi(bits - 1),
+                    append(arith::fptosi(value, target_type, location)),
+                )
+            } else {
+                (
+                    (CmpfPredicate::Ogt, -1.0),
+                    2f64.powi(bits),
+                    append(arith::fptoui(value, target_type, location)),
+                )
+            };
+            let fits_below = append(arith::cmpf(ctx, lower.0, value, float(lower.1), location));
+            let fits_above = append(arith::cmpf(
+                ctx,
+                CmpfPredicate::Olt,
+                value,
+                float(upper),
+                location,
+            ));
+            (
+                append(arith::andi(fits_below, fits_above, location)),
+                result,
+            )
+        }
+        (Err(_), Err(_)) if value.r#type() == target_type => (build_bool(ctx, block, true), value),
+        (Err(_), Err(_)) if target_type == Type::float64(ctx) => (
+            build_bool(ctx, block, true),
+            append(arith::extf(value, target_type, location)),
+        ),
+        (Err(_), Err(_)) => {
+            // Narrowing fails for finite values beyond the range of `Float32`, but infinities
+            // and NaN carry over.
+            let float = |x: f64| -> Value<'c, 'b> {
+                append(arith::constant(
+                    ctx,
+                    FloatAttribute::new(ctx, x, value.r#type()).into(),
+                    location,
+                ))
+            };
+            let max = f64::from(f32::MAX);
+            let too_large = append(arith::andi(
+                append(arith::cmpf(
+                    ctx,
+                    CmpfPredicate::Ogt,
+                    value,
+                    float(max),
+                    location,
+                )),
+                append(arith::cmpf(
+                    ctx,
+                    CmpfPredicate::Olt,
+                    value,
+                    float(f64::INFINITY),
+                    location,
+                )),
+                location,
+            ));
+            let too_small = append(arith::andi(
+                append(arith::cmpf(
+                    ctx,
+                    CmpfPredicate::Olt,
+                    value,
+                    float(-max),
+                    location,
+                )),
+                append(arith::cmpf(
+                    ctx,
+                    CmpfPredicate::Ogt,
+                    value,
+                    float(f64::NEG_INFINITY),
+                    location,
+                )),
+                location,
+            ));
+            let overflows = append(arith::ori(too_large, too_small, location));
+            let k1 = build_bool(ctx, block, true);
+            (
+                append(arith::xori(overflows, k1, location)),
+                append(arith::truncf(value, target_type, location)),
+            )
+        }
+    };
+
+    let option = option_type(target.clone());
+    let option_type = build_type(ctx, &option)?;
+    let (decl, _) = union_decl(ctx, &option).unwrap();
+    let build_case = |case: &str, values: Vec<Value<'c, 'b>>| {
+        let case = decl.cases.iter().position(|x| x.name.name == case).unwrap();
+        let case_block = Block::new(&[]);
+        let value = build_union_value(ctx, function, &case_block, decl, case, values, option_type);
+        case_block.append_operation(scf::r#yield(&[value], location));
+
+        let region = Region::new();
+        region.append_block(case_block);
+        region
+    };
+
+    Ok(block
+        .append_operation(scf::r#if(
+            fits,
+            &[option_type],
+            build_case("Some", vec![result]),
+            build_case("None", Vec::new()),
+            location,
+        ))
+        .result(0)
+        .unwrap()
+        .into())
+}
+
+#[derive(Clone, Copy)]
+pub(super) enum ArithOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+}
+
+/// What integer arithmetic does when the result doesn't fit in its type.
+#[derive(Clone, Copy)]
+pub(super) enum Overflow {
+    /// Abort the program, which is what the operators do.
+    Trap,
+    /// Wrap around modulo 2^n.
+    Wrap,
+    /// Clamp to the bounds of the type.
+    Saturate,
+}
+
+/// The builtin functions for arithmetic which doesn't trap on overflow.
+pub(super) fn arith_builtin(name: &str) -> Option<(ArithOp, Overflow)> {
+    Some(match name {
+        "modularAdd" => (ArithOp::Add, Overflow::Wrap),
+        "modularSubtract" => (ArithOp::Sub, Overflow::Wrap),
+        "modularMultiply" => (ArithOp::Mul, Overflow::Wrap),
+        "modularDivide" => (ArithOp::Div, Overflow::Wrap),
+        "saturatingAdd" => (ArithOp::Add, Overflow::Saturate),
+        "saturatingSubtract" => (ArithOp::Sub, Overflow::Saturate),
+        "saturatingMultiply" => (ArithOp::Mul, Overflow::Saturate),
+        "saturatingDivide" => (ArithOp::Div, Overflow::Saturate),
+        _ => return None,
+    })
+}
+
+/// Build integer arithmetic with the given overflow behaviour. Division by zero aborts
+/// regardless, since it has no meaningful result.
+#[allow(clippy::too_many_arguments)]
+pub(super) fn build_integer_arith<'c, 'b>(
+    ctx: &'c BuildContext<'c>,
+    function: &FunctionContext<'c, 'b>,
+    block: &'b Block<'c>,
+    op: ArithOp,
+    overflow: Overflow,
+    lhs: Value<'c, 'b>,
+    rhs: Value<'c, 'b>,
+    is_signed: bool,
+) -> Value<'c, 'b> {
+    let r#type = lhs.r#type();
+    let bits = IntegerType::try_from(r#type).unwrap().width();
+    let (min, max) = if is_signed {
+        (i64::MIN >> (64 - bits), i64::MAX >> (64 - bits))
+    } else {
+        (0, (u64::MAX >> (64 - bits)) as i64)
+    };
+    let location = Location::unknown(ctx);
+    let append = |operation| -> Value<'c, 'b> {
+        block.append_operation(operation).result(0).unwrap().into()
+    };
+
+    match (op, overflow) {
+        (ArithOp::Div, _) => {
+            let k0 = build_integer(ctx, block, 0, r#type);
+            let is_zero = append(arith::cmpi(ctx, CmpiPredicate::Eq, rhs, k0, location));
+            build_check(ctx, function, block, is_zero, "division by zero");
+
+            if !is_signed {
+                return append(arith::divui(lhs, rhs, location));
+            }
+
+            // `MIN / -1` is the only signed division that overflows. Dividing by one instead
+            // gives `MIN`, which is also the wrapped result.
+            let k_min = build_integer(ctx, block, min, r#type);
+            let k_neg1 = build_integer(ctx, block, -1, r#type);
+            let k1 = build_integer(ctx, block, 1, r#type);
+            let is_min = append(arith::cmpi(ctx, CmpiPredicate::Eq, lhs, k_min, location));
+            let is_neg1 = append(arith::cmpi(ctx, CmpiPredicate::Eq, rhs, k_neg1, location));
+            let overflows = append(arith::andi(is_min, is_neg1, location));
+            if let Overflow::Trap = overflow {
+                build_check(ctx, function, block, overflows, "integer overflow");
+            }
+
+            let rhs = append(arith::select(overflows, k1, rhs, location));
+            let value = append(arith::divsi(lhs, rhs, location));
+            match overflow {
+                Overflow::Saturate => {
+                    let k_max = build_integer(ctx, block, max, r#type);
+                    append(arith::select(overflows, k_max, value, location))
+                }
+                Overflow::Trap | Overflow::Wrap => value,
+            }
+        }
+        (_, Overflow::Wrap) => append(match op {
+            ArithOp::Add => arith::addi(lhs, rhs, location),
+            ArithOp::Sub => arith::subi(lhs, rhs, location),
+            _ => arith::muli(lhs, rhs, location),
+        }),
+        (ArithOp::Add | ArithOp::Sub, Overflow::Saturate) => {
+            let name = match (op, is_signed) {
+                (ArithOp::Add, true) => "llvm.intr.sadd.sat",
+                (ArithOp::Add, false) => "llvm.intr.uadd.sat",
+                (_, true) => "llvm.intr.ssub.sat",
+                (_, false) => "llvm.intr.usub.sat",
+            };
+            append(
+                OperationBuilder::new(name, location)
+                    .add_operands(&[lhs, rhs])
+                    .add_results(&[r#type])
+                    .build()
+                    .unwrap(),
+            )
+        }
+        (_, Overflow::Trap | Overflow::Saturate) => {
+            let name = match (op, is_signed) {
+                (ArithOp::Add, true) => "llvm.intr.sadd.with.overflow",
+                (ArithOp::Add, false) => "llvm.intr.uadd.with.overflow",
+                (ArithOp::Sub, true) => "llvm.intr.ssub.with.overflow",
+                (ArithOp::Sub, false) => "llvm.intr.usub.with.overflow",
+                (_, true) => "llvm.intr.smul.with.overflow",
+                (_, false) => "llvm.intr.umul.with.overflow",
+            };
+            let bool_type = IntegerType::new(ctx, 1).into();
+            let result = append(
+                OperationBuilder::new(name, location)
+                    .add_operands(&[lhs, rhs])
+                    .add_results(&[llvm::r#type::r#struct(ctx, &[r#type, bool_type], false)])
+                    .build()
+                    .unwrap(),
+            );
+            let value = build_extract_value(ctx, block, result, 0, r#type);
+            let overflows = build_extract_value(ctx, block, result, 1, bool_type);
+
+            if let Overflow::Trap = overflow {
+                build_check(ctx, function, block, overflows, "integer overflow");
+                return value;
+            }
+
+            // Only multiplication gets here: the product is negative when exactly one of the
+            // operands is.
+            let k_max = build_integer(ctx, block, max, r#type);
+            let bound = if is_signed {
+                let k_min = build_integer(ctx, block, min, r#type);
+                let signs = append(arith::xori(lhs, rhs, location));
+                let k0 = build_integer(ctx, block, 0, r#type);
+                let is_negative = append(arith::cmpi(ctx, CmpiPredicate::Slt, signs, k0, location));
+                append(arith::select(is_negative, k_min, k_max, location))
+            } else {
+                k_max
+            };
+            append(arith::select(overflows, bound, value, location))
+        }
+    }
+}
+
+/// The functions of the runtime which are called like Austral functions, unless a function of the
+/// same name is in scope.
+pub(super) fn is_runtime_builtin(ctx: &BuildContext<'_>, name: &str) -> bool {
+    const NAMES: &[&str] = &["argumentCount", "nthArgument", "abort", "printLn"];
+    NAMES.contains(&name) && !ctx.functions.contains_key(name)
+}
+
+pub(super) fn build_runtime_builtin<'c, 'b>(
+    ctx: &'c BuildContext<'c>,
+    function: &FunctionContext<'c, 'b>,
+    block: &'b Block<'c>,
+    expr: &FnCallExpr,
+    locals: &HashMap<&str, Local<'c, 'b>>,
+) -> CodegenResult<Value<'c, 'b>> {
+    let name = expr.target.name.as_str();
+    let args = positional_arguments(expr)?;
+
+    let i64_type = IntegerType::new(ctx, 64).into();
+    let value = match (name, args) {
+        ("argumentCount", []) => build_call(ctx, block, "au_argument_count", &[], &[i64_type])
+            .expect("call should have a result"),
+        ("nthArgument", [index]) => {
+            let index = process_expr(index);
+            let index = build_expr(ctx, function, block, &index, Some(i64_type), locals)?;
+            build_call(ctx, block, "au_nth_argument", &[index], &[string_type(ctx)])
+                .expect("call should have a result")
+        }
+        ("abort", [message]) => {
+            let (buffer, len) = build_string(ctx, function, block, message, locals)?;
+            let status = build_integer(ctx, block, 1, IntegerType::new(ctx, 32).into());
+            build_call(ctx, block, "au_abort", &[buffer, len, status], &[]);
+            build_bool(ctx, block, false)
+        }
+        ("printLn", [value]) => {
+            let print = Expression::Atomic(AtomicExpr::FnCall(FnCallExpr {
+                target: Ident::new("print"),
+                args: FnCallArgs::Positional(vec![value.clone()]),
+            }));
+            build_expr(ctx, function, block, &print, None, locals)?;
+            let stdout = build_integer(ctx, block, STDOUT, IntegerType::new(ctx, 32).into());
+            build_write_str(ctx, block, stdout, "\n");
+            build_bool(ctx, block, false)
+        }
+        _ => return Err(CodegenError::ArgumentCount(name.to_string())),
+    };
+
+    Ok(value)
+}
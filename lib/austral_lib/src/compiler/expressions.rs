@@ -0,0 +1,1250 @@
+use super::{
+    builtins::{
+        arith_builtin, build_conversion, build_integer_arith, build_memory_builtin,
+        build_runtime_builtin, conversion_target, is_memory_builtin, is_runtime_builtin,
+        option_type, ArithOp, Overflow,
+    },
+    declare_external,
+    operations::{
+        build_element_ptr, build_extract_value, build_insert_value, build_literal_str,
+        build_llvm_load, build_llvm_store, build_memref_ptr, build_slot_ptr, build_span,
+        build_undef,
+    },
+    runtime::build_check,
+    statements::{FunctionContext, Local},
+    typeclasses::{is_method, resolve_method},
+    types::{
+        build_foreign_param_type, build_type, build_union_type, mentions_type_params, record_slot,
+        static_str_type, tag_bits, type_layout,
+    },
+    BuildContext,
+};
+use crate::{
+    ast::{
+        ArithExpr, AtomicExpr, CmpExpr, CompoundExpr, Expression, FnCallArgs, Ident, IntrinExpr,
+        LogicExpr, PathSegment, RecordDecl, SelectExpr, TypeSpec, UnionDecl,
+    },
+    constant_eval::{self, ConstValue, Constant},
+    embed_check,
+    error::{CodegenError, CodegenResult},
+    r#type::{Signedness, Ty},
+    type_system::primitive_type,
+};
+use melior::{
+    dialect::{
+        arith::{self, CmpfPredicate, CmpiPredicate},
+        func, llvm, memref, scf,
+    },
+    ir::{
+        attribute::{FlatSymbolRefAttribute, FloatAttribute, IntegerAttribute},
+        operation::OperationBuilder,
+        r#type::IntegerType,
+        Block, Location, Region, Type, Value, ValueLike,
+    },
+};
+use std::{borrow::Cow, collections::HashMap};
+
+/// Build the value of a variable or constant, along with its type.
+pub(super) fn build_name<'c, 'b>(
+    ctx: &'c BuildContext<'c>,
+    function: &FunctionContext<'c, 'b>,
+    block: &'b Block<'c>,
+    name: &str,
+    locals: &HashMap<&str, Local<'c, 'b>>,
+) -> CodegenResult<(Value<'c, 'b>, TypeSpec)> {
+    match locals.get(name) {
+        Some(Local::Value { value, r#type }) => Ok((*value, r#type.clone())),
+        Some(Local::Variable { slot, r#type }) => {
+            let value = block
+                .append_operation(memref::load(*slot, &[], Location::unknown(ctx)))
+                .result(0)
+                .unwrap()
+                .into();
+            Ok((value, r#type.clone()))
+        }
+        None => {
+            let constant = ctx
+                .constants
+                .get(name)
+                .cloned()
+                .or_else(|| constant_eval::builtin_constant(name))
+                .ok_or_else(|| CodegenError::UnknownVariable(name.to_string()))?;
+            Ok((
+                build_constant(ctx, function, block, &constant, locals)?,
+                constant.r#type,
+            ))
+        }
+    }
+}
+
+/// Where the value a path leads to lives.
+#[derive(Clone, Copy)]
+pub(super) enum Place<'c, 'b> {
+    Value(Value<'c, 'b>),
+    /// Behind a pointer, once the path has gone through a pointer or into a span.
+    Memory(Value<'c, 'b>),
+}
+
+/// Follow the segments of a path. Slots of values are extracted, while anything reached through
+/// a pointer stays in memory, so that assignments can store there.
+#[allow(clippy::too_many_arguments)]
+pub(super) fn build_path_segments<'c, 'b>(
+    ctx: &'c BuildContext<'c>,
+    function: &FunctionContext<'c, 'b>,
+    block: &'b Block<'c>,
+    mut place: Place<'c, 'b>,
+    mut r#type: TypeSpec,
+    segments: &[PathSegment],
+    locals: &HashMap<&str, Local<'c, 'b>>,
+) -> CodegenResult<(Place<'c, 'b>, TypeSpec)> {
+    for segment in segments {
+        let segment_type =
+            segment_type(ctx, &r#type, segment).ok_or_else(|| segment_error(&r#type, segment))?;
+
+        place = match segment {
+            PathSegment::SlotAccess(slot_name) => {
+                let (index, _) = record_slot(ctx, &r#type, &slot_name.name).unwrap();
+                match place {
+                    Place::Value(value) => Place::Value(build_extract_value(
+                        ctx,
+                        block,
+                        value,
+                        index,
+                        build_type(ctx, &segment_type)?,
+                    )),
+                    Place::Memory(ptr) => {
+                        let record_type = build_type(ctx, &r#type)?;
+                        Place::Memory(build_slot_ptr(ctx, block, ptr, record_type, index))
+                    }
+                }
+            }
+            PathSegment::PtrSlotAccess(slot_name) => {
+                let ptr = build_place_value(ctx, block, place, &r#type)?;
+                let record = pointee(&r#type).unwrap();
+                let (index, _) = record_slot(ctx, record, &slot_name.name).unwrap();
+                let record_type = build_type(ctx, record)?;
+                Place::Memory(build_slot_ptr(ctx, block, ptr, record_type, index))
+            }
+            PathSegment::ArrayIndex(expr) => {
+                let span = build_place_value(ctx, block, place, &r#type)?;
+                let index_type = build_type(
+                    ctx,
+                    &TypeSpec::Simple {
+                        name: Ident::new("Index"),
+                    },
+                )?;
+                let expr = process_expr(expr);
+                let index = build_expr(ctx, function, block, &expr, Some(index_type), locals)?;
+
+                let ptr =
+                    build_extract_value(ctx, block, span, 0, llvm::r#type::opaque_pointer(ctx));
+                let len = build_extract_value(ctx, block, span, 1, index_type);
+                let is_out_of_bounds = block
+                    .append_operation(arith::cmpi(
+                        ctx,
+                        CmpiPredicate::Uge,
+                        index,
+                        len,
+                        Location::unknown(ctx),
+                    ))
+                    .result(0)
+                    .unwrap()
+                    .into();
+                build_check(
+                    ctx,
+                    function,
+                    block,
+                    is_out_of_bounds,
+                    "span index out of bounds",
+                );
+
+                let element_type = build_type(ctx, &segment_type)?;
+                Place::Memory(build_element_ptr(ctx, block, ptr, element_type, index))
+            }
+        };
+        r#type = segment_type;
+    }
+
+    Ok((place, r#type))
+}
+
+fn build_place_value<'c, 'b>(
+    ctx: &'c BuildContext<'c>,
+    block: &'b Block<'c>,
+    place: Place<'c, 'b>,
+    r#type: &TypeSpec,
+) -> CodegenResult<Value<'c, 'b>> {
+    Ok(match place {
+        Place::Value(value) => value,
+        Place::Memory(ptr) => build_llvm_load(ctx, block, ptr, build_type(ctx, r#type)?),
+    })
+}
+
+/// The type of what a path segment leads to, from a value of the given type.
+fn segment_type(
+    ctx: &BuildContext<'_>,
+    r#type: &TypeSpec,
+    segment: &PathSegment,
+) -> Option<TypeSpec> {
+    match segment {
+        PathSegment::SlotAccess(slot_name) => Some(record_slot(ctx, r#type, &slot_name.name)?.1),
+        PathSegment::PtrSlotAccess(slot_name) => {
+            Some(record_slot(ctx, pointee(r#type)?, &slot_name.name)?.1)
+        }
+        PathSegment::ArrayIndex(_) => match r#type {
+            TypeSpec::SpanRead { lhs, .. } | TypeSpec::SpanWrite { lhs, .. } => {
+                Some((**lhs).clone())
+            }
+            _ => None,
+        },
+    }
+}
+
+/// Why a path segment doesn't apply to a value of the given type.
+fn segment_error(r#type: &TypeSpec, segment: &PathSegment) -> CodegenError {
+    match segment {
+        PathSegment::PtrSlotAccess(_) if pointee(r#type).is_none() => CodegenError::NotAPointer,
+        PathSegment::SlotAccess(slot_name) | PathSegment::PtrSlotAccess(slot_name) => {
+            CodegenError::UnknownSlot(slot_name.name.clone())
+        }
+        PathSegment::ArrayIndex(_) => CodegenError::NotASpan,
+    }
+}
+
+/// The type a pointer or a reference points to.
+pub(super) fn pointee(r#type: &TypeSpec) -> Option<&TypeSpec> {
+    match r#type {
+        TypeSpec::Generic { name, type_params }
+            if matches!(name.name.as_str(), "Pointer" | "Address") =>
+        {
+            type_params.first()
+        }
+        TypeSpec::BorrowRead { lhs, .. } | TypeSpec::BorrowWrite { lhs, .. } => Some(lhs),
+        _ => None,
+    }
+}
+
+/// The address of a variable, to borrow it. Variables already live in a stack slot, while
+/// immutable values are spilled to a new one.
+pub(super) fn build_address<'c, 'b>(
+    ctx: &'c BuildContext<'c>,
+    function: &FunctionContext<'c, 'b>,
+    block: &'b Block<'c>,
+    name: &str,
+    locals: &HashMap<&str, Local<'c, 'b>>,
+) -> CodegenResult<(Value<'c, 'b>, TypeSpec)> {
+    if let Some(Local::Variable { slot, r#type }) = locals.get(name) {
+        return Ok((build_memref_ptr(ctx, block, *slot), r#type.clone()));
+    }
+
+    let (value, r#type) = build_name(ctx, function, block, name, locals)?;
+    let ptr = function.llvm_alloca(ctx, value.r#type());
+    build_llvm_store(ctx, block, value, ptr);
+    Ok((ptr, r#type))
+}
+
+pub(super) fn build_bool<'c, 'b>(
+    ctx: &'c BuildContext<'c>,
+    block: &'b Block<'c>,
+    value: bool,
+) -> Value<'c, 'b> {
+    block
+        .append_operation(arith::constant(
+            ctx,
+            IntegerAttribute::new(value as i64, IntegerType::new(ctx, 1).into()).into(),
+            Location::unknown(ctx),
+        ))
+        .result(0)
+        .unwrap()
+        .into()
+}
+
+/// Build a record from the named arguments of its constructor.
+fn build_record<'c, 'b>(
+    ctx: &'c BuildContext<'c>,
+    function: &FunctionContext<'c, 'b>,
+    block: &'b Block<'c>,
+    decl: &RecordDecl,
+    args: &FnCallArgs,
+    locals: &HashMap<&str, Local<'c, 'b>>,
+) -> CodegenResult<Value<'c, 'b>> {
+    let values = decl
+        .slots
+        .iter()
+        .map(|slot| {
+            let expr = named_argument(&decl.name, args, &slot.name)?;
+            let expr = process_expr(expr);
+
+            // The type of a generic slot is only known from its value.
+            let slot_type = match mentions_type_params(&slot.r#type, &decl.type_params) {
+                true => None,
+                false => Some(build_type(ctx, &slot.r#type)?),
+            };
+            build_expr(ctx, function, block, &expr, slot_type, locals)
+        })
+        .collect::<CodegenResult<Vec<_>>>()?;
+
+    let r#type = llvm::r#type::r#struct(
+        ctx,
+        &values.iter().map(|x| x.r#type()).collect::<Vec<_>>(),
+        false,
+    );
+    let mut record = build_undef(ctx, block, r#type);
+    for (index, value) in values.into_iter().enumerate() {
+        record = build_insert_value(ctx, block, record, index, value);
+    }
+
+    Ok(record)
+}
+
+/// The argument for a parameter of a function, record or union case taking named arguments.
+fn named_argument<'e>(
+    callee: &Ident,
+    args: &'e FnCallArgs,
+    name: &Ident,
+) -> CodegenResult<&'e Expression> {
+    match args {
+        FnCallArgs::Named(args) => args.get(name).ok_or_else(|| CodegenError::MissingArgument {
+            function: callee.name.clone(),
+            argument: name.name.clone(),
+        }),
+        _ => Err(CodegenError::NamedArguments(callee.name.clone())),
+    }
+}
+
+/// Build a union from the constructor of one of its cases. The representation is told apart by
+/// the expected type, since the layout of a generic union depends on its type arguments.
+#[allow(clippy::too_many_arguments)]
+fn build_union<'c, 'b>(
+    ctx: &'c BuildContext<'c>,
+    function: &FunctionContext<'c, 'b>,
+    block: &'b Block<'c>,
+    decl: &UnionDecl,
+    case: usize,
+    args: &FnCallArgs,
+    r#type: Type<'c>,
+    locals: &HashMap<&str, Local<'c, 'b>>,
+) -> CodegenResult<Value<'c, 'b>> {
+    let values = decl.cases[case]
+        .fields
+        .iter()
+        .map(|field| {
+            let expr = named_argument(&decl.cases[case].name, args, &field.name)?;
+            let expr = process_expr(expr);
+
+            let field_type = match mentions_type_params(&field.r#type, &decl.type_params) {
+                true => None,
+                false => Some(build_type(ctx, &field.r#type)?),
+            };
+            build_expr(ctx, function, block, &expr, field_type, locals)
+        })
+        .collect::<CodegenResult<Vec<_>>>()?;
+
+    Ok(build_union_value(
+        ctx, function, block, decl, case, values, r#type,
+    ))
+}
+
+/// Build a union holding the given case, from the values of its fields.
+pub(super) fn build_union_value<'c, 'b>(
+    ctx: &'c BuildContext<'c>,
+    function: &FunctionContext<'c, 'b>,
+    block: &'b Block<'c>,
+    decl: &UnionDecl,
+    case: usize,
+    values: Vec<Value<'c, 'b>>,
+    r#type: Type<'c>,
+) -> Value<'c, 'b> {
+    let build_tag = |block: &'b Block<'c>| -> Value<'c, 'b> {
+        block
+            .append_operation(arith::constant(
+                ctx,
+                IntegerAttribute::new(case as i64, IntegerType::new(ctx, tag_bits(decl)).into())
+                    .into(),
+                Location::unknown(ctx),
+            ))
+            .result(0)
+            .unwrap()
+            .into()
+    };
+
+    if r#type.is_integer() {
+        build_tag(block)
+    } else if r#type.is_llvm_pointer_type() {
+        match values.as_slice() {
+            [value] => *value,
+            _ => block
+                .append_operation(
+                    OperationBuilder::new("llvm.mlir.zero", Location::unknown(ctx))
+                        .add_results(&[r#type])
+                        .build()
+                        .unwrap(),
+                )
+                .result(0)
+                .unwrap()
+                .into(),
+        }
+    } else {
+        let payload_type = llvm::r#type::r#struct(
+            ctx,
+            &values.iter().map(|x| x.r#type()).collect::<Vec<_>>(),
+            false,
+        );
+        let mut payload = build_undef(ctx, block, payload_type);
+        for (index, value) in values.into_iter().enumerate() {
+            payload = build_insert_value(ctx, block, payload, index, value);
+        }
+
+        // The storage follows the tag.
+        let slot = function.llvm_alloca(ctx, r#type);
+        let storage = build_slot_ptr(ctx, block, slot, r#type, 1);
+        build_llvm_store(ctx, block, payload, storage);
+        let value = build_llvm_load(ctx, block, slot, r#type);
+        build_insert_value(ctx, block, value, 0, build_tag(block))
+    }
+}
+
+pub(super) fn process_expr(expr: &Expression) -> Cow<Expression> {
+    if let Expression::Atomic(AtomicExpr::FnCall(expr)) = expr {
+        match expr.target.name.as_str() {
+            "ExitSuccess" => return Cow::Owned(Expression::Atomic(AtomicExpr::ConstInt(0))),
+            "ExitFailure" => return Cow::Owned(Expression::Atomic(AtomicExpr::ConstInt(1))),
+            _ => {}
+        }
+    }
+
+    Cow::Borrowed(expr)
+}
+
+pub(super) fn build_expr<'c, 'b>(
+    ctx: &'c BuildContext<'c>,
+    function: &FunctionContext<'c, 'b>,
+    block: &'b Block<'c>,
+    expr: &Expression,
+
+    target_type: Option<Type<'c>>,
+    locals: &HashMap<&str, Local<'c, 'b>>,
+) -> CodegenResult<Value<'c, 'b>> {
+    let value = match expr {
+        Expression::Atomic(expr) => match expr {
+            AtomicExpr::ConstInt(value) => {
+                build_int_literal(ctx, block, *value as i64, target_type)?
+            }
+            // Characters are bytes, unless they're used as another integer type.
+            AtomicExpr::ConstChar(value) => build_integer(
+                ctx,
+                block,
+                u32::from(*value) as i64,
+                target_type.unwrap_or_else(|| IntegerType::new(ctx, 8).into()),
+            ),
+            AtomicExpr::ConstFloat(value) => block
+                .append_operation(arith::constant(
+                    ctx,
+                    FloatAttribute::new(
+                        ctx,
+                        *value,
+                        target_type.unwrap_or_else(|| Type::float64(ctx)),
+                    )
+                    .into(),
+                    Location::unknown(ctx),
+                ))
+                .result(0)
+                .unwrap()
+                .into(),
+            // Literals are spans of static bytes, unless a pointer is expected to pass them to C.
+            AtomicExpr::ConstStr(value) => {
+                let buffer = build_literal_str(ctx, block, value);
+                if target_type == Some(llvm::r#type::opaque_pointer(ctx)) {
+                    buffer
+                } else {
+                    let len = build_integer(
+                        ctx,
+                        block,
+                        value.len() as i64,
+                        IntegerType::new(ctx, 64).into(),
+                    );
+                    build_span(ctx, block, buffer, len)
+                }
+            }
+            AtomicExpr::FnCall(expr) if ctx.records.contains_key(&expr.target.name) => {
+                build_record(
+                    ctx,
+                    function,
+                    block,
+                    &ctx.records[&expr.target.name],
+                    &expr.args,
+                    locals,
+                )?
+            }
+            AtomicExpr::FnCall(expr) if ctx.cases.contains_key(&expr.target.name) => {
+                let decl = &ctx.unions[&ctx.cases[&expr.target.name]];
+                let r#type = match target_type {
+                    Some(r#type) => r#type,
+                    None if decl.type_params.is_empty() => build_union_type(
+                        ctx,
+                        &TypeSpec::Simple {
+                            name: decl.name.clone(),
+                        },
+                    )?,
+                    None => {
+                        return Err(CodegenError::UnknownTypeArguments(expr.target.name.clone()))
+                    }
+                };
+                let case = decl
+                    .cases
+                    .iter()
+                    .position(|case| case.name == expr.target)
+                    .unwrap();
+                build_union(ctx, function, block, decl, case, &expr.args, r#type, locals)?
+            }
+            AtomicExpr::FnCall(expr) if conversion_target(ctx, &expr.target.name).is_some() => {
+                let target = conversion_target(ctx, &expr.target.name).unwrap();
+                let arg = match &expr.args {
+                    FnCallArgs::Positional(args) if args.len() == 1 => &args[0],
+                    _ => return Err(CodegenError::ArgumentCount(expr.target.name.clone())),
+                };
+                let source = infer_type(ctx, arg, locals);
+                let arg = process_expr(arg);
+                let value = build_expr(ctx, function, block, &arg, None, locals)?;
+                build_conversion(ctx, function, block, value, source.as_ref(), &target)?
+            }
+            AtomicExpr::FnCall(expr) if is_memory_builtin(ctx, &expr.target.name) => {
+                build_memory_builtin(ctx, function, block, expr, target_type, locals)?
+            }
+            AtomicExpr::FnCall(expr) if is_runtime_builtin(ctx, &expr.target.name) => {
+                build_runtime_builtin(ctx, function, block, expr, locals)?
+            }
+            AtomicExpr::FnCall(expr) if is_method(ctx, &expr.target.name) => {
+                let call = resolve_method(ctx, expr, locals)
+                    .ok_or_else(|| CodegenError::NoInstance(expr.target.name.clone()))?;
+                let call = Expression::Atomic(AtomicExpr::FnCall(call));
+                build_expr(ctx, function, block, &call, target_type, locals)?
+            }
+            AtomicExpr::FnCall(expr) if arith_builtin(&expr.target.name).is_some() => {
+                let (op, overflow) = arith_builtin(&expr.target.name).unwrap();
+                let (lhs, rhs) = match &expr.args {
+                    FnCallArgs::Positional(args) => match args.as_slice() {
+                        [Expression::Atomic(lhs), Expression::Atomic(rhs)] => (lhs, rhs),
+                        _ => return Err(CodegenError::ArgumentCount(expr.target.name.clone())),
+                    },
+                    _ => return Err(CodegenError::PositionalArguments(expr.target.name.clone())),
+                };
+
+                let (lhs_value, rhs_value) =
+                    build_operands(ctx, function, block, lhs, rhs, target_type, locals)?;
+                let is_signed = is_signed(
+                    infer_atomic_type(ctx, lhs, locals)
+                        .or_else(|| infer_atomic_type(ctx, rhs, locals))
+                        .as_ref(),
+                );
+                build_integer_arith(
+                    ctx, function, block, op, overflow, lhs_value, rhs_value, is_signed,
+                )
+            }
+            AtomicExpr::FnCall(expr) => {
+                let symbol = ctx.functions.get(expr.target.name.as_str());
+                if symbol.is_none() && expr.target.name != "surrenderRoot" {
+                    return Err(CodegenError::UnknownFunction(expr.target.name.clone()));
+                }
+                let args = match &expr.args {
+                    FnCallArgs::Empty => Vec::new(),
+                    FnCallArgs::Positional(args) => args.iter().collect(),
+                    // Named arguments are evaluated in declaration order.
+                    FnCallArgs::Named(_) => {
+                        let symbol = symbol.ok_or_else(|| {
+                            CodegenError::UnknownFunction(expr.target.name.clone())
+                        })?;
+                        symbol
+                            .params
+                            .iter()
+                            .map(|param| named_argument(&expr.target, &expr.args, &param.name))
+                            .collect::<CodegenResult<Vec<_>>>()?
+                    }
+                }
+                .into_iter()
+                .enumerate()
+                .map(|(i, expr)| {
+                    let expr = process_expr(expr);
+                    let Some(symbol) = symbol else {
+                        return build_expr(ctx, function, block, &expr, None, locals);
+                    };
+                    if !symbol.is_foreign {
+                        let arg_type = build_type(ctx, &symbol.params[i].r#type)?;
+                        return build_expr(ctx, function, block, &expr, Some(arg_type), locals);
+                    }
+
+                    // String literals are built as a pointer directly, other spans pass theirs.
+                    let arg_type = build_foreign_param_type(ctx, &symbol.params[i].r#type)?;
+                    let value = build_expr(ctx, function, block, &expr, Some(arg_type), locals)?;
+                    Ok(if value.r#type() == arg_type {
+                        value
+                    } else {
+                        build_extract_value(ctx, block, value, 0, arg_type)
+                    })
+                })
+                .collect::<CodegenResult<Vec<_>>>()?;
+
+                match expr.target.name.as_str() {
+                    "surrenderRoot" => block
+                        .append_operation(arith::constant(
+                            ctx,
+                            IntegerAttribute::new(0, IntegerType::new(ctx, 1).into()).into(),
+                            Location::unknown(ctx),
+                        ))
+                        .result(0)
+                        .unwrap()
+                        .into(),
+                    name => {
+                        let symbol = symbol
+                            .ok_or_else(|| CodegenError::UnknownFunction(name.to_string()))?;
+                        if symbol.is_imported {
+                            let arg_types = symbol
+                                .params
+                                .iter()
+                                .map(|param| match symbol.is_foreign {
+                                    true => build_foreign_param_type(ctx, &param.r#type),
+                                    false => build_type(ctx, &param.r#type),
+                                })
+                                .collect::<CodegenResult<Vec<_>>>()?;
+                            let ret_type = build_type(ctx, &symbol.ret_type)?;
+                            declare_external(ctx, &symbol.symbol, &arg_types, &[ret_type]);
+                        }
+
+                        block
+                            .append_operation(func::call(
+                                ctx,
+                                FlatSymbolRefAttribute::new(ctx, &symbol.symbol),
+                                &args,
+                                &[build_type(ctx, &symbol.ret_type)?],
+                                Location::unknown(ctx),
+                            ))
+                            .result(0)
+                            .unwrap()
+                            .into()
+                    }
+                }
+            }
+            AtomicExpr::ConstBool(value) => build_bool(ctx, block, *value),
+            AtomicExpr::ConstNil => block
+                .append_operation(arith::constant(
+                    ctx,
+                    IntegerAttribute::new(0, IntegerType::new(ctx, 1).into()).into(),
+                    Location::unknown(ctx),
+                ))
+                .result(0)
+                .unwrap()
+                .into(),
+            AtomicExpr::Paren(expr) => build_expr(ctx, function, block, expr, target_type, locals)?,
+            AtomicExpr::Path(expr) => {
+                let (value, r#type) =
+                    build_name(ctx, function, block, expr.first.name.as_str(), locals)?;
+                let (place, r#type) = build_path_segments(
+                    ctx,
+                    function,
+                    block,
+                    Place::Value(value),
+                    r#type,
+                    &expr.extra,
+                    locals,
+                )?;
+                build_place_value(ctx, block, place, &r#type)?
+            }
+            AtomicExpr::BorrowRead(name) | AtomicExpr::BorrowWrite(name) => {
+                build_address(ctx, function, block, &name.name, locals)?.0
+            }
+            AtomicExpr::ReBorrow(name) => build_name(ctx, function, block, &name.name, locals)?.0,
+            AtomicExpr::RefPath(expr) => {
+                let (ptr, r#type) =
+                    build_address(ctx, function, block, expr.first.name.as_str(), locals)?;
+                let (place, _) = build_path_segments(
+                    ctx,
+                    function,
+                    block,
+                    Place::Memory(ptr),
+                    r#type,
+                    &expr.extra,
+                    locals,
+                )?;
+                let Place::Memory(ptr) = place else {
+                    unreachable!()
+                };
+                ptr
+            }
+            AtomicExpr::SizeOf(r#type) => {
+                let (size, _) = type_layout(ctx, r#type)?;
+                build_integer(ctx, block, size as i64, IntegerType::new(ctx, 64).into())
+            }
+            AtomicExpr::Intrinsic(IntrinExpr::Embed { r#type, exp, args }) => {
+                let code = embed_check::parse_embed(exp, args.len())?;
+
+                // The code only sees its arguments. Those of unknown type, like literals, take
+                // the type of the embed.
+                let mut embed_locals = HashMap::new();
+                let names = (1..=args.len())
+                    .map(embed_check::placeholder)
+                    .collect::<Vec<_>>();
+                for (name, arg) in names.iter().zip(args) {
+                    let arg_type = infer_type(ctx, arg, locals).unwrap_or_else(|| r#type.clone());
+                    let arg = process_expr(arg);
+                    let value = build_expr(
+                        ctx,
+                        function,
+                        block,
+                        &arg,
+                        Some(build_type(ctx, &arg_type)?),
+                        locals,
+                    )?;
+                    embed_locals.insert(
+                        name.as_str(),
+                        Local::Value {
+                            value,
+                            r#type: arg_type,
+                        },
+                    );
+                }
+
+                let code = process_expr(&code);
+                build_expr(
+                    ctx,
+                    function,
+                    block,
+                    &code,
+                    Some(build_type(ctx, r#type)?),
+                    &embed_locals,
+                )?
+            }
+            AtomicExpr::Deref(expr) => {
+                let r#type =
+                    infer_atomic_type(ctx, expr, locals).ok_or(CodegenError::NotAPointer)?;
+                let pointee = pointee(&r#type).ok_or(CodegenError::NotAPointer)?;
+                let expr = process_expr(&Expression::Atomic((**expr).clone())).into_owned();
+                let ptr = build_expr(ctx, function, block, &expr, None, locals)?;
+                build_llvm_load(ctx, block, ptr, build_type(ctx, pointee)?)
+            }
+        },
+        Expression::Compound(expr) => match expr {
+            CompoundExpr::Arith(expr) => match expr {
+                ArithExpr::Add(lhs, rhs)
+                | ArithExpr::Sub(lhs, rhs)
+                | ArithExpr::Mul(lhs, rhs)
+                | ArithExpr::Div(lhs, rhs) => {
+                    let (lhs_value, rhs_value) =
+                        build_operands(ctx, function, block, lhs, rhs, target_type, locals)?;
+                    let is_signed = is_signed(
+                        infer_atomic_type(ctx, lhs, locals)
+                            .or_else(|| infer_atomic_type(ctx, rhs, locals))
+                            .as_ref(),
+                    );
+                    let op = match expr {
+                        ArithExpr::Add(..) => ArithOp::Add,
+                        ArithExpr::Sub(..) => ArithOp::Sub,
+                        ArithExpr::Mul(..) => ArithOp::Mul,
+                        ArithExpr::Div(..) => ArithOp::Div,
+                        ArithExpr::Neg(_) => unreachable!(),
+                    };
+
+                    if IntegerType::try_from(lhs_value.r#type()).is_ok() {
+                        build_integer_arith(
+                            ctx,
+                            function,
+                            block,
+                            op,
+                            Overflow::Trap,
+                            lhs_value,
+                            rhs_value,
+                            is_signed,
+                        )
+                    } else {
+                        let location = Location::unknown(ctx);
+                        let operation = match op {
+                            ArithOp::Add => arith::addf(lhs_value, rhs_value, location),
+                            ArithOp::Sub => arith::subf(lhs_value, rhs_value, location),
+                            ArithOp::Mul => arith::mulf(lhs_value, rhs_value, location),
+                            ArithOp::Div => arith::divf(lhs_value, rhs_value, location),
+                        };
+                        block.append_operation(operation).result(0).unwrap().into()
+                    }
+                }
+                ArithExpr::Neg(AtomicExpr::ConstInt(value)) => {
+                    build_int_literal(ctx, block, (*value as i64).wrapping_neg(), target_type)?
+                }
+                ArithExpr::Neg(expr) => {
+                    let expr = process_expr(&Expression::Atomic(expr.clone())).into_owned();
+                    let value = build_expr(ctx, function, block, &expr, target_type, locals)?;
+
+                    if IntegerType::try_from(value.r#type()).is_ok() {
+                        // Negating the minimum of a signed type, or anything but zero of an
+                        // unsigned one, overflows.
+                        let zero = build_integer(ctx, block, 0, value.r#type());
+                        let is_signed = is_signed(infer_type(ctx, &expr, locals).as_ref());
+                        build_integer_arith(
+                            ctx,
+                            function,
+                            block,
+                            ArithOp::Sub,
+                            Overflow::Trap,
+                            zero,
+                            value,
+                            is_signed,
+                        )
+                    } else {
+                        block
+                            .append_operation(arith::negf(value, Location::unknown(ctx)))
+                            .result(0)
+                            .unwrap()
+                            .into()
+                    }
+                }
+            },
+            CompoundExpr::Cmp(expr) => {
+                let (lhs, rhs) = match expr {
+                    CmpExpr::Eq(lhs, rhs)
+                    | CmpExpr::NotEq(lhs, rhs)
+                    | CmpExpr::Lt(lhs, rhs)
+                    | CmpExpr::LtEq(lhs, rhs)
+                    | CmpExpr::Gt(lhs, rhs)
+                    | CmpExpr::GtEq(lhs, rhs) => (lhs, rhs),
+                };
+                let (lhs_value, rhs_value) =
+                    build_operands(ctx, function, block, lhs, rhs, None, locals)?;
+                let is_signed = is_signed(
+                    infer_atomic_type(ctx, lhs, locals)
+                        .or_else(|| infer_atomic_type(ctx, rhs, locals))
+                        .as_ref(),
+                );
+
+                let operation = if IntegerType::try_from(lhs_value.r#type()).is_ok() {
+                    arith::cmpi(
+                        ctx,
+                        integer_predicate(expr, is_signed),
+                        lhs_value,
+                        rhs_value,
+                        Location::unknown(ctx),
+                    )
+                } else {
+                    arith::cmpf(
+                        ctx,
+                        float_predicate(expr),
+                        lhs_value,
+                        rhs_value,
+                        Location::unknown(ctx),
+                    )
+                };
+                block.append_operation(operation).result(0).unwrap().into()
+            }
+            // A cast only gives its value a type, which decides how literals are built. Any other
+            // value must already have that type.
+            CompoundExpr::Cast(expr) => {
+                let inferred = infer_atomic_type(ctx, &expr.value, locals);
+                let value = process_expr(&Expression::Atomic(expr.value.clone())).into_owned();
+                let r#type = build_type(ctx, &expr.r#type)?;
+                let value = build_expr(ctx, function, block, &value, Some(r#type), locals)?;
+                if value.r#type() != r#type || inferred.is_some_and(|x| x != expr.r#type) {
+                    return Err(CodegenError::TypeAscription(expr.r#type.to_string()));
+                }
+                value
+            }
+            CompoundExpr::Logic(expr) => build_logic(ctx, function, block, expr, locals)?,
+            CompoundExpr::Select(expr) => {
+                build_select(ctx, function, block, expr, target_type, locals)?
+            }
+        },
+    };
+
+    Ok(value)
+}
+
+fn integer_predicate(expr: &CmpExpr, is_signed: bool) -> CmpiPredicate {
+    match (expr, is_signed) {
+        (CmpExpr::Eq(..), _) => CmpiPredicate::Eq,
+        (CmpExpr::NotEq(..), _) => CmpiPredicate::Ne,
+        (CmpExpr::Lt(..), true) => CmpiPredicate::Slt,
+        (CmpExpr::Lt(..), false) => CmpiPredicate::Ult,
+        (CmpExpr::LtEq(..), true) => CmpiPredicate::Sle,
+        (CmpExpr::LtEq(..), false) => CmpiPredicate::Ule,
+        (CmpExpr::Gt(..), true) => CmpiPredicate::Sgt,
+        (CmpExpr::Gt(..), false) => CmpiPredicate::Ugt,
+        (CmpExpr::GtEq(..), true) => CmpiPredicate::Sge,
+        (CmpExpr::GtEq(..), false) => CmpiPredicate::Uge,
+    }
+}
+
+fn float_predicate(expr: &CmpExpr) -> CmpfPredicate {
+    match expr {
+        CmpExpr::Eq(..) => CmpfPredicate::Oeq,
+        // `NaN /= x` holds, so inequality is unordered.
+        CmpExpr::NotEq(..) => CmpfPredicate::Une,
+        CmpExpr::Lt(..) => CmpfPredicate::Olt,
+        CmpExpr::LtEq(..) => CmpfPredicate::Ole,
+        CmpExpr::Gt(..) => CmpfPredicate::Ogt,
+        CmpExpr::GtEq(..) => CmpfPredicate::Oge,
+    }
+}
+
+/// Build `and` and `or`, which only evaluate their right operand when needed.
+fn build_logic<'c, 'b>(
+    ctx: &'c BuildContext<'c>,
+    function: &FunctionContext<'c, 'b>,
+    block: &'b Block<'c>,
+    expr: &LogicExpr,
+    locals: &HashMap<&str, Local<'c, 'b>>,
+) -> CodegenResult<Value<'c, 'b>> {
+    let bool_type = IntegerType::new(ctx, 1).into();
+    let build_region = |expr: Option<&AtomicExpr>, default: bool| -> CodegenResult<Region<'c>> {
+        let block = Block::new(&[]);
+        let value = match expr {
+            Some(expr) => {
+                let expr = process_expr(&Expression::Atomic(expr.clone())).into_owned();
+                build_expr(ctx, function, &block, &expr, Some(bool_type), locals)?
+            }
+            None => build_bool(ctx, &block, default),
+        };
+        block.append_operation(scf::r#yield(&[value], Location::unknown(ctx)));
+
+        let region = Region::new();
+        region.append_block(block);
+        Ok(region)
+    };
+
+    let (lhs, then_region, else_region) = match expr {
+        LogicExpr::And(lhs, rhs) => (
+            lhs,
+            build_region(Some(rhs), false)?,
+            build_region(None, false)?,
+        ),
+        LogicExpr::Or(lhs, rhs) => (
+            lhs,
+            build_region(None, true)?,
+            build_region(Some(rhs), true)?,
+        ),
+        LogicExpr::Not(expr) => {
+            let expr = process_expr(&Expression::Atomic(expr.clone())).into_owned();
+            let value = build_expr(ctx, function, block, &expr, Some(bool_type), locals)?;
+            let k1 = build_bool(ctx, block, true);
+
+            return Ok(block
+                .append_operation(arith::xori(value, k1, Location::unknown(ctx)))
+                .result(0)
+                .unwrap()
+                .into());
+        }
+    };
+
+    let lhs = process_expr(&Expression::Atomic(lhs.clone())).into_owned();
+    let condition = build_expr(ctx, function, block, &lhs, Some(bool_type), locals)?;
+
+    Ok(block
+        .append_operation(scf::r#if(
+            condition,
+            &[bool_type],
+            then_region,
+            else_region,
+            Location::unknown(ctx),
+        ))
+        .result(0)
+        .unwrap()
+        .into())
+}
+
+/// Build a span of bytes as its pointer and length.
+pub(super) fn build_string<'c, 'b>(
+    ctx: &'c BuildContext<'c>,
+    function: &FunctionContext<'c, 'b>,
+    block: &'b Block<'c>,
+    expr: &Expression,
+    locals: &HashMap<&str, Local<'c, 'b>>,
+) -> CodegenResult<(Value<'c, 'b>, Value<'c, 'b>)> {
+    let i64_type = IntegerType::new(ctx, 64).into();
+    let expr = process_expr(expr);
+    let span = build_expr(ctx, function, block, &expr, None, locals)?;
+    Ok((
+        build_extract_value(ctx, block, span, 0, llvm::r#type::opaque_pointer(ctx)),
+        build_extract_value(ctx, block, span, 1, i64_type),
+    ))
+}
+
+/// Integer literals take the expected type, or `Int32` without one, like the argument of
+/// `toFloat64(5)` or the operands of `1 = 2`.
+fn build_int_literal<'c, 'b>(
+    ctx: &'c BuildContext<'c>,
+    block: &'b Block<'c>,
+    value: i64,
+    target_type: Option<Type<'c>>,
+) -> CodegenResult<Value<'c, 'b>> {
+    let r#type = match target_type {
+        Some(r#type) => r#type,
+        None if i32::try_from(value).is_ok() => IntegerType::new(ctx, 32).into(),
+        None => return Err(CodegenError::UntypedLiteral(value.to_string())),
+    };
+    Ok(build_integer(ctx, block, value, r#type))
+}
+
+pub(super) fn build_integer<'c, 'b>(
+    ctx: &'c BuildContext<'c>,
+    block: &'b Block<'c>,
+    value: i64,
+    r#type: Type<'c>,
+) -> Value<'c, 'b> {
+    block
+        .append_operation(arith::constant(
+            ctx,
+            IntegerAttribute::new(value, r#type).into(),
+            Location::unknown(ctx),
+        ))
+        .result(0)
+        .unwrap()
+        .into()
+}
+
+/// Build `if c then a else b`. Without an expected type the result has the type of `a`.
+fn build_select<'c, 'b>(
+    ctx: &'c BuildContext<'c>,
+    function: &FunctionContext<'c, 'b>,
+    block: &'b Block<'c>,
+    expr: &SelectExpr,
+    target_type: Option<Type<'c>>,
+    locals: &HashMap<&str, Local<'c, 'b>>,
+) -> CodegenResult<Value<'c, 'b>> {
+    let condition = process_expr(&expr.condition);
+    let condition = build_expr(
+        ctx,
+        function,
+        block,
+        &condition,
+        Some(IntegerType::new(ctx, 1).into()),
+        locals,
+    )?;
+
+    let build_branch = |expr: &Expression, target_type: Option<Type<'c>>| {
+        let block = Block::new(&[]);
+        let expr = process_expr(expr);
+        let value = build_expr(ctx, function, &block, &expr, target_type, locals)?;
+        let r#type = value.r#type();
+        block.append_operation(scf::r#yield(&[value], Location::unknown(ctx)));
+
+        let region = Region::new();
+        region.append_block(block);
+        Ok::<_, CodegenError>((region, r#type))
+    };
+    let (then_region, result_type) = build_branch(&expr.value_true, target_type)?;
+    let (else_region, _) = build_branch(&expr.value_false, Some(result_type))?;
+
+    Ok(block
+        .append_operation(scf::r#if(
+            condition,
+            &[result_type],
+            then_region,
+            else_region,
+            Location::unknown(ctx),
+        ))
+        .result(0)
+        .unwrap()
+        .into())
+}
+
+/// Build both operands of a binary operator. Without an expected type, literals take the type of
+/// the other operand.
+fn build_operands<'c, 'b>(
+    ctx: &'c BuildContext<'c>,
+    function: &FunctionContext<'c, 'b>,
+    block: &'b Block<'c>,
+    lhs: &AtomicExpr,
+    rhs: &AtomicExpr,
+    target_type: Option<Type<'c>>,
+    locals: &HashMap<&str, Local<'c, 'b>>,
+) -> CodegenResult<(Value<'c, 'b>, Value<'c, 'b>)> {
+    let lhs = process_expr(&Expression::Atomic(lhs.clone())).into_owned();
+    let rhs = process_expr(&Expression::Atomic(rhs.clone())).into_owned();
+
+    if target_type.is_none() && is_literal(&lhs) && !is_literal(&rhs) {
+        let rhs_value = build_expr(ctx, function, block, &rhs, None, locals)?;
+        let lhs_value = build_expr(ctx, function, block, &lhs, Some(rhs_value.r#type()), locals)?;
+        Ok((lhs_value, rhs_value))
+    } else {
+        let lhs_value = build_expr(ctx, function, block, &lhs, target_type, locals)?;
+        let rhs_value = build_expr(
+            ctx,
+            function,
+            block,
+            &rhs,
+            target_type.or_else(|| Some(lhs_value.r#type())),
+            locals,
+        )?;
+        Ok((lhs_value, rhs_value))
+    }
+}
+
+/// Infer the declared type of an expression from the names in scope, which decides between signed
+/// and unsigned operations on its value.
+pub(super) fn infer_type(
+    ctx: &BuildContext<'_>,
+    expr: &Expression,
+    locals: &HashMap<&str, Local<'_, '_>>,
+) -> Option<TypeSpec> {
+    match expr {
+        Expression::Atomic(expr) => infer_atomic_type(ctx, expr, locals),
+        Expression::Compound(expr) => match expr {
+            CompoundExpr::Arith(
+                ArithExpr::Add(lhs, rhs)
+                | ArithExpr::Sub(lhs, rhs)
+                | ArithExpr::Mul(lhs, rhs)
+                | ArithExpr::Div(lhs, rhs),
+            ) => {
+                infer_atomic_type(ctx, lhs, locals).or_else(|| infer_atomic_type(ctx, rhs, locals))
+            }
+            CompoundExpr::Arith(ArithExpr::Neg(expr)) => infer_atomic_type(ctx, expr, locals),
+            CompoundExpr::Cast(expr) => Some(expr.r#type.clone()),
+            CompoundExpr::Cmp(_) | CompoundExpr::Logic(_) => Some(TypeSpec::Simple {
+                name: Ident::new("Bool"),
+            }),
+            CompoundExpr::Select(expr) => infer_type(ctx, &expr.value_true, locals)
+                .or_else(|| infer_type(ctx, &expr.value_false, locals)),
+        },
+    }
+}
+
+fn infer_atomic_type(
+    ctx: &BuildContext<'_>,
+    expr: &AtomicExpr,
+    locals: &HashMap<&str, Local<'_, '_>>,
+) -> Option<TypeSpec> {
+    match expr {
+        AtomicExpr::ConstBool(_) => Some(TypeSpec::Simple {
+            name: Ident::new("Bool"),
+        }),
+        AtomicExpr::ConstNil => Some(TypeSpec::Simple {
+            name: Ident::new("Unit"),
+        }),
+        AtomicExpr::ConstChar(_) => Some(TypeSpec::Simple {
+            name: Ident::new("Nat8"),
+        }),
+        AtomicExpr::ConstStr(_) => Some(static_str_type()),
+        AtomicExpr::FnCall(expr) if conversion_target(ctx, &expr.target.name).is_some() => Some(
+            option_type(conversion_target(ctx, &expr.target.name).unwrap()),
+        ),
+        AtomicExpr::FnCall(expr) if is_memory_builtin(ctx, &expr.target.name) => {
+            let pointer = match &expr.args {
+                FnCallArgs::Positional(args) => infer_type(ctx, args.first()?, locals)?,
+                _ => return None,
+            };
+            match expr.target.name.as_str() {
+                "load" => pointee(&pointer).cloned(),
+                "store" => Some(TypeSpec::Simple {
+                    name: Ident::new("Unit"),
+                }),
+                "nullCheck" => Some(option_type(TypeSpec::Generic {
+                    name: Ident::new("Pointer"),
+                    type_params: vec![pointee(&pointer)?.clone()],
+                })),
+                _ => Some(pointer),
+            }
+        }
+        AtomicExpr::FnCall(expr) if is_method(ctx, &expr.target.name) => {
+            let call = resolve_method(ctx, expr, locals)?;
+            ctx.functions
+                .get(&call.target.name)
+                .map(|x| x.ret_type.clone())
+        }
+        AtomicExpr::FnCall(expr) if is_runtime_builtin(ctx, &expr.target.name) => {
+            let name = match expr.target.name.as_str() {
+                "argumentCount" => "Index",
+                "nthArgument" => return Some(static_str_type()),
+                _ => "Unit",
+            };
+            Some(TypeSpec::Simple {
+                name: Ident::new(name),
+            })
+        }
+        AtomicExpr::FnCall(expr) if arith_builtin(&expr.target.name).is_some() => {
+            match &expr.args {
+                FnCallArgs::Positional(args) => {
+                    args.iter().find_map(|arg| infer_type(ctx, arg, locals))
+                }
+                _ => None,
+            }
+        }
+        AtomicExpr::FnCall(expr) => {
+            let name = &expr.target.name;
+            let decl = match (ctx.records.get(name), ctx.cases.get(name)) {
+                (Some(decl), _) => Some((&decl.name, &decl.type_params)),
+                (_, Some(name)) => Some((&ctx.unions[name].name, &ctx.unions[name].type_params)),
+                _ => None,
+            };
+            match decl {
+                Some((name, type_params)) if type_params.is_empty() => {
+                    Some(TypeSpec::Simple { name: name.clone() })
+                }
+                Some(_) => None,
+                None => ctx.functions.get(name.as_str()).map(|x| x.ret_type.clone()),
+            }
+        }
+        AtomicExpr::Paren(expr) => infer_type(ctx, expr, locals),
+        AtomicExpr::Path(expr) => {
+            let name = expr.first.name.as_str();
+            let mut r#type = locals
+                .get(name)
+                .map(|x| x.type_spec().clone())
+                .or_else(|| ctx.constants.get(name).map(|x| x.r#type.clone()))
+                .or_else(|| constant_eval::builtin_constant(name).map(|x| x.r#type))?;
+
+            for segment in &expr.extra {
+                r#type = segment_type(ctx, &r#type, segment)?;
+            }
+            Some(r#type)
+        }
+        AtomicExpr::Deref(expr) => pointee(&infer_atomic_type(ctx, expr, locals)?).cloned(),
+        AtomicExpr::Intrinsic(IntrinExpr::Embed { r#type, .. }) => Some(r#type.clone()),
+        AtomicExpr::SizeOf(_) => Some(TypeSpec::Simple {
+            name: Ident::new("ByteSize"),
+        }),
+        AtomicExpr::ReBorrow(name) => locals
+            .get(name.name.as_str())
+            .map(|x| x.type_spec().clone()),
+        _ => None,
+    }
+}
+
+/// Operands of unknown type, like two literals, are treated as signed.
+pub(super) fn is_signed(r#type: Option<&TypeSpec>) -> bool {
+    let ty = match r#type {
+        Some(TypeSpec::Simple { name }) => primitive_type(&name.name),
+        _ => None,
+    };
+    !matches!(ty, Some(Ty::Integer(Signedness::Unsigned, _)))
+}
+
+fn is_literal(expr: &Expression) -> bool {
+    matches!(
+        expr,
+        Expression::Atomic(
+            AtomicExpr::ConstInt(_) | AtomicExpr::ConstFloat(_) | AtomicExpr::ConstChar(_)
+        )
+    )
+}
+
+/// Materialize a folded constant at its point of use.
+fn build_constant<'c, 'b>(
+    ctx: &'c BuildContext<'c>,
+    function: &FunctionContext<'c, 'b>,
+    block: &'b Block<'c>,
+    constant: &Constant,
+    locals: &HashMap<&str, Local<'c, 'b>>,
+) -> CodegenResult<Value<'c, 'b>> {
+    let r#type = build_type(ctx, &constant.r#type)?;
+    let attribute = match &constant.value {
+        ConstValue::Bool(value) => IntegerAttribute::new(*value as i64, r#type).into(),
+        ConstValue::Int(value) => IntegerAttribute::new(*value as i64, r#type).into(),
+        ConstValue::Float(value) => FloatAttribute::new(ctx, *value, r#type).into(),
+        ConstValue::Str(value) => {
+            let expr = Expression::Atomic(AtomicExpr::ConstStr(value.clone()));
+            return build_expr(ctx, function, block, &expr, None, locals);
+        }
+        ConstValue::Unit => IntegerAttribute::new(0, r#type).into(),
+    };
+
+    Ok(block
+        .append_operation(arith::constant(ctx, attribute, Location::unknown(ctx)))
+        .result(0)
+        .unwrap()
+        .into())
+}
@@ -0,0 +1,613 @@
+pub use self::target::{
+    module_to_assembly, module_to_llvm_ir, module_to_object, object_to_binary,
+    object_to_shared_lib, DataLayout, LLVMCompileError, TargetOptions,
+};
+use self::{
+    expressions::build_bool,
+    operations::build_global_store,
+    runtime::{build_runtime, printable_instances},
+    statements::{build_block, FunctionContext, Local},
+    typeclasses::{method_function, method_symbol, pervasive_typeclasses},
+    types::{build_foreign_param_type, build_type},
+};
+use crate::{
+    ast::{
+        FunctionDecl, FunctionDef, ModuleDecl, ModuleDeclItem, ModuleDef, ModuleDefItem, Param,
+        RecordDecl, TypeClassDecl, TypeParam, TypeSpec, UnionDecl,
+    },
+    backend::pass_manager::run_pass_manager,
+    capability_check,
+    constant_eval::{self, Constant},
+    embed_check,
+    error::{CodegenError, CodegenResult},
+    lexer, linearity_check, literal_check, pragma_check, universe_check,
+};
+use chumsky::{IterParser, Parser};
+use melior::{
+    dialect::{arith, func, llvm, memref, DialectRegistry},
+    ir::{
+        attribute::{FlatSymbolRefAttribute, IntegerAttribute, StringAttribute, TypeAttribute},
+        operation::OperationBuilder,
+        r#type::{FunctionType, IntegerType, MemRefType},
+        Block, Identifier, Location, Module, Region, Type,
+    },
+    utility::register_all_llvm_translations,
+    Context,
+};
+use std::{
+    collections::{HashMap, HashSet},
+    ops::Deref,
+    path::Path,
+    sync::Mutex,
+};
+
+mod builtins;
+mod expressions;
+mod operations;
+mod runtime;
+mod statements;
+mod target;
+mod typeclasses;
+mod types;
+
+struct BuildContext<'c> {
+    context: &'c Context,
+    module: Module<'c>,
+
+    functions: HashMap<String, FunctionSymbol>,
+    constants: HashMap<String, Constant>,
+    records: HashMap<String, RecordDecl>,
+    unions: HashMap<String, UnionDecl>,
+    /// The union each case constructor belongs to.
+    cases: HashMap<String, String>,
+    /// The typeclass each method belongs to.
+    methods: HashMap<String, TypeClassDecl>,
+    instances: Vec<Instance>,
+    data_layout: DataLayout,
+    externals: Mutex<HashSet<String>>,
+    literal_str: Mutex<HashMap<String, usize>>,
+}
+
+/// How calls to an Austral function are lowered.
+struct FunctionSymbol {
+    /// The name of the `func.func`, which is prefixed with the name of the module unless the
+    /// function is the entrypoint or a foreign import. Exported functions are also given their
+    /// external name by a wrapper.
+    symbol: String,
+    params: Vec<Param>,
+    ret_type: TypeSpec,
+    /// Whether the function is defined in another module, and must be declared before calling it.
+    is_imported: bool,
+    /// Whether the function is a foreign import, whose span parameters are lowered to pointers.
+    is_foreign: bool,
+}
+
+impl FunctionSymbol {
+    fn new(module: &str, decl: &FunctionDecl, is_imported: bool) -> Self {
+        let foreign_import = pragma_check::foreign_import(&decl.pragmas);
+        let is_foreign = foreign_import.is_some();
+        let symbol = foreign_import.unwrap_or_else(|| match decl.name.name.as_str() {
+            "main" if !is_imported => decl.name.name.clone(),
+            name => format!("{module}__{name}"),
+        });
+
+        Self {
+            symbol,
+            params: decl.params.clone(),
+            ret_type: decl.ret_type.clone(),
+            is_imported,
+            is_foreign,
+        }
+    }
+}
+
+/// An instance of a typeclass. Its methods are registered in `functions` under their symbols.
+struct Instance {
+    typeclass: String,
+    type_params: Vec<TypeParam>,
+    arg: TypeSpec,
+    /// The symbol of each method.
+    methods: HashMap<String, String>,
+}
+
+impl<'c> Deref for BuildContext<'c> {
+    type Target = Context;
+
+    fn deref(&self) -> &Self::Target {
+        self.context
+    }
+}
+
+pub fn compile<'c>(
+    context: &'c Context,
+    root: &ModuleDef,
+    interfaces: &[ModuleDecl],
+) -> CodegenResult<Module<'c>> {
+    compile_for_target(context, root, interfaces, &TargetOptions::host())
+}
+
+/// Compile a module whose records and unions are laid out for `target`.
+pub fn compile_for_target<'c>(
+    context: &'c Context,
+    root: &ModuleDef,
+    interfaces: &[ModuleDecl],
+    target: &TargetOptions,
+) -> CodegenResult<Module<'c>> {
+    let mut functions = HashMap::new();
+    for import in &root.imports {
+        let module_name = import
+            .module
+            .iter()
+            .map(|x| x.name.as_str())
+            .collect::<Vec<_>>()
+            .join(".");
+        let Some(interface) = interfaces.iter().find(|x| x.name.name == module_name) else {
+            continue;
+        };
+
+        for symbol in &import.symbols {
+            let decl = interface.contents.iter().find_map(|item| match item {
+                ModuleDeclItem::Function(decl) if decl.name == symbol.import_name => Some(decl),
+                _ => None,
+            });
+            if let Some(decl) = decl {
+                let alias = symbol.rename_into.as_ref().unwrap_or(&symbol.import_name);
+                functions.insert(
+                    alias.name.clone(),
+                    FunctionSymbol::new(&module_name, decl, true),
+                );
+            }
+        }
+    }
+    for item in &root.contents {
+        if let ModuleDefItem::Function(def) = item {
+            functions.insert(
+                def.name.name.clone(),
+                FunctionSymbol::new(&root.name.name, def, false),
+            );
+        }
+    }
+
+    let methods = pervasive_typeclasses()
+        .into_iter()
+        .chain(root.contents.iter().filter_map(|item| {
+            match item {
+                ModuleDefItem::TypeClass(def) => Some(TypeClassDecl {
+                    doc_string: def.doc_string.clone(),
+                    pragmas: def.pragmas.clone(),
+                    name: def.name.clone(),
+                    type_param: def.type_param.clone(),
+                    methods: def
+                        .methods
+                        .iter()
+                        .map(|method| method.decl.clone())
+                        .collect(),
+                }),
+                _ => None,
+            }
+        }))
+        .flat_map(|decl| {
+            decl.methods
+                .iter()
+                .map(|method| (method.name.name.clone(), decl.clone()))
+                .collect::<Vec<_>>()
+        })
+        .collect();
+
+    let mut instances = printable_instances(&mut functions);
+    for item in &root.contents {
+        if let ModuleDefItem::Instance(def) = item {
+            let methods = def
+                .methods
+                .iter()
+                .map(|method| {
+                    let symbol = method_symbol(&def.name.name, &method.decl.name.name, &def.arg);
+                    let function = FunctionSymbol {
+                        symbol: symbol.clone(),
+                        params: method.decl.params.clone(),
+                        ret_type: method.decl.ret_ty.clone(),
+                        is_imported: false,
+                        is_foreign: false,
+                    };
+                    functions.insert(symbol.clone(), function);
+                    (method.decl.name.name.clone(), symbol)
+                })
+                .collect();
+            instances.push(Instance {
+                typeclass: def.name.name.clone(),
+                type_params: def.type_params.clone(),
+                arg: def.arg.clone(),
+                methods,
+            });
+        }
+    }
+
+    let constants = constant_eval::evaluate_module(root)?;
+    let records = root
+        .contents
+        .iter()
+        .filter_map(|item| match item {
+            ModuleDefItem::Record(decl) => Some((decl.name.name.clone(), decl.clone())),
+            _ => None,
+        })
+        .collect();
+
+    let unions = pervasive_unions()
+        .into_iter()
+        .chain(root.contents.iter().filter_map(|item| match item {
+            ModuleDefItem::Union(decl) => Some(decl.clone()),
+            _ => None,
+        }))
+        .map(|decl| (decl.name.name.clone(), decl))
+        .collect::<HashMap<_, _>>();
+    let cases = unions
+        .values()
+        .flat_map(|decl| {
+            decl.cases
+                .iter()
+                .map(|case| (case.name.name.clone(), decl.name.name.clone()))
+        })
+        .collect();
+
+    // Layouts are computed here for records and unions, so the module must be lowered with the
+    // same data layout.
+    let data_layout =
+        DataLayout::new(target).map_err(|error| CodegenError::Target(error.to_string()))?;
+    let mut module = Module::new(Location::unknown(context));
+    module.as_operation_mut().set_attribute(
+        "llvm.data_layout",
+        StringAttribute::new(context, &data_layout.description).into(),
+    );
+
+    let build_context = BuildContext {
+        context,
+        module,
+
+        functions,
+        constants,
+        records,
+        unions,
+        cases,
+        methods,
+        instances,
+        data_layout,
+        externals: Mutex::new(HashSet::default()),
+        literal_str: Mutex::new(HashMap::default()),
+    };
+
+    build_runtime(&build_context);
+
+    for module_item in &root.contents {
+        match module_item {
+            ModuleDefItem::Function(data) => compile_function(&build_context, data)?,
+            // Constants are folded into every use.
+            ModuleDefItem::Constant(_) => {}
+            ModuleDefItem::Instance(def) => {
+                for method in &def.methods {
+                    compile_function(&build_context, &method_function(def, method))?;
+                }
+            }
+            // Records and unions only exist as the types of values, and typeclasses to resolve
+            // method calls.
+            ModuleDefItem::Record(_) | ModuleDefItem::Union(_) | ModuleDefItem::TypeClass(_) => {}
+            ModuleDefItem::Type(decl) => {
+                return Err(CodegenError::OpaqueType(decl.name.name.clone()))
+            }
+        }
+    }
+
+    Ok(build_context.module)
+}
+
+/// The unions every module can use without importing them.
+const PERVASIVE_UNIONS: &str = "
+    union Option[T: Type]: Type is
+        case None;
+        case Some is
+            value: T;
+    end;
+
+    union Either[L: Type, R: Type]: Type is
+        case Left is
+            left: L;
+        case Right is
+            right: R;
+    end;
+";
+
+fn pervasive_unions() -> Vec<UnionDecl> {
+    let tokens = lexer::lex(PERVASIVE_UNIONS)
+        .map(|(token, _span)| token)
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+
+    let decls = UnionDecl::parser()
+        .repeated()
+        .collect::<Vec<_>>()
+        .parse(&tokens)
+        .into_result();
+    decls.unwrap()
+}
+
+/// Declare an external C function, unless it has already been declared.
+fn declare_external(ctx: &BuildContext<'_>, name: &str, inputs: &[Type], results: &[Type]) {
+    if !ctx.externals.lock().unwrap().insert(name.to_string()) {
+        return;
+    }
+
+    ctx.module.body().append_operation(func::func(
+        ctx,
+        StringAttribute::new(ctx, name),
+        TypeAttribute::new(FunctionType::new(ctx, inputs, results).into()),
+        Region::new(),
+        &[(
+            Identifier::new(ctx, "sym_visibility"),
+            StringAttribute::new(ctx, "private").into(),
+        )],
+        Location::unknown(ctx),
+    ));
+}
+
+fn compile_function(ctx: &BuildContext<'_>, root: &FunctionDef) -> CodegenResult<()> {
+    if let Some(external_name) = pragma_check::foreign_import(&root.pragmas) {
+        let arg_types = root
+            .params
+            .iter()
+            .map(|param| build_foreign_param_type(ctx, &param.r#type))
+            .collect::<CodegenResult<Vec<_>>>()?;
+        let ret_type = build_type(ctx, &root.ret_type)?;
+
+        declare_external(ctx, &external_name, &arg_types, &[ret_type]);
+        return Ok(());
+    }
+
+    let region = Region::new();
+
+    // The entrypoint is called by the C runtime with the process arguments, which are saved for
+    // the runtime. The root capability is materialized here instead of being passed in.
+    let is_main = root.name.name == "main";
+
+    let arg_types = if is_main {
+        vec![
+            IntegerType::new(ctx, 32).into(),
+            llvm::r#type::opaque_pointer(ctx),
+        ]
+    } else {
+        root.params
+            .iter()
+            .map(|param| build_type(ctx, &param.r#type))
+            .collect::<CodegenResult<Vec<Type>>>()?
+    };
+    let ret_type = build_type(ctx, &root.ret_type)?;
+
+    let block = region.append_block(Block::new(
+        &arg_types
+            .iter()
+            .map(|r#type| (*r#type, Location::unknown(ctx)))
+            .collect::<Vec<_>>(),
+    ));
+    let mut locals = HashMap::new();
+
+    if is_main {
+        let argc = block
+            .append_operation(arith::extsi(
+                block.argument(0).unwrap().into(),
+                IntegerType::new(ctx, 64).into(),
+                Location::unknown(ctx),
+            ))
+            .result(0)
+            .unwrap()
+            .into();
+        let argv = block
+            .append_operation(
+                OperationBuilder::new("llvm.ptrtoint", Location::unknown(ctx))
+                    .add_operands(&[block.argument(1).unwrap().into()])
+                    .add_results(&[IntegerType::new(ctx, 64).into()])
+                    .build()
+                    .unwrap(),
+            )
+            .result(0)
+            .unwrap()
+            .into();
+        build_global_store(ctx, &block, "au_argc", argc);
+        build_global_store(ctx, &block, "au_argv", argv);
+
+        if let Some(param) = root.params.first() {
+            let value = block
+                .append_operation(arith::constant(
+                    ctx,
+                    IntegerAttribute::new(1, build_type(ctx, &param.r#type)?).into(),
+                    Location::unknown(ctx),
+                ))
+                .result(0)
+                .unwrap()
+                .into();
+            let local = Local::Value {
+                value,
+                r#type: param.r#type.clone(),
+            };
+            locals.insert(param.name.name.as_str(), local);
+        }
+    } else {
+        for (i, param) in root.params.iter().enumerate() {
+            let local = Local::Value {
+                value: block.argument(i).unwrap().into(),
+                r#type: param.r#type.clone(),
+            };
+            locals.insert(param.name.name.as_str(), local);
+        }
+    }
+
+    let flag_type = MemRefType::new(IntegerType::new(ctx, 1).into(), &[], None, None);
+    let return_flag = block
+        .append_operation(memref::alloca(
+            ctx,
+            flag_type,
+            &[],
+            &[],
+            None,
+            Location::unknown(ctx),
+        ))
+        .result(0)
+        .unwrap()
+        .into();
+    let return_slot = block
+        .append_operation(memref::alloca(
+            ctx,
+            MemRefType::new(ret_type, &[], None, None),
+            &[],
+            &[],
+            None,
+            Location::unknown(ctx),
+        ))
+        .result(0)
+        .unwrap()
+        .into();
+    let k0 = build_bool(ctx, &block, false);
+    block.append_operation(memref::store(k0, return_flag, &[], Location::unknown(ctx)));
+
+    let function = FunctionContext {
+        name: root.name.name.clone(),
+        entry: &block,
+        ret_type,
+        return_flag,
+        return_slot,
+    };
+    build_block(ctx, &function, &block, &root.body, &locals)?;
+
+    let value = block
+        .append_operation(memref::load(return_slot, &[], Location::unknown(ctx)))
+        .result(0)
+        .unwrap()
+        .into();
+    block.append_operation(func::r#return(&[value], Location::unknown(ctx)));
+
+    let symbol = &ctx.functions[&root.name.name].symbol;
+    ctx.module.body().append_operation(func::func(
+        ctx,
+        StringAttribute::new(ctx, symbol),
+        TypeAttribute::new(FunctionType::new(ctx, &arg_types, &[ret_type]).into()),
+        region,
+        &[],
+        Location::unknown(ctx),
+    ));
+
+    if let Some(external_name) = pragma_check::foreign_export(&root.pragmas) {
+        build_export(ctx, &external_name, symbol, &arg_types, ret_type);
+    }
+
+    Ok(())
+}
+
+/// Define a function under the external name of an exported function, which forwards to it. Other
+/// Austral modules only know the function from its interface, and call its mangled symbol.
+fn build_export<'c>(
+    ctx: &'c BuildContext<'c>,
+    external_name: &str,
+    symbol: &str,
+    arg_types: &[Type<'c>],
+    ret_type: Type<'c>,
+) {
+    let region = Region::new();
+    let block = region.append_block(Block::new(
+        &arg_types
+            .iter()
+            .map(|r#type| (*r#type, Location::unknown(ctx)))
+            .collect::<Vec<_>>(),
+    ));
+
+    let args = (0..arg_types.len())
+        .map(|i| block.argument(i).unwrap().into())
+        .collect::<Vec<_>>();
+    let value = block
+        .append_operation(func::call(
+            ctx,
+            FlatSymbolRefAttribute::new(ctx, symbol),
+            &args,
+            &[ret_type],
+            Location::unknown(ctx),
+        ))
+        .result(0)
+        .unwrap()
+        .into();
+    block.append_operation(func::r#return(&[value], Location::unknown(ctx)));
+
+    ctx.module.body().append_operation(func::func(
+        ctx,
+        StringAttribute::new(ctx, external_name),
+        TypeAttribute::new(FunctionType::new(ctx, arg_types, &[ret_type]).into()),
+        region,
+        &[],
+        Location::unknown(ctx),
+    ));
+}
+
+/// Run the semantic checks on a module body before compiling it, looking up imported declarations
+/// in `interfaces`.
+pub fn check(
+    root: &ModuleDef,
+    interfaces: &[ModuleDecl],
+) -> Result<(), Box<dyn std::error::Error>> {
+    universe_check::check_module_def(root, interfaces)?;
+    pragma_check::check_module_def(root)?;
+    constant_eval::evaluate_module(root)?;
+    literal_check::check_module_def(root)?;
+    capability_check::check_module_def(root)?;
+    linearity_check::check_module_def(root)?;
+    embed_check::check_module_def(root)?;
+
+    Ok(())
+}
+
+pub fn compile_to_binary(
+    input: &str,
+    interfaces: &[ModuleDecl],
+    is_library: bool,
+    output_filename: &Path,
+    target: &TargetOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let tokens = lexer::lex(input)
+        .map(|(token, _span)| token)
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+
+    let ast = ModuleDef::parser().parse(&tokens).into_result().unwrap();
+    check(&ast, interfaces)?;
+
+    let context = Context::new();
+    context.append_dialect_registry(&{
+        let dialect_registry = DialectRegistry::new();
+        melior::utility::register_all_dialects(&dialect_registry);
+        dialect_registry
+    });
+    register_all_llvm_translations(&context);
+    context.load_all_available_dialects();
+
+    let mut module = compile_for_target(&context, &ast, interfaces, target)?;
+    run_pass_manager(&context, &mut module)?;
+
+    link_module(&module, is_library, output_filename, target)
+}
+
+/// Link a module that already went through the pass manager into a binary or shared library.
+pub fn link_module(
+    module: &Module<'_>,
+    is_library: bool,
+    output_filename: &Path,
+    target: &TargetOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    // The system linker and C library are the host's, so binaries for other targets would be
+    // linked against the wrong ones.
+    if !target.is_host() {
+        return Err(CodegenError::CrossLinking(target.triple.clone()).into());
+    }
+
+    let object = module_to_object(module, target)?;
+    if is_library {
+        object_to_shared_lib(&object, output_filename)?;
+    } else {
+        object_to_binary(&object, output_filename)?;
+    }
+
+    Ok(())
+}
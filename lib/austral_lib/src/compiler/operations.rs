@@ -0,0 +1,372 @@
+use super::{expressions::build_integer, types::string_type, BuildContext};
+use melior::{
+    dialect::{func, index, llvm, memref},
+    ir::{
+        attribute::{
+            DenseElementsAttribute, DenseI32ArrayAttribute, DenseI64ArrayAttribute,
+            FlatSymbolRefAttribute, IntegerAttribute, TypeAttribute,
+        },
+        operation::OperationBuilder,
+        r#type::{IntegerType, MemRefType, RankedTensorType},
+        Block, Identifier, Location, Type, Value, ValueLike,
+    },
+};
+use std::collections::hash_map::Entry;
+
+pub(super) fn build_undef<'c, 'b>(
+    ctx: &'c BuildContext<'c>,
+    block: &'b Block<'c>,
+    r#type: Type<'c>,
+) -> Value<'c, 'b> {
+    block
+        .append_operation(
+            OperationBuilder::new("llvm.mlir.undef", Location::unknown(ctx))
+                .add_results(&[r#type])
+                .build()
+                .unwrap(),
+        )
+        .result(0)
+        .unwrap()
+        .into()
+}
+
+pub(super) fn build_extract_value<'c, 'b>(
+    ctx: &'c BuildContext<'c>,
+    block: &'b Block<'c>,
+    container: Value<'c, 'b>,
+    index: usize,
+    result_type: Type<'c>,
+) -> Value<'c, 'b> {
+    block
+        .append_operation(
+            OperationBuilder::new("llvm.extractvalue", Location::unknown(ctx))
+                .add_operands(&[container])
+                .add_attributes(&[(
+                    Identifier::new(ctx, "position"),
+                    DenseI64ArrayAttribute::new(ctx, &[index as i64]).into(),
+                )])
+                .add_results(&[result_type])
+                .build()
+                .unwrap(),
+        )
+        .result(0)
+        .unwrap()
+        .into()
+}
+
+pub(super) fn build_insert_value<'c, 'b>(
+    ctx: &'c BuildContext<'c>,
+    block: &'b Block<'c>,
+    container: Value<'c, 'b>,
+    index: usize,
+    value: Value<'c, 'b>,
+) -> Value<'c, 'b> {
+    block
+        .append_operation(
+            OperationBuilder::new("llvm.insertvalue", Location::unknown(ctx))
+                .add_operands(&[container, value])
+                .add_attributes(&[(
+                    Identifier::new(ctx, "position"),
+                    DenseI64ArrayAttribute::new(ctx, &[index as i64]).into(),
+                )])
+                .add_results(&[container.r#type()])
+                .build()
+                .unwrap(),
+        )
+        .result(0)
+        .unwrap()
+        .into()
+}
+
+/// The address of a field of the struct `ptr` points to.
+pub(super) fn build_slot_ptr<'c, 'b>(
+    ctx: &'c BuildContext<'c>,
+    block: &'b Block<'c>,
+    ptr: Value<'c, 'b>,
+    r#type: Type<'c>,
+    index: usize,
+) -> Value<'c, 'b> {
+    block
+        .append_operation(
+            OperationBuilder::new("llvm.getelementptr", Location::unknown(ctx))
+                .add_operands(&[ptr])
+                .add_attributes(&[
+                    (
+                        Identifier::new(ctx, "rawConstantIndices"),
+                        DenseI32ArrayAttribute::new(ctx, &[0, index as i32]).into(),
+                    ),
+                    (
+                        Identifier::new(ctx, "elem_type"),
+                        TypeAttribute::new(r#type).into(),
+                    ),
+                ])
+                .add_results(&[llvm::r#type::opaque_pointer(ctx)])
+                .build()
+                .unwrap(),
+        )
+        .result(0)
+        .unwrap()
+        .into()
+}
+
+/// The address of the element `index` places after the one `ptr` points to.
+pub(super) fn build_element_ptr<'c, 'b>(
+    ctx: &'c BuildContext<'c>,
+    block: &'b Block<'c>,
+    ptr: Value<'c, 'b>,
+    r#type: Type<'c>,
+    index: Value<'c, 'b>,
+) -> Value<'c, 'b> {
+    block
+        .append_operation(
+            OperationBuilder::new("llvm.getelementptr", Location::unknown(ctx))
+                .add_operands(&[ptr, index])
+                .add_attributes(&[
+                    // Marks the index as taken from the operands.
+                    (
+                        Identifier::new(ctx, "rawConstantIndices"),
+                        DenseI32ArrayAttribute::new(ctx, &[i32::MIN]).into(),
+                    ),
+                    (
+                        Identifier::new(ctx, "elem_type"),
+                        TypeAttribute::new(r#type).into(),
+                    ),
+                ])
+                .add_results(&[llvm::r#type::opaque_pointer(ctx)])
+                .build()
+                .unwrap(),
+        )
+        .result(0)
+        .unwrap()
+        .into()
+}
+
+/// A pointer to the bytes of a string literal. Literals are emitted once per module as read-only
+/// data, and end with a NUL byte which isn't part of their length so that they can be passed to
+/// C functions.
+pub(super) fn build_literal_str<'c, 'b>(
+    ctx: &'c BuildContext<'c>,
+    block: &'b Block<'c>,
+    value: &str,
+) -> Value<'c, 'b> {
+    let mut literal_str = ctx.literal_str.lock().unwrap();
+    let len = value.len() + 1;
+
+    let num_literals = literal_str.len();
+    let literal_idx = match literal_str.entry(value.to_string()) {
+        Entry::Occupied(entry) => *entry.get(),
+        Entry::Vacant(entry) => {
+            ctx.module.body().append_operation(memref::global(
+                ctx,
+                &format!("LiteralStr{num_literals}"),
+                Some("private"),
+                MemRefType::new(IntegerType::new(ctx, 8).into(), &[len as i64], None, None),
+                Some(
+                    DenseElementsAttribute::new(
+                        RankedTensorType::new(&[len as u64], IntegerType::new(ctx, 8).into(), None)
+                            .into(),
+                        &value
+                            .bytes()
+                            .chain([0])
+                            .map(|x| {
+                                IntegerAttribute::new(x as i64, IntegerType::new(ctx, 8).into())
+                                    .into()
+                            })
+                            .collect::<Vec<_>>(),
+                    )
+                    .unwrap()
+                    .into(),
+                ),
+                true,
+                None,
+                Location::unknown(ctx),
+            ));
+
+            *entry.insert(num_literals)
+        }
+    };
+
+    let value = block
+        .append_operation(memref::get_global(
+            ctx,
+            &format!("LiteralStr{literal_idx}"),
+            MemRefType::new(IntegerType::new(ctx, 8).into(), &[len as i64], None, None),
+            Location::unknown(ctx),
+        ))
+        .result(0)
+        .unwrap()
+        .into();
+    build_memref_ptr(ctx, block, value)
+}
+
+/// The address of the data of a memref, as an LLVM pointer.
+pub(super) fn build_memref_ptr<'c, 'b>(
+    ctx: &'c BuildContext<'c>,
+    block: &'b Block<'c>,
+    memref: Value<'c, 'b>,
+) -> Value<'c, 'b> {
+    let value = block
+        .append_operation(
+            OperationBuilder::new(
+                "memref.extract_aligned_pointer_as_index",
+                Location::unknown(ctx),
+            )
+            .add_operands(&[memref])
+            .add_results(&[Type::index(ctx)])
+            .build()
+            .unwrap(),
+        )
+        .result(0)
+        .unwrap()
+        .into();
+    let value = block
+        .append_operation(index::castu(
+            value,
+            IntegerType::new(ctx, 64).into(),
+            Location::unknown(ctx),
+        ))
+        .result(0)
+        .unwrap()
+        .into();
+    block
+        .append_operation(
+            OperationBuilder::new("llvm.inttoptr", Location::unknown(ctx))
+                .add_operands(&[value])
+                .add_results(&[llvm::r#type::opaque_pointer(ctx)])
+                .build()
+                .unwrap(),
+        )
+        .result(0)
+        .unwrap()
+        .into()
+}
+
+pub(super) fn build_llvm_store<'c, 'b>(
+    ctx: &'c BuildContext<'c>,
+    block: &'b Block<'c>,
+    value: Value<'c, 'b>,
+    ptr: Value<'c, 'b>,
+) {
+    block.append_operation(
+        OperationBuilder::new("llvm.store", Location::unknown(ctx))
+            .add_operands(&[value, ptr])
+            .build()
+            .unwrap(),
+    );
+}
+
+pub(super) fn build_llvm_load<'c, 'b>(
+    ctx: &'c BuildContext<'c>,
+    block: &'b Block<'c>,
+    ptr: Value<'c, 'b>,
+    r#type: Type<'c>,
+) -> Value<'c, 'b> {
+    block
+        .append_operation(
+            OperationBuilder::new("llvm.load", Location::unknown(ctx))
+                .add_operands(&[ptr])
+                .add_results(&[r#type])
+                .build()
+                .unwrap(),
+        )
+        .result(0)
+        .unwrap()
+        .into()
+}
+
+pub(super) fn build_span<'c, 'b>(
+    ctx: &'c BuildContext<'c>,
+    block: &'b Block<'c>,
+    buffer: Value<'c, 'b>,
+    len: Value<'c, 'b>,
+) -> Value<'c, 'b> {
+    let span = build_undef(ctx, block, string_type(ctx));
+    let span = build_insert_value(ctx, block, span, 0, buffer);
+    build_insert_value(ctx, block, span, 1, len)
+}
+
+pub(super) fn build_call<'c, 'b>(
+    ctx: &'c BuildContext<'c>,
+    block: &'b Block<'c>,
+    name: &str,
+    args: &[Value<'c, 'b>],
+    results: &[Type<'c>],
+) -> Option<Value<'c, 'b>> {
+    block
+        .append_operation(func::call(
+            ctx,
+            FlatSymbolRefAttribute::new(ctx, name),
+            args,
+            results,
+            Location::unknown(ctx),
+        ))
+        .result(0)
+        .ok()
+        .map(Into::into)
+}
+
+pub(super) fn build_global_load<'c, 'b>(
+    ctx: &'c BuildContext<'c>,
+    block: &'b Block<'c>,
+    name: &str,
+) -> Value<'c, 'b> {
+    let global = block
+        .append_operation(memref::get_global(
+            ctx,
+            name,
+            MemRefType::new(IntegerType::new(ctx, 64).into(), &[], None, None),
+            Location::unknown(ctx),
+        ))
+        .result(0)
+        .unwrap()
+        .into();
+    block
+        .append_operation(memref::load(global, &[], Location::unknown(ctx)))
+        .result(0)
+        .unwrap()
+        .into()
+}
+
+pub(super) fn build_global_store<'c, 'b>(
+    ctx: &'c BuildContext<'c>,
+    block: &'b Block<'c>,
+    name: &str,
+    value: Value<'c, 'b>,
+) {
+    let global = block
+        .append_operation(memref::get_global(
+            ctx,
+            name,
+            MemRefType::new(IntegerType::new(ctx, 64).into(), &[], None, None),
+            Location::unknown(ctx),
+        ))
+        .result(0)
+        .unwrap()
+        .into();
+    block.append_operation(memref::store(value, global, &[], Location::unknown(ctx)));
+}
+
+/// Allocate a stack slot in the current block, for functions of the runtime which don't allocate
+/// within loops.
+pub(super) fn build_llvm_alloca<'c, 'b>(
+    ctx: &'c BuildContext<'c>,
+    block: &'b Block<'c>,
+    r#type: Type<'c>,
+) -> Value<'c, 'b> {
+    let k1 = build_integer(ctx, block, 1, IntegerType::new(ctx, 64).into());
+    block
+        .append_operation(
+            OperationBuilder::new("llvm.alloca", Location::unknown(ctx))
+                .add_operands(&[k1])
+                .add_attributes(&[(
+                    Identifier::new(ctx, "elem_type"),
+                    TypeAttribute::new(r#type).into(),
+                )])
+                .add_results(&[llvm::r#type::opaque_pointer(ctx)])
+                .build()
+                .unwrap(),
+        )
+        .result(0)
+        .unwrap()
+        .into()
+}
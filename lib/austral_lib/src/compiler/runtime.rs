@@ -0,0 +1,926 @@
+use super::{
+    declare_external,
+    expressions::{build_bool, build_integer},
+    operations::{
+        build_call, build_element_ptr, build_extract_value, build_global_load, build_literal_str,
+        build_llvm_alloca, build_llvm_load, build_llvm_store, build_span,
+    },
+    statements::FunctionContext,
+    typeclasses::method_symbol,
+    types::{build_type, string_type},
+    BuildContext, FunctionSymbol, Instance,
+};
+use crate::{
+    ast::{Ident, Param, TypeParam, TypeSpec, Universe},
+    lexer,
+    r#type::{Signedness, Ty},
+    type_system::{integer_bits, primitive_type},
+};
+use melior::{
+    dialect::{
+        arith::{self, CmpfPredicate, CmpiPredicate},
+        func, llvm, memref, scf,
+    },
+    ir::{
+        attribute::{
+            DenseElementsAttribute, FloatAttribute, IntegerAttribute, StringAttribute,
+            TypeAttribute,
+        },
+        operation::OperationBuilder,
+        r#type::{FunctionType, IntegerType, MemRefType, RankedTensorType},
+        Block, Identifier, Location, Region, Type, Value,
+    },
+};
+use std::collections::HashMap;
+
+/// The types the runtime implements `Printable` for: the primitive types and spans of bytes in
+/// any region.
+fn printable_types() -> Vec<(Vec<TypeParam>, TypeSpec)> {
+    const PRIMITIVES: &[&str] = &[
+        "Unit", "Bool", "Nat8", "Nat16", "Nat32", "Nat64", "Int8", "Int16", "Int32", "Int64",
+        "Index", "ByteSize", "Float32", "Float64",
+    ];
+
+    let region = TypeParam {
+        name: Ident::new("R"),
+        universe: Universe(lexer::Universe::Region),
+        params: Vec::new(),
+    };
+    let nat8 = Box::new(TypeSpec::Simple {
+        name: Ident::new("Nat8"),
+    });
+    let r = Box::new(TypeSpec::Simple {
+        name: Ident::new("R"),
+    });
+
+    PRIMITIVES
+        .iter()
+        .map(|name| {
+            let name = Ident::new(*name);
+            (Vec::new(), TypeSpec::Simple { name })
+        })
+        .chain([
+            (
+                vec![region.clone()],
+                TypeSpec::SpanRead {
+                    lhs: nat8.clone(),
+                    rhs: r.clone(),
+                },
+            ),
+            (vec![region], TypeSpec::SpanWrite { lhs: nat8, rhs: r }),
+        ])
+        .collect()
+}
+
+pub(super) fn printable_instances(
+    functions: &mut HashMap<String, FunctionSymbol>,
+) -> Vec<Instance> {
+    printable_types()
+        .into_iter()
+        .map(|(type_params, arg)| {
+            let symbol = method_symbol("au_Printable", "print", &arg);
+            let function = FunctionSymbol {
+                symbol: symbol.clone(),
+                params: vec![Param {
+                    name: Ident::new("value"),
+                    r#type: arg.clone(),
+                }],
+                ret_type: TypeSpec::Simple {
+                    name: Ident::new("Unit"),
+                },
+                is_imported: false,
+                is_foreign: false,
+            };
+            functions.insert(symbol.clone(), function);
+
+            Instance {
+                typeclass: "Printable".to_string(),
+                type_params,
+                arg,
+                methods: HashMap::from([("print".to_string(), symbol)]),
+            }
+        })
+        .collect()
+}
+
+pub(super) const STDOUT: i64 = 1;
+const STDERR: i64 = 2;
+
+/// Write the textual form of a value of a type in [`printable_types`] to standard output.
+fn build_print_value<'c, 'b>(
+    ctx: &'c BuildContext<'c>,
+    block: &'b Block<'c>,
+    value: Value<'c, 'b>,
+    r#type: &TypeSpec,
+) {
+    let fd = build_integer(ctx, block, STDOUT, IntegerType::new(ctx, 32).into());
+    let i64_type = IntegerType::new(ctx, 64).into();
+    let TypeSpec::Simple { name } = r#type else {
+        let buffer = build_extract_value(ctx, block, value, 0, llvm::r#type::opaque_pointer(ctx));
+        let len = build_extract_value(ctx, block, value, 1, i64_type);
+        build_call(ctx, block, "au_write", &[fd, buffer, len], &[]);
+        return;
+    };
+
+    match primitive_type(&name.name) {
+        Some(Ty::Unit) => build_write_str(ctx, block, fd, "nil"),
+        Some(Ty::Boolean) => build_write_select(ctx, block, fd, value, "true", "false"),
+        Some(Ty::Integer(signedness, width)) => {
+            let is_signed = matches!(signedness, Signedness::Signed);
+            let value = if integer_bits(width) == 64 {
+                value
+            } else if is_signed {
+                block
+                    .append_operation(arith::extsi(value, i64_type, Location::unknown(ctx)))
+                    .result(0)
+                    .unwrap()
+                    .into()
+            } else {
+                block
+                    .append_operation(arith::extui(value, i64_type, Location::unknown(ctx)))
+                    .result(0)
+                    .unwrap()
+                    .into()
+            };
+            let is_signed = build_bool(ctx, block, is_signed);
+            build_call(ctx, block, "au_write_int", &[fd, value, is_signed], &[]);
+        }
+        Some(Ty::SingleFloat) => {
+            let value = block
+                .append_operation(arith::extf(
+                    value,
+                    Type::float64(ctx),
+                    Location::unknown(ctx),
+                ))
+                .result(0)
+                .unwrap()
+                .into();
+            build_call(ctx, block, "au_write_float", &[fd, value], &[]);
+        }
+        Some(Ty::DoubleFloat) => {
+            build_call(ctx, block, "au_write_float", &[fd, value], &[]);
+        }
+        _ => unreachable!("`{}` is not printable", name.name),
+    }
+}
+
+/// Write one of two strings known at compile time, selecting it rather than branching.
+fn build_write_select<'c, 'b>(
+    ctx: &'c BuildContext<'c>,
+    block: &'b Block<'c>,
+    fd: Value<'c, 'b>,
+    condition: Value<'c, 'b>,
+    value_true: &str,
+    value_false: &str,
+) {
+    let i64_type = IntegerType::new(ctx, 64).into();
+    let select = |value_true, value_false| -> Value<'c, 'b> {
+        block
+            .append_operation(arith::select(
+                condition,
+                value_true,
+                value_false,
+                Location::unknown(ctx),
+            ))
+            .result(0)
+            .unwrap()
+            .into()
+    };
+    let buffer = select(
+        build_literal_str(ctx, block, value_true),
+        build_literal_str(ctx, block, value_false),
+    );
+    let len = select(
+        build_integer(ctx, block, value_true.len() as i64, i64_type),
+        build_integer(ctx, block, value_false.len() as i64, i64_type),
+    );
+    build_call(ctx, block, "au_write", &[fd, buffer, len], &[]);
+}
+
+/// Write a string known at compile time to a standard stream.
+pub(super) fn build_write_str<'c, 'b>(
+    ctx: &'c BuildContext<'c>,
+    block: &'b Block<'c>,
+    fd: Value<'c, 'b>,
+    value: &str,
+) {
+    let buffer = build_literal_str(ctx, block, value);
+    let len = build_integer(
+        ctx,
+        block,
+        value.len() as i64,
+        IntegerType::new(ctx, 64).into(),
+    );
+    build_call(ctx, block, "au_write", &[fd, buffer, len], &[]);
+}
+
+/// Define the runtime every program is linked with. Its functions are private, so each module
+/// has its own copy and they don't clash at link time.
+///
+/// - `au_argc` and `au_argv` hold the arguments of the process, saved by `main`. The pointer is
+///   kept as an integer.
+/// - `au_argument_count() -> i64` and `au_nth_argument(i64) -> string` read them. Arguments are
+///   returned without their NUL terminator.
+/// - `au_write(fd, ptr, len)` writes a whole buffer to a file descriptor.
+/// - `au_write_int(fd, i64, is_signed)` writes an integer in decimal.
+/// - `au_write_float(fd, f64)` writes a float with six decimals.
+/// - `au_Printable_print_T(T)` implement `Printable` for the types in [`printable_types`].
+/// - `au_abort(ptr, len, status)` writes a message and a newline to standard error and exits.
+pub(super) fn build_runtime(ctx: &BuildContext<'_>) {
+    let i1_type = IntegerType::new(ctx, 1).into();
+    let i8_type = IntegerType::new(ctx, 8).into();
+    let i32_type = IntegerType::new(ctx, 32).into();
+    let i64_type: Type = IntegerType::new(ctx, 64).into();
+    let ptr_type = llvm::r#type::opaque_pointer(ctx);
+
+    declare_external(ctx, "write", &[i32_type, ptr_type, i64_type], &[i64_type]);
+    declare_external(ctx, "exit", &[i32_type], &[]);
+    declare_external(ctx, "strlen", &[ptr_type], &[i64_type]);
+    declare_external(
+        ctx,
+        "fcvt",
+        &[Type::float64(ctx), i32_type, ptr_type, ptr_type],
+        &[ptr_type],
+    );
+
+    for name in ["au_argc", "au_argv"] {
+        ctx.module.body().append_operation(memref::global(
+            ctx,
+            name,
+            Some("private"),
+            MemRefType::new(i64_type, &[], None, None),
+            Some(
+                DenseElementsAttribute::new(
+                    RankedTensorType::new(&[], i64_type, None).into(),
+                    &[IntegerAttribute::new(0, i64_type).into()],
+                )
+                .unwrap()
+                .into(),
+            ),
+            false,
+            None,
+            Location::unknown(ctx),
+        ));
+    }
+
+    define_runtime_function(ctx, "au_argument_count", &[], &[i64_type], |block| {
+        let argc = build_global_load(ctx, block, "au_argc");
+        block.append_operation(func::r#return(&[argc], Location::unknown(ctx)));
+    });
+
+    define_runtime_function(
+        ctx,
+        "au_nth_argument",
+        &[i64_type],
+        &[string_type(ctx)],
+        |block| {
+            let index = block.argument(0).unwrap().into();
+            let argc = build_global_load(ctx, block, "au_argc");
+            let is_out_of_bounds = block
+                .append_operation(arith::cmpi(
+                    ctx,
+                    CmpiPredicate::Uge,
+                    index,
+                    argc,
+                    Location::unknown(ctx),
+                ))
+                .result(0)
+                .unwrap()
+                .into();
+            build_abort_if(
+                ctx,
+                block,
+                is_out_of_bounds,
+                "argument index out of bounds in `nthArgument`",
+            );
+
+            let argv = build_global_load(ctx, block, "au_argv");
+            let argv = block
+                .append_operation(
+                    OperationBuilder::new("llvm.inttoptr", Location::unknown(ctx))
+                        .add_operands(&[argv])
+                        .add_results(&[ptr_type])
+                        .build()
+                        .unwrap(),
+                )
+                .result(0)
+                .unwrap()
+                .into();
+            let arg = build_element_ptr(ctx, block, argv, ptr_type, index);
+            let arg = build_llvm_load(ctx, block, arg, ptr_type);
+            let len = build_call(ctx, block, "strlen", &[arg], &[i64_type])
+                .expect("call should have a result");
+
+            let string = build_span(ctx, block, arg, len);
+            block.append_operation(func::r#return(&[string], Location::unknown(ctx)));
+        },
+    );
+
+    // `write` may write less than it was asked to, so loop until the buffer is empty. Errors end
+    // the loop, since there is no better stream to report them on.
+    define_runtime_function(
+        ctx,
+        "au_write",
+        &[i32_type, ptr_type, i64_type],
+        &[],
+        |block| {
+            let fd = block.argument(0).unwrap().into();
+            let k0 = build_integer(ctx, block, 0, i64_type);
+
+            let before_block = Block::new(&[
+                (ptr_type, Location::unknown(ctx)),
+                (i64_type, Location::unknown(ctx)),
+            ]);
+            let buffer = before_block.argument(0).unwrap().into();
+            let len = before_block.argument(1).unwrap().into();
+            let has_more = before_block
+                .append_operation(arith::cmpi(
+                    ctx,
+                    CmpiPredicate::Sgt,
+                    len,
+                    k0,
+                    Location::unknown(ctx),
+                ))
+                .result(0)
+                .unwrap()
+                .into();
+            before_block.append_operation(scf::condition(
+                has_more,
+                &[buffer, len],
+                Location::unknown(ctx),
+            ));
+
+            let after_block = Block::new(&[
+                (ptr_type, Location::unknown(ctx)),
+                (i64_type, Location::unknown(ctx)),
+            ]);
+            let buffer = after_block.argument(0).unwrap().into();
+            let len = after_block.argument(1).unwrap().into();
+            let written = build_call(ctx, &after_block, "write", &[fd, buffer, len], &[i64_type])
+                .expect("call should have a result");
+            let failed = after_block
+                .append_operation(arith::cmpi(
+                    ctx,
+                    CmpiPredicate::Sle,
+                    written,
+                    k0,
+                    Location::unknown(ctx),
+                ))
+                .result(0)
+                .unwrap()
+                .into();
+            let written = after_block
+                .append_operation(arith::select(failed, len, written, Location::unknown(ctx)))
+                .result(0)
+                .unwrap()
+                .into();
+            let buffer = build_element_ptr(ctx, &after_block, buffer, i8_type, written);
+            let len = after_block
+                .append_operation(arith::subi(len, written, Location::unknown(ctx)))
+                .result(0)
+                .unwrap()
+                .into();
+            after_block.append_operation(scf::r#yield(&[buffer, len], Location::unknown(ctx)));
+
+            let before_region = Region::new();
+            before_region.append_block(before_block);
+            let after_region = Region::new();
+            after_region.append_block(after_block);
+            block.append_operation(scf::r#while(
+                &[
+                    block.argument(1).unwrap().into(),
+                    block.argument(2).unwrap().into(),
+                ],
+                &[ptr_type, i64_type],
+                before_region,
+                after_region,
+                Location::unknown(ctx),
+            ));
+            block.append_operation(func::r#return(&[], Location::unknown(ctx)));
+        },
+    );
+
+    // Digits are written backwards from the end of a buffer, which has room for the 20 digits of
+    // the largest `Nat64` and a sign. The magnitude of a negative number is computed with
+    // wrapping negation, which is correct for the minimum as an unsigned number.
+    define_runtime_function(
+        ctx,
+        "au_write_int",
+        &[i32_type, i64_type, i1_type],
+        &[],
+        |block| {
+            const LEN: i64 = 21;
+
+            let fd = block.argument(0).unwrap().into();
+            let value = block.argument(1).unwrap().into();
+            let is_signed = block.argument(2).unwrap().into();
+
+            let k0 = build_integer(ctx, block, 0, i64_type);
+            let k1 = build_integer(ctx, block, 1, i64_type);
+            let k10 = build_integer(ctx, block, 10, i64_type);
+            let buffer_len = build_integer(ctx, block, LEN, i64_type);
+            let buffer = build_llvm_alloca(ctx, block, llvm::r#type::array(i8_type, LEN as u32));
+
+            let is_negative = block
+                .append_operation(arith::cmpi(
+                    ctx,
+                    CmpiPredicate::Slt,
+                    value,
+                    k0,
+                    Location::unknown(ctx),
+                ))
+                .result(0)
+                .unwrap()
+                .into();
+            let is_negative = block
+                .append_operation(arith::andi(is_signed, is_negative, Location::unknown(ctx)))
+                .result(0)
+                .unwrap()
+                .into();
+            let negated = block
+                .append_operation(arith::subi(k0, value, Location::unknown(ctx)))
+                .result(0)
+                .unwrap()
+                .into();
+            let magnitude = block
+                .append_operation(arith::select(
+                    is_negative,
+                    negated,
+                    value,
+                    Location::unknown(ctx),
+                ))
+                .result(0)
+                .unwrap()
+                .into();
+
+            // A do-while loop: the before region writes a digit, so zero still gets one.
+            let before_block = Block::new(&[
+                (i64_type, Location::unknown(ctx)),
+                (i64_type, Location::unknown(ctx)),
+            ]);
+            let rest = before_block.argument(0).unwrap().into();
+            let position = before_block.argument(1).unwrap().into();
+            let position = before_block
+                .append_operation(arith::subi(position, k1, Location::unknown(ctx)))
+                .result(0)
+                .unwrap()
+                .into();
+            let digit = before_block
+                .append_operation(arith::remui(rest, k10, Location::unknown(ctx)))
+                .result(0)
+                .unwrap()
+                .into();
+            let digit = before_block
+                .append_operation(arith::trunci(digit, i8_type, Location::unknown(ctx)))
+                .result(0)
+                .unwrap()
+                .into();
+            let zero = build_integer(ctx, &before_block, i64::from(b'0'), i8_type);
+            let digit = before_block
+                .append_operation(arith::addi(digit, zero, Location::unknown(ctx)))
+                .result(0)
+                .unwrap()
+                .into();
+            let ptr = build_element_ptr(ctx, &before_block, buffer, i8_type, position);
+            build_llvm_store(ctx, &before_block, digit, ptr);
+            let rest = before_block
+                .append_operation(arith::divui(rest, k10, Location::unknown(ctx)))
+                .result(0)
+                .unwrap()
+                .into();
+            let has_more = before_block
+                .append_operation(arith::cmpi(
+                    ctx,
+                    CmpiPredicate::Ne,
+                    rest,
+                    k0,
+                    Location::unknown(ctx),
+                ))
+                .result(0)
+                .unwrap()
+                .into();
+            before_block.append_operation(scf::condition(
+                has_more,
+                &[rest, position],
+                Location::unknown(ctx),
+            ));
+
+            let after_block = Block::new(&[
+                (i64_type, Location::unknown(ctx)),
+                (i64_type, Location::unknown(ctx)),
+            ]);
+            after_block.append_operation(scf::r#yield(
+                &[
+                    after_block.argument(0).unwrap().into(),
+                    after_block.argument(1).unwrap().into(),
+                ],
+                Location::unknown(ctx),
+            ));
+
+            let before_region = Region::new();
+            before_region.append_block(before_block);
+            let after_region = Region::new();
+            after_region.append_block(after_block);
+            let position = block
+                .append_operation(scf::r#while(
+                    &[magnitude, buffer_len],
+                    &[i64_type, i64_type],
+                    before_region,
+                    after_region,
+                    Location::unknown(ctx),
+                ))
+                .result(1)
+                .unwrap()
+                .into();
+
+            // The sign always goes before the digits, but only counts when negative.
+            let sign_position = block
+                .append_operation(arith::subi(position, k1, Location::unknown(ctx)))
+                .result(0)
+                .unwrap()
+                .into();
+            let minus = build_integer(ctx, block, i64::from(b'-'), i8_type);
+            let ptr = build_element_ptr(ctx, block, buffer, i8_type, sign_position);
+            build_llvm_store(ctx, block, minus, ptr);
+            let position = block
+                .append_operation(arith::select(
+                    is_negative,
+                    sign_position,
+                    position,
+                    Location::unknown(ctx),
+                ))
+                .result(0)
+                .unwrap()
+                .into();
+
+            let start = build_element_ptr(ctx, block, buffer, i8_type, position);
+            let len = block
+                .append_operation(arith::subi(buffer_len, position, Location::unknown(ctx)))
+                .result(0)
+                .unwrap()
+                .into();
+            build_call(ctx, block, "au_write", &[fd, start, len], &[]);
+            block.append_operation(func::r#return(&[], Location::unknown(ctx)));
+        },
+    );
+
+    // Floats are written the way `printf("%f")` writes them. `fcvt` rounds to six decimals, and
+    // returns the digits along with the position of the decimal point within them. The position
+    // is negative for numbers below 0.1, and zeros then go between the point and the digits.
+    define_runtime_function(
+        ctx,
+        "au_write_float",
+        &[i32_type, Type::float64(ctx)],
+        &[],
+        |block| {
+            let fd = block.argument(0).unwrap().into();
+            let value = block.argument(1).unwrap().into();
+
+            let is_nan = block
+                .append_operation(arith::cmpf(
+                    ctx,
+                    CmpfPredicate::Uno,
+                    value,
+                    value,
+                    Location::unknown(ctx),
+                ))
+                .result(0)
+                .unwrap()
+                .into();
+            let is_negative = block
+                .append_operation(arith::cmpf(
+                    ctx,
+                    CmpfPredicate::Olt,
+                    value,
+                    block
+                        .append_operation(arith::constant(
+                            ctx,
+                            FloatAttribute::new(ctx, 0.0, Type::float64(ctx)).into(),
+                            Location::unknown(ctx),
+                        ))
+                        .result(0)
+                        .unwrap()
+                        .into(),
+                    Location::unknown(ctx),
+                ))
+                .result(0)
+                .unwrap()
+                .into();
+            let magnitude = block
+                .append_operation(arith::negf(value, Location::unknown(ctx)))
+                .result(0)
+                .unwrap()
+                .into();
+            let magnitude = block
+                .append_operation(arith::select(
+                    is_negative,
+                    magnitude,
+                    value,
+                    Location::unknown(ctx),
+                ))
+                .result(0)
+                .unwrap()
+                .into();
+            let is_infinite = block
+                .append_operation(arith::cmpf(
+                    ctx,
+                    CmpfPredicate::Oeq,
+                    magnitude,
+                    block
+                        .append_operation(arith::constant(
+                            ctx,
+                            FloatAttribute::new(ctx, f64::INFINITY, Type::float64(ctx)).into(),
+                            Location::unknown(ctx),
+                        ))
+                        .result(0)
+                        .unwrap()
+                        .into(),
+                    Location::unknown(ctx),
+                ))
+                .result(0)
+                .unwrap()
+                .into();
+
+            let write_finite = |block: &Block<'_>| {
+                let k0 = build_integer(ctx, block, 0, i64_type);
+                let k1 = build_integer(ctx, block, 1, i64_type);
+                let precision = build_integer(ctx, block, 6, i64_type);
+                let decimal_point = build_llvm_alloca(ctx, block, i32_type);
+                let sign = build_llvm_alloca(ctx, block, i32_type);
+                let digits = build_call(
+                    ctx,
+                    block,
+                    "fcvt",
+                    &[
+                        value,
+                        build_integer(ctx, block, 6, i32_type),
+                        decimal_point,
+                        sign,
+                    ],
+                    &[ptr_type],
+                )
+                .expect("call should have a result");
+                let len = build_call(ctx, block, "strlen", &[digits], &[i64_type])
+                    .expect("call should have a result");
+
+                let sign = build_llvm_load(ctx, block, sign, i32_type);
+                let is_negative = block
+                    .append_operation(arith::cmpi(
+                        ctx,
+                        CmpiPredicate::Ne,
+                        sign,
+                        build_integer(ctx, block, 0, i32_type),
+                        Location::unknown(ctx),
+                    ))
+                    .result(0)
+                    .unwrap()
+                    .into();
+                build_write_select(ctx, block, fd, is_negative, "-", "");
+
+                // The integral part, which is a zero when there are no digits for it.
+                let decimal_point = build_llvm_load(ctx, block, decimal_point, i32_type);
+                let decimal_point = block
+                    .append_operation(arith::extsi(
+                        decimal_point,
+                        i64_type,
+                        Location::unknown(ctx),
+                    ))
+                    .result(0)
+                    .unwrap()
+                    .into();
+                let integral_len = block
+                    .append_operation(arith::maxsi(decimal_point, k0, Location::unknown(ctx)))
+                    .result(0)
+                    .unwrap()
+                    .into();
+                let has_integral = block
+                    .append_operation(arith::cmpi(
+                        ctx,
+                        CmpiPredicate::Sgt,
+                        integral_len,
+                        k0,
+                        Location::unknown(ctx),
+                    ))
+                    .result(0)
+                    .unwrap()
+                    .into();
+                let zero = build_literal_str(ctx, block, "0");
+                let integral = block
+                    .append_operation(arith::select(
+                        has_integral,
+                        digits,
+                        zero,
+                        Location::unknown(ctx),
+                    ))
+                    .result(0)
+                    .unwrap()
+                    .into();
+                let len_written = block
+                    .append_operation(arith::select(
+                        has_integral,
+                        integral_len,
+                        k1,
+                        Location::unknown(ctx),
+                    ))
+                    .result(0)
+                    .unwrap()
+                    .into();
+                build_call(ctx, block, "au_write", &[fd, integral, len_written], &[]);
+                build_write_str(ctx, block, fd, ".");
+
+                let leading_zeros = block
+                    .append_operation(arith::subi(k0, decimal_point, Location::unknown(ctx)))
+                    .result(0)
+                    .unwrap()
+                    .into();
+                let leading_zeros = block
+                    .append_operation(arith::maxsi(leading_zeros, k0, Location::unknown(ctx)))
+                    .result(0)
+                    .unwrap()
+                    .into();
+                let leading_zeros = block
+                    .append_operation(arith::minsi(
+                        leading_zeros,
+                        precision,
+                        Location::unknown(ctx),
+                    ))
+                    .result(0)
+                    .unwrap()
+                    .into();
+                let zeros = build_literal_str(ctx, block, "000000");
+                build_call(ctx, block, "au_write", &[fd, zeros, leading_zeros], &[]);
+
+                let fraction = build_element_ptr(ctx, block, digits, i8_type, integral_len);
+                let fraction_len = block
+                    .append_operation(arith::subi(len, integral_len, Location::unknown(ctx)))
+                    .result(0)
+                    .unwrap()
+                    .into();
+                build_call(ctx, block, "au_write", &[fd, fraction, fraction_len], &[]);
+            };
+
+            let write_infinite_or_finite = |block: &Block<'_>| {
+                build_if_else(
+                    ctx,
+                    block,
+                    is_infinite,
+                    |block| build_write_select(ctx, block, fd, is_negative, "-inf", "inf"),
+                    write_finite,
+                );
+            };
+            build_if_else(
+                ctx,
+                block,
+                is_nan,
+                |block| build_write_str(ctx, block, fd, "nan"),
+                write_infinite_or_finite,
+            );
+            block.append_operation(func::r#return(&[], Location::unknown(ctx)));
+        },
+    );
+
+    for (_, r#type) in printable_types() {
+        define_runtime_function(
+            ctx,
+            &method_symbol("au_Printable", "print", &r#type),
+            &[build_type(ctx, &r#type).expect("printable types should be primitive")],
+            &[i1_type],
+            |block| {
+                build_print_value(ctx, block, block.argument(0).unwrap().into(), &r#type);
+                let unit = build_bool(ctx, block, false);
+                block.append_operation(func::r#return(&[unit], Location::unknown(ctx)));
+            },
+        );
+    }
+
+    define_runtime_function(
+        ctx,
+        "au_abort",
+        &[ptr_type, i64_type, i32_type],
+        &[],
+        |block| {
+            let stderr = build_integer(ctx, block, STDERR, i32_type);
+            let message = block.argument(0).unwrap().into();
+            let len = block.argument(1).unwrap().into();
+            build_call(ctx, block, "au_write", &[stderr, message, len], &[]);
+            build_write_str(ctx, block, stderr, "\n");
+            build_call(
+                ctx,
+                block,
+                "exit",
+                &[block.argument(2).unwrap().into()],
+                &[],
+            );
+            block.append_operation(func::r#return(&[], Location::unknown(ctx)));
+        },
+    );
+}
+
+/// Build an `scf.if` without results, with branches built by the closures.
+fn build_if_else<'c>(
+    ctx: &'c BuildContext<'c>,
+    block: &Block<'c>,
+    condition: Value<'c, '_>,
+    then: impl FnOnce(&Block<'c>),
+    r#else: impl FnOnce(&Block<'c>),
+) {
+    let then_block = Block::new(&[]);
+    then(&then_block);
+    then_block.append_operation(scf::r#yield(&[], Location::unknown(ctx)));
+    let then_region = Region::new();
+    then_region.append_block(then_block);
+
+    let else_block = Block::new(&[]);
+    r#else(&else_block);
+    else_block.append_operation(scf::r#yield(&[], Location::unknown(ctx)));
+    let else_region = Region::new();
+    else_region.append_block(else_block);
+
+    block.append_operation(scf::r#if(
+        condition,
+        &[],
+        then_region,
+        else_region,
+        Location::unknown(ctx),
+    ));
+}
+
+fn define_runtime_function<'c>(
+    ctx: &'c BuildContext<'c>,
+    name: &str,
+    inputs: &[Type<'c>],
+    results: &[Type<'c>],
+    build: impl FnOnce(&Block<'c>),
+) {
+    let region = Region::new();
+    let block = region.append_block(Block::new(
+        &inputs
+            .iter()
+            .map(|r#type| (*r#type, Location::unknown(ctx)))
+            .collect::<Vec<_>>(),
+    ));
+    build(&block);
+
+    ctx.module.body().append_operation(func::func(
+        ctx,
+        StringAttribute::new(ctx, name),
+        TypeAttribute::new(FunctionType::new(ctx, inputs, results).into()),
+        region,
+        &[(
+            Identifier::new(ctx, "sym_visibility"),
+            StringAttribute::new(ctx, "private").into(),
+        )],
+        Location::unknown(ctx),
+    ));
+}
+
+/// Abort with a message naming the function when `condition` holds.
+pub(super) fn build_check<'c, 'b>(
+    ctx: &'c BuildContext<'c>,
+    function: &FunctionContext<'c, 'b>,
+    block: &'b Block<'c>,
+    condition: Value<'c, 'b>,
+    message: &str,
+) {
+    build_abort_if(
+        ctx,
+        block,
+        condition,
+        &format!("{message} in `{}`", function.name),
+    );
+}
+
+fn build_abort_if<'c, 'b>(
+    ctx: &'c BuildContext<'c>,
+    block: &'b Block<'c>,
+    condition: Value<'c, 'b>,
+    message: &str,
+) {
+    let abort_block = Block::new(&[]);
+    build_abort(ctx, &abort_block, message);
+    abort_block.append_operation(scf::r#yield(&[], Location::unknown(ctx)));
+
+    let then_region = Region::new();
+    then_region.append_block(abort_block);
+    block.append_operation(scf::r#if(
+        condition,
+        &[],
+        then_region,
+        Region::new(),
+        Location::unknown(ctx),
+    ));
+}
+
+/// Print a message to standard error and exit with a failure status. Control never comes back,
+/// but regions still need their terminator after it.
+fn build_abort<'c, 'b>(ctx: &'c BuildContext<'c>, block: &'b Block<'c>, message: &str) {
+    let buffer = build_literal_str(ctx, block, message);
+    let len = build_integer(
+        ctx,
+        block,
+        message.len() as i64,
+        IntegerType::new(ctx, 64).into(),
+    );
+    let status = build_integer(ctx, block, 1, IntegerType::new(ctx, 32).into());
+    build_call(ctx, block, "au_abort", &[buffer, len, status], &[]);
+}
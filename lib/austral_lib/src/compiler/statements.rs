@@ -0,0 +1,821 @@
+use super::{
+    expressions::{
+        build_address, build_bool, build_expr, build_name, build_path_segments, infer_type,
+        process_expr, Place,
+    },
+    operations::{
+        build_extract_value, build_insert_value, build_llvm_load, build_llvm_store, build_slot_ptr,
+    },
+    types::{build_type, record_slot, substitute, tag_bits, union_decl, union_layout, UnionLayout},
+    BuildContext,
+};
+use crate::{
+    ast::{
+        AssignStmt, BorrowMode, BorrowMutMode, BorrowStmt, CaseStmt, ForStmt, Ident, IfStmt,
+        LetStmtTarget, PathSegment, Statement, TypeSpec, WhileStmt,
+    },
+    error::{CodegenError, CodegenResult},
+};
+use melior::{
+    dialect::{
+        arith::{self, CmpiPredicate},
+        index, llvm, memref, scf,
+    },
+    ir::{
+        attribute::{DenseI64ArrayAttribute, IntegerAttribute, TypeAttribute},
+        operation::OperationBuilder,
+        r#type::{IntegerType, MemRefType},
+        Block, Identifier, Location, Region, Type, Value, ValueLike,
+    },
+};
+use std::collections::HashMap;
+
+/// State shared by every block of the function being compiled.
+///
+/// Austral allows `return` anywhere, but `func.return` can only terminate the function's own
+/// region and not the nested `scf` regions, so returning stores the value in `return_slot` and
+/// raises `return_flag`. Statements which follow one that may have returned are guarded by the
+/// flag, and the function returns the stored value once the body is done.
+pub(super) struct FunctionContext<'c, 'b> {
+    /// Names the function in runtime error messages.
+    pub(super) name: String,
+    pub(super) entry: &'b Block<'c>,
+    pub(super) ret_type: Type<'c>,
+    pub(super) return_flag: Value<'c, 'b>,
+    pub(super) return_slot: Value<'c, 'b>,
+}
+
+impl<'c, 'b> FunctionContext<'c, 'b> {
+    /// Allocate a stack slot in the entry block, so that declarations within loops don't grow the
+    /// stack on every iteration.
+    pub(super) fn alloca(&self, ctx: &'c BuildContext<'c>, r#type: Type<'c>) -> Value<'c, 'b> {
+        self.entry
+            .insert_operation(
+                0,
+                memref::alloca(
+                    ctx,
+                    MemRefType::new(r#type, &[], None, None),
+                    &[],
+                    &[],
+                    None,
+                    Location::unknown(ctx),
+                ),
+            )
+            .result(0)
+            .unwrap()
+            .into()
+    }
+
+    /// Allocate untyped memory in the entry block, for values which are read back as another
+    /// type, like the payload of a union.
+    pub(super) fn llvm_alloca(&self, ctx: &'c BuildContext<'c>, r#type: Type<'c>) -> Value<'c, 'b> {
+        let k1 = self
+            .entry
+            .insert_operation(
+                0,
+                arith::constant(
+                    ctx,
+                    IntegerAttribute::new(1, IntegerType::new(ctx, 64).into()).into(),
+                    Location::unknown(ctx),
+                ),
+            )
+            .result(0)
+            .unwrap()
+            .into();
+
+        self.entry
+            .insert_operation(
+                1,
+                OperationBuilder::new("llvm.alloca", Location::unknown(ctx))
+                    .add_operands(&[k1])
+                    .add_attributes(&[(
+                        Identifier::new(ctx, "elem_type"),
+                        TypeAttribute::new(r#type).into(),
+                    )])
+                    .add_results(&[llvm::r#type::opaque_pointer(ctx)])
+                    .build()
+                    .unwrap(),
+            )
+            .result(0)
+            .unwrap()
+            .into()
+    }
+}
+
+/// A name in scope, with its declared type. Immutable bindings are SSA values, while `var`s live
+/// in a stack slot since they can be reassigned from within nested regions.
+#[derive(Clone)]
+pub(super) enum Local<'c, 'b> {
+    Value {
+        value: Value<'c, 'b>,
+        r#type: TypeSpec,
+    },
+    Variable {
+        slot: Value<'c, 'b>,
+        r#type: TypeSpec,
+    },
+}
+
+impl Local<'_, '_> {
+    pub(super) fn type_spec(&self) -> &TypeSpec {
+        match self {
+            Self::Value { r#type, .. } | Self::Variable { r#type, .. } => r#type,
+        }
+    }
+}
+
+pub(super) fn build_block<'c, 'b, 'a>(
+    ctx: &'c BuildContext<'c>,
+    function: &FunctionContext<'c, 'b>,
+    block: &'b Block<'c>,
+    stmts: &'a [Statement],
+    locals: &HashMap<&'a str, Local<'c, 'b>>,
+) -> CodegenResult<()> {
+    let mut locals = locals.clone();
+
+    for (i, stmt) in stmts.iter().enumerate() {
+        match stmt {
+            Statement::Let(stmt) => {
+                let expr = process_expr(&stmt.value);
+                match &stmt.target {
+                    LetStmtTarget::Simple { name, r#type } => {
+                        let value = build_expr(
+                            ctx,
+                            function,
+                            block,
+                            &expr,
+                            Some(build_type(ctx, r#type)?),
+                            &locals,
+                        )?;
+                        let local = build_local(
+                            ctx,
+                            function,
+                            block,
+                            value,
+                            r#type.clone(),
+                            stmt.is_mutable,
+                        );
+                        locals.insert(name.name.as_str(), local);
+                    }
+                    LetStmtTarget::Destructure(bindings) => {
+                        let record_type = infer_type(ctx, &stmt.value, &locals)
+                            .ok_or(CodegenError::NotARecord)?;
+                        let record = build_expr(
+                            ctx,
+                            function,
+                            block,
+                            &expr,
+                            Some(build_type(ctx, &record_type)?),
+                            &locals,
+                        )?;
+
+                        for binding in bindings {
+                            let (index, _) = record_slot(ctx, &record_type, &binding.name.name)
+                                .ok_or_else(|| {
+                                    CodegenError::UnknownSlot(binding.name.name.clone())
+                                })?;
+                            let value = build_extract_value(
+                                ctx,
+                                block,
+                                record,
+                                index,
+                                build_type(ctx, &binding.r#type)?,
+                            );
+                            let local = build_local(
+                                ctx,
+                                function,
+                                block,
+                                value,
+                                binding.r#type.clone(),
+                                stmt.is_mutable,
+                            );
+                            let name = binding.rename.as_ref().unwrap_or(&binding.name);
+                            locals.insert(name.name.as_str(), local);
+                        }
+                    }
+                }
+            }
+            Statement::Assign(stmt) => build_assign(ctx, function, block, stmt, &locals)?,
+            Statement::Discard(expr) => {
+                let expr = process_expr(expr);
+                build_expr(ctx, function, block, &expr, None, &locals)?;
+            }
+            Statement::Return(expr) => {
+                let expr = process_expr(expr);
+                let value = build_expr(
+                    ctx,
+                    function,
+                    block,
+                    &expr,
+                    Some(function.ret_type),
+                    &locals,
+                )?;
+                block.append_operation(memref::store(
+                    value,
+                    function.return_slot,
+                    &[],
+                    Location::unknown(ctx),
+                ));
+                let k1 = build_bool(ctx, block, true);
+                block.append_operation(memref::store(
+                    k1,
+                    function.return_flag,
+                    &[],
+                    Location::unknown(ctx),
+                ));
+
+                // Anything after a return is unreachable.
+                return Ok(());
+            }
+            Statement::If(stmt) => build_if(ctx, function, block, stmt, &locals)?,
+            Statement::While(stmt) => build_while(ctx, function, block, stmt, &locals)?,
+            Statement::For(stmt) => build_for(ctx, function, block, stmt, &locals)?,
+            Statement::Case(stmt) => build_case(ctx, function, block, stmt, &locals)?,
+            Statement::Borrow(stmt) => build_borrow(ctx, function, block, stmt, &locals)?,
+        }
+
+        let rest = &stmts[i + 1..];
+        if may_return(std::slice::from_ref(stmt)) && !rest.is_empty() {
+            let is_running = build_is_running(ctx, function, block);
+            let region = build_region(ctx, function, rest, &locals)?;
+            block.append_operation(scf::r#if(
+                is_running,
+                &[],
+                region,
+                Region::new(),
+                Location::unknown(ctx),
+            ));
+            return Ok(());
+        }
+    }
+
+    Ok(())
+}
+
+/// Build a region holding a single block with the given statements.
+fn build_region<'c, 'b, 'a>(
+    ctx: &'c BuildContext<'c>,
+    function: &FunctionContext<'c, 'b>,
+    stmts: &'a [Statement],
+    locals: &HashMap<&'a str, Local<'c, 'b>>,
+) -> CodegenResult<Region<'c>> {
+    let block = Block::new(&[]);
+    build_block(ctx, function, &block, stmts, locals)?;
+    block.append_operation(scf::r#yield(&[], Location::unknown(ctx)));
+
+    let region = Region::new();
+    region.append_block(block);
+    Ok(region)
+}
+
+fn build_if<'c, 'b, 'a>(
+    ctx: &'c BuildContext<'c>,
+    function: &FunctionContext<'c, 'b>,
+    block: &'b Block<'c>,
+    stmt: &'a IfStmt,
+    locals: &HashMap<&'a str, Local<'c, 'b>>,
+) -> CodegenResult<()> {
+    let expr = process_expr(&stmt.value);
+    let condition = build_expr(
+        ctx,
+        function,
+        block,
+        &expr,
+        Some(IntegerType::new(ctx, 1).into()),
+        locals,
+    )?;
+
+    let then_region = build_region(ctx, function, &stmt.contents, locals)?;
+    let else_region = match &stmt.r#else {
+        Some(stmts) => build_region(ctx, function, stmts, locals)?,
+        None => Region::new(),
+    };
+
+    block.append_operation(scf::r#if(
+        condition,
+        &[],
+        then_region,
+        else_region,
+        Location::unknown(ctx),
+    ));
+    Ok(())
+}
+
+fn build_while<'c, 'b, 'a>(
+    ctx: &'c BuildContext<'c>,
+    function: &FunctionContext<'c, 'b>,
+    block: &'b Block<'c>,
+    stmt: &'a WhileStmt,
+    locals: &HashMap<&'a str, Local<'c, 'b>>,
+) -> CodegenResult<()> {
+    let before_block = Block::new(&[]);
+    let expr = process_expr(&stmt.value);
+    let condition = build_expr(
+        ctx,
+        function,
+        &before_block,
+        &expr,
+        Some(IntegerType::new(ctx, 1).into()),
+        locals,
+    )?;
+    let condition = if may_return(&stmt.contents) {
+        let is_running = build_is_running(ctx, function, &before_block);
+        before_block
+            .append_operation(arith::andi(condition, is_running, Location::unknown(ctx)))
+            .result(0)
+            .unwrap()
+            .into()
+    } else {
+        condition
+    };
+    before_block.append_operation(scf::condition(condition, &[], Location::unknown(ctx)));
+
+    let before_region = Region::new();
+    before_region.append_block(before_block);
+    let after_region = build_region(ctx, function, &stmt.contents, locals)?;
+
+    block.append_operation(scf::r#while(
+        &[],
+        &[],
+        before_region,
+        after_region,
+        Location::unknown(ctx),
+    ));
+    Ok(())
+}
+
+/// Lower an inclusive `for i from a to b` loop.
+///
+/// Besides the index the loop carries whether there are iterations left, which is computed before
+/// incrementing so that `to` may be the maximum value of the index type.
+fn build_for<'c, 'b, 'a>(
+    ctx: &'c BuildContext<'c>,
+    function: &FunctionContext<'c, 'b>,
+    block: &'b Block<'c>,
+    stmt: &'a ForStmt,
+    locals: &HashMap<&'a str, Local<'c, 'b>>,
+) -> CodegenResult<()> {
+    // The index is always an `Index`, which is what the literal checker validates the bounds as.
+    let index_spec = TypeSpec::Simple {
+        name: Ident::new("Index"),
+    };
+    for bound in [&stmt.range.start, &stmt.range.end] {
+        match infer_type(ctx, bound, locals) {
+            Some(r#type) if r#type != index_spec => {
+                return Err(CodegenError::ForBound {
+                    name: stmt.name.name.clone(),
+                    r#type: r#type.to_string(),
+                });
+            }
+            _ => {}
+        }
+    }
+    let index_type = build_type(ctx, &index_spec)?;
+    let bool_type = IntegerType::new(ctx, 1).into();
+
+    let start = process_expr(&stmt.range.start);
+    let start = build_expr(ctx, function, block, &start, Some(index_type), locals)?;
+    let end = process_expr(&stmt.range.end);
+    let end = build_expr(ctx, function, block, &end, Some(index_type), locals)?;
+
+    let is_pending = block
+        .append_operation(arith::cmpi(
+            ctx,
+            CmpiPredicate::Ule,
+            start,
+            end,
+            Location::unknown(ctx),
+        ))
+        .result(0)
+        .unwrap()
+        .into();
+
+    let before_block = Block::new(&[
+        (index_type, Location::unknown(ctx)),
+        (bool_type, Location::unknown(ctx)),
+    ]);
+    let index = before_block.argument(0).unwrap().into();
+    let condition = before_block.argument(1).unwrap().into();
+    let condition = if may_return(&stmt.contents) {
+        let is_running = build_is_running(ctx, function, &before_block);
+        before_block
+            .append_operation(arith::andi(condition, is_running, Location::unknown(ctx)))
+            .result(0)
+            .unwrap()
+            .into()
+    } else {
+        condition
+    };
+    before_block.append_operation(scf::condition(condition, &[index], Location::unknown(ctx)));
+    let before_region = Region::new();
+    before_region.append_block(before_block);
+
+    let after_block = Block::new(&[(index_type, Location::unknown(ctx))]);
+    let index = after_block.argument(0).unwrap().into();
+    let mut body_locals = locals.clone();
+    let local = Local::Value {
+        value: index,
+        r#type: index_spec,
+    };
+    body_locals.insert(stmt.name.name.as_str(), local);
+    build_block(ctx, function, &after_block, &stmt.contents, &body_locals)?;
+
+    let is_pending_next = after_block
+        .append_operation(arith::cmpi(
+            ctx,
+            CmpiPredicate::Ne,
+            index,
+            end,
+            Location::unknown(ctx),
+        ))
+        .result(0)
+        .unwrap()
+        .into();
+    let k1 = after_block
+        .append_operation(arith::constant(
+            ctx,
+            IntegerAttribute::new(1, index_type).into(),
+            Location::unknown(ctx),
+        ))
+        .result(0)
+        .unwrap()
+        .into();
+    let index_next = after_block
+        .append_operation(arith::addi(index, k1, Location::unknown(ctx)))
+        .result(0)
+        .unwrap()
+        .into();
+    after_block.append_operation(scf::r#yield(
+        &[index_next, is_pending_next],
+        Location::unknown(ctx),
+    ));
+    let after_region = Region::new();
+    after_region.append_block(after_block);
+
+    block.append_operation(scf::r#while(
+        &[start, is_pending],
+        &[index_type],
+        before_region,
+        after_region,
+        Location::unknown(ctx),
+    ));
+    Ok(())
+}
+
+/// Bind a value to a new name, giving `var`s a stack slot.
+fn build_local<'c, 'b>(
+    ctx: &'c BuildContext<'c>,
+    function: &FunctionContext<'c, 'b>,
+    block: &'b Block<'c>,
+    value: Value<'c, 'b>,
+    r#type: TypeSpec,
+    is_mutable: bool,
+) -> Local<'c, 'b> {
+    if is_mutable {
+        let slot = function.alloca(ctx, value.r#type());
+        block.append_operation(memref::store(value, slot, &[], Location::unknown(ctx)));
+        Local::Variable { slot, r#type }
+    } else {
+        Local::Value { value, r#type }
+    }
+}
+
+/// Assigning to a slot rebuilds the records along the path around the new value, and stores the
+/// outermost one back into the variable.
+fn build_assign<'c, 'b>(
+    ctx: &'c BuildContext<'c>,
+    function: &FunctionContext<'c, 'b>,
+    block: &'b Block<'c>,
+    stmt: &AssignStmt,
+    locals: &HashMap<&str, Local<'c, 'b>>,
+) -> CodegenResult<()> {
+    let name = stmt.target.first.name.as_str();
+
+    // Writing through a pointer or into a span only needs the address the path leads to, and
+    // leaves the root unchanged.
+    let is_indirect = stmt
+        .target
+        .extra
+        .iter()
+        .any(|x| !matches!(x, PathSegment::SlotAccess(_)));
+    if is_indirect {
+        let (root, r#type) = build_name(ctx, function, block, name, locals)?;
+        let (place, r#type) = build_path_segments(
+            ctx,
+            function,
+            block,
+            Place::Value(root),
+            r#type,
+            &stmt.target.extra,
+            locals,
+        )?;
+        let Place::Memory(ptr) = place else {
+            unreachable!()
+        };
+
+        let expr = process_expr(&stmt.value);
+        let value = build_expr(
+            ctx,
+            function,
+            block,
+            &expr,
+            Some(build_type(ctx, &r#type)?),
+            locals,
+        )?;
+        build_llvm_store(ctx, block, value, ptr);
+        return Ok(());
+    }
+
+    let (slot, r#type) = match locals.get(name) {
+        Some(Local::Variable { slot, r#type }) => (*slot, r#type),
+        Some(Local::Value { .. }) => {
+            return Err(CodegenError::ImmutableAssignment(name.to_string()))
+        }
+        None => return Err(CodegenError::UnknownVariable(name.to_string())),
+    };
+
+    let mut types = vec![r#type.clone()];
+    let mut indices = Vec::with_capacity(stmt.target.extra.len());
+    for segment in &stmt.target.extra {
+        match segment {
+            PathSegment::SlotAccess(slot_name) => {
+                let (index, slot_type) =
+                    record_slot(ctx, types.last().unwrap(), &slot_name.name)
+                        .ok_or_else(|| CodegenError::UnknownSlot(slot_name.name.clone()))?;
+                indices.push(index);
+                types.push(slot_type);
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    let expr = process_expr(&stmt.value);
+    let target_type = build_type(ctx, types.last().unwrap())?;
+    let mut value = build_expr(ctx, function, block, &expr, Some(target_type), locals)?;
+
+    if !indices.is_empty() {
+        let root = block
+            .append_operation(memref::load(slot, &[], Location::unknown(ctx)))
+            .result(0)
+            .unwrap()
+            .into();
+        let mut containers = vec![root];
+        for (index, r#type) in indices.iter().zip(&types[1..]).take(indices.len() - 1) {
+            let container = *containers.last().unwrap();
+            let r#type = build_type(ctx, r#type)?;
+            containers.push(build_extract_value(ctx, block, container, *index, r#type));
+        }
+
+        for (container, index) in containers.into_iter().zip(&indices).rev() {
+            value = build_insert_value(ctx, block, container, *index, value);
+        }
+    }
+
+    block.append_operation(memref::store(value, slot, &[], Location::unknown(ctx)));
+    Ok(())
+}
+
+/// Run the body of a `borrow` statement with the reference in scope.
+fn build_borrow<'c, 'b, 'a>(
+    ctx: &'c BuildContext<'c>,
+    function: &FunctionContext<'c, 'b>,
+    block: &'b Block<'c>,
+    stmt: &'a BorrowStmt,
+    locals: &HashMap<&'a str, Local<'c, 'b>>,
+) -> CodegenResult<()> {
+    let value = match stmt.mode {
+        BorrowMode::Read | BorrowMode::Write => {
+            build_address(ctx, function, block, &stmt.orig.name, locals)?.0
+        }
+        // Reborrowing copies the reference.
+        BorrowMode::ReBorrow => build_name(ctx, function, block, &stmt.orig.name, locals)?.0,
+    };
+
+    let lhs = Box::new(stmt.r#type.clone());
+    let rhs = Box::new(TypeSpec::Simple {
+        name: stmt.reg.clone(),
+    });
+    let r#type = match stmt.mut_mode {
+        BorrowMutMode::Read => TypeSpec::BorrowRead { lhs, rhs },
+        BorrowMutMode::Write => TypeSpec::BorrowWrite { lhs, rhs },
+    };
+
+    let mut locals = locals.clone();
+    locals.insert(stmt.name.name.as_str(), Local::Value { value, r#type });
+    build_block(ctx, function, block, &stmt.body, &locals)
+}
+
+/// Whether any of the statements contains a `return`.
+fn may_return(stmts: &[Statement]) -> bool {
+    stmts.iter().any(|stmt| match stmt {
+        Statement::Return(_) => true,
+        Statement::Borrow(stmt) => may_return(&stmt.body),
+        Statement::Case(stmt) => stmt.variants.iter().any(|x| may_return(&x.block)),
+        Statement::For(stmt) => may_return(&stmt.contents),
+        Statement::If(stmt) => {
+            may_return(&stmt.contents) || stmt.r#else.as_deref().is_some_and(may_return)
+        }
+        Statement::While(stmt) => may_return(&stmt.contents),
+        Statement::Assign(_) | Statement::Discard(_) | Statement::Let(_) => false,
+    })
+}
+
+/// Build a flag which is `true` while the function has not returned yet.
+fn build_is_running<'c, 'b>(
+    ctx: &'c BuildContext<'c>,
+    function: &FunctionContext<'c, '_>,
+    block: &'b Block<'c>,
+) -> Value<'c, 'b> {
+    let has_returned = block
+        .append_operation(memref::load(
+            function.return_flag,
+            &[],
+            Location::unknown(ctx),
+        ))
+        .result(0)
+        .unwrap()
+        .into();
+    let k1 = build_bool(ctx, block, true);
+
+    block
+        .append_operation(arith::xori(has_returned, k1, Location::unknown(ctx)))
+        .result(0)
+        .unwrap()
+        .into()
+}
+
+/// Lower `case` to a switch over the index of the case the value holds. Each `when` binds the
+/// fields it names before running its body.
+fn build_case<'c, 'b, 'a>(
+    ctx: &'c BuildContext<'c>,
+    function: &FunctionContext<'c, 'b>,
+    block: &'b Block<'c>,
+    stmt: &'a CaseStmt,
+    locals: &HashMap<&'a str, Local<'c, 'b>>,
+) -> CodegenResult<()> {
+    let union_type = infer_type(ctx, &stmt.value, locals).ok_or(CodegenError::NotAUnion)?;
+    let (decl, args) = union_decl(ctx, &union_type).ok_or(CodegenError::NotAUnion)?;
+    let r#type = build_type(ctx, &union_type)?;
+
+    let expr = process_expr(&stmt.value);
+    let value = build_expr(ctx, function, block, &expr, Some(r#type), locals)?;
+
+    let build_index = |x: usize| -> Value<'c, 'b> {
+        block
+            .append_operation(arith::constant(
+                ctx,
+                IntegerAttribute::new(x as i64, Type::index(ctx)).into(),
+                Location::unknown(ctx),
+            ))
+            .result(0)
+            .unwrap()
+            .into()
+    };
+    let index_cast = |tag: Value<'c, 'b>| -> Value<'c, 'b> {
+        block
+            .append_operation(index::castu(tag, Type::index(ctx), Location::unknown(ctx)))
+            .result(0)
+            .unwrap()
+            .into()
+    };
+    let (index, storage) = match union_layout(ctx, &union_type)? {
+        UnionLayout::Tag { .. } => (index_cast(value), None),
+        UnionLayout::Nullable { empty, full } => {
+            let address = block
+                .append_operation(
+                    OperationBuilder::new("llvm.ptrtoint", Location::unknown(ctx))
+                        .add_operands(&[value])
+                        .add_results(&[IntegerType::new(ctx, 64).into()])
+                        .build()
+                        .unwrap(),
+                )
+                .result(0)
+                .unwrap()
+                .into();
+            let k0 = block
+                .append_operation(arith::constant(
+                    ctx,
+                    IntegerAttribute::new(0, IntegerType::new(ctx, 64).into()).into(),
+                    Location::unknown(ctx),
+                ))
+                .result(0)
+                .unwrap()
+                .into();
+            let is_null = block
+                .append_operation(arith::cmpi(
+                    ctx,
+                    CmpiPredicate::Eq,
+                    address,
+                    k0,
+                    Location::unknown(ctx),
+                ))
+                .result(0)
+                .unwrap()
+                .into();
+            let index = block
+                .append_operation(arith::select(
+                    is_null,
+                    build_index(empty),
+                    build_index(full),
+                    Location::unknown(ctx),
+                ))
+                .result(0)
+                .unwrap()
+                .into();
+            (index, None)
+        }
+        UnionLayout::Tagged { tag_bits, .. } => {
+            let slot = function.llvm_alloca(ctx, r#type);
+            build_llvm_store(ctx, block, value, slot);
+            let tag =
+                build_extract_value(ctx, block, value, 0, IntegerType::new(ctx, tag_bits).into());
+            (
+                index_cast(tag),
+                Some(build_slot_ptr(ctx, block, slot, r#type, 1)),
+            )
+        }
+    };
+
+    let regions = decl
+        .cases
+        .iter()
+        .map(|case| -> CodegenResult<Region<'c>> {
+            let region_block = Block::new(&[]);
+            if let Some(when) = stmt.variants.iter().find(|x| x.ident == case.name) {
+                let fields = case
+                    .fields
+                    .iter()
+                    .map(|field| substitute(&field.r#type, &decl.type_params, args))
+                    .collect::<Vec<_>>();
+                let payload = match storage {
+                    Some(storage) => {
+                        let payload_type = llvm::r#type::r#struct(
+                            ctx,
+                            &fields
+                                .iter()
+                                .map(|x| build_type(ctx, x))
+                                .collect::<CodegenResult<Vec<_>>>()?,
+                            false,
+                        );
+                        Some(build_llvm_load(ctx, &region_block, storage, payload_type))
+                    }
+                    None => None,
+                };
+
+                let mut locals = locals.clone();
+                for binding in &when.bindings {
+                    let position = case
+                        .fields
+                        .iter()
+                        .position(|field| field.name == binding.name)
+                        .ok_or_else(|| CodegenError::UnknownField(binding.name.name.clone()))?;
+                    let value = match payload {
+                        Some(payload) => build_extract_value(
+                            ctx,
+                            &region_block,
+                            payload,
+                            position,
+                            build_type(ctx, &fields[position])?,
+                        ),
+                        // Only the pointer of a nullable union can be bound.
+                        None => value,
+                    };
+                    let name = binding.rename.as_ref().unwrap_or(&binding.name);
+                    locals.insert(
+                        name.name.as_str(),
+                        Local::Value {
+                            value,
+                            r#type: binding.r#type.clone(),
+                        },
+                    );
+                }
+                build_block(ctx, function, &region_block, &when.block, &locals)?;
+            }
+            region_block.append_operation(scf::r#yield(&[], Location::unknown(ctx)));
+
+            let region = Region::new();
+            region.append_block(region_block);
+            Ok(region)
+        })
+        .collect::<CodegenResult<Vec<_>>>()?;
+
+    // Every case is covered, so the default region is never taken.
+    let default_block = Block::new(&[]);
+    default_block.append_operation(scf::r#yield(&[], Location::unknown(ctx)));
+    let default_region = Region::new();
+    default_region.append_block(default_block);
+
+    let cases = (0..decl.cases.len() as i64).collect::<Vec<_>>();
+    block.append_operation(
+        OperationBuilder::new("scf.index_switch", Location::unknown(ctx))
+            .add_operands(&[index])
+            .add_attributes(&[(
+                Identifier::new(ctx, "cases"),
+                DenseI64ArrayAttribute::new(ctx, &cases).into(),
+            )])
+            .add_regions(std::iter::once(default_region).chain(regions).collect())
+            .build()
+            .unwrap(),
+    );
+    Ok(())
+}
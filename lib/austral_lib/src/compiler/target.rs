@@ -0,0 +1,456 @@
+use llvm_sys::{
+    core::{
+        LLVMContextCreate, LLVMContextDispose, LLVMDisposeMemoryBuffer, LLVMDisposeMessage,
+        LLVMDisposeModule, LLVMDoubleTypeInContext, LLVMFloatTypeInContext, LLVMGetBufferSize,
+        LLVMGetBufferStart, LLVMIntTypeInContext, LLVMPointerTypeInContext,
+        LLVMPrintModuleToString,
+    },
+    prelude::{LLVMContextRef, LLVMMemoryBufferRef, LLVMModuleRef},
+    target::{
+        LLVMABIAlignmentOfType, LLVMABISizeOfType, LLVMCopyStringRepOfTargetData,
+        LLVMDisposeTargetData, LLVM_InitializeAllAsmPrinters, LLVM_InitializeAllTargetInfos,
+        LLVM_InitializeAllTargetMCs, LLVM_InitializeAllTargets,
+    },
+    target_machine::{
+        LLVMCodeGenFileType, LLVMCodeGenOptLevel, LLVMCodeModel, LLVMCreateTargetDataLayout,
+        LLVMCreateTargetMachine, LLVMDisposeTargetMachine, LLVMGetDefaultTargetTriple,
+        LLVMGetHostCPUFeatures, LLVMGetHostCPUName, LLVMGetTargetFromTriple,
+        LLVMNormalizeTargetTriple, LLVMRelocMode, LLVMTargetMachineEmitToMemoryBuffer,
+        LLVMTargetMachineRef, LLVMTargetRef,
+    },
+};
+use melior::ir::Module;
+use mlir_sys::MlirOperation;
+use std::{
+    ffi::{c_char, CStr, CString},
+    fmt::Display,
+    io::Write,
+    mem::MaybeUninit,
+    path::Path,
+    ptr::{addr_of_mut, null_mut},
+    sync::OnceLock,
+};
+use tempfile::NamedTempFile;
+
+extern "C" {
+    /// Translate operation that satisfies LLVM dialect module requirements into an LLVM IR module living in the given context.
+    /// This translates operations from any dilalect that has a registered implementation of LLVMTranslationDialectInterface.
+    fn mlirTranslateModuleToLLVMIR(
+        module_operation_ptr: MlirOperation,
+        llvm_context: LLVMContextRef,
+    ) -> LLVMModuleRef;
+}
+
+#[derive(Debug, Clone)]
+pub struct LLVMCompileError(String);
+
+impl std::error::Error for LLVMCompileError {}
+
+impl Display for LLVMCompileError {
+    #[inline]
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// The machine to generate code for.
+#[derive(Clone, Debug)]
+pub struct TargetOptions {
+    /// The target triple, such as `aarch64-unknown-linux-gnu`.
+    pub triple: String,
+    /// The CPU to tune for and whose instructions may be used. Empty means a generic CPU.
+    pub cpu: String,
+    /// The features to enable or disable on top of the CPU's, such as `+neon,-sve`.
+    pub features: String,
+    pub reloc_mode: LLVMRelocMode,
+    pub code_model: LLVMCodeModel,
+}
+
+impl TargetOptions {
+    /// A generic CPU of the target `triple`.
+    pub fn new(triple: impl Into<String>) -> Self {
+        Self {
+            triple: triple.into(),
+            cpu: String::new(),
+            features: String::new(),
+            reloc_mode: LLVMRelocMode::LLVMRelocDefault,
+            code_model: LLVMCodeModel::LLVMCodeModelDefault,
+        }
+    }
+
+    /// The host, including its CPU and features.
+    pub fn host() -> Self {
+        unsafe {
+            let take = |message: *mut c_char| {
+                let value = CStr::from_ptr(message).to_string_lossy().into_owned();
+                LLVMDisposeMessage(message);
+                value
+            };
+
+            Self {
+                cpu: take(LLVMGetHostCPUName()),
+                features: take(LLVMGetHostCPUFeatures()),
+                ..Self::new(take(LLVMGetDefaultTargetTriple()))
+            }
+        }
+    }
+
+    /// Whether this is the host's target, which the system linker and C library are for. Vendors
+    /// are ignored, since `x86_64-pc-linux-gnu` and `x86_64-unknown-linux-gnu` link the same way.
+    pub fn is_host(&self) -> bool {
+        let without_vendor = |triple: &str| {
+            let mut parts = normalize_triple(triple)?
+                .split('-')
+                .map(str::to_string)
+                .collect::<Vec<_>>();
+            if parts.len() > 1 {
+                parts.remove(1);
+            }
+            Some(parts)
+        };
+        let triple = without_vendor(&self.triple);
+        triple.is_some() && triple == without_vendor(&Self::host().triple)
+    }
+
+    /// The same target, with the relocation model used for shared libraries.
+    pub fn for_library(self) -> Self {
+        Self {
+            reloc_mode: LLVMRelocMode::LLVMRelocDynamicNoPic,
+            ..self
+        }
+    }
+}
+
+/// Sizes and alignments of the primitive types on the target, as LLVM lays them out. Records and
+/// unions are built from these with the usual struct rules.
+#[derive(Clone, Debug)]
+pub struct DataLayout {
+    /// The LLVM data layout string.
+    pub description: String,
+    pub pointer: (u64, u64),
+    /// The layouts of `i8`, `i16`, `i32` and `i64`.
+    pub integers: [(u64, u64); 4],
+    pub float32: (u64, u64),
+    pub float64: (u64, u64),
+}
+
+impl DataLayout {
+    /// Query the data layout of the host target.
+    pub fn host() -> Result<Self, LLVMCompileError> {
+        Self::new(&TargetOptions::host())
+    }
+
+    /// Query the data layout of `target`.
+    pub fn new(target: &TargetOptions) -> Result<Self, LLVMCompileError> {
+        initialize_targets();
+
+        unsafe {
+            let machine = create_target_machine(target)?;
+            let target_data = LLVMCreateTargetDataLayout(machine);
+            let llvm_context = LLVMContextCreate();
+
+            let layout = |r#type| {
+                (
+                    LLVMABISizeOfType(target_data, r#type),
+                    u64::from(LLVMABIAlignmentOfType(target_data, r#type)),
+                )
+            };
+            let description = LLVMCopyStringRepOfTargetData(target_data);
+            let data_layout = Self {
+                description: CStr::from_ptr(description).to_string_lossy().into_owned(),
+                pointer: layout(LLVMPointerTypeInContext(llvm_context, 0)),
+                integers: [8, 16, 32, 64]
+                    .map(|bits| layout(LLVMIntTypeInContext(llvm_context, bits))),
+                float32: layout(LLVMFloatTypeInContext(llvm_context)),
+                float64: layout(LLVMDoubleTypeInContext(llvm_context)),
+            };
+
+            LLVMDisposeMessage(description);
+            LLVMContextDispose(llvm_context);
+            LLVMDisposeTargetData(target_data);
+            LLVMDisposeTargetMachine(machine);
+
+            Ok(data_layout)
+        }
+    }
+
+    /// The layout of an integer of 8, 16, 32 or 64 bits.
+    pub fn integer(&self, bits: u32) -> (u64, u64) {
+        self.integers[bits.trailing_zeros() as usize - 3]
+    }
+}
+
+fn initialize_targets() {
+    static INITIALIZED: OnceLock<()> = OnceLock::new();
+
+    INITIALIZED.get_or_init(|| unsafe {
+        LLVM_InitializeAllTargets();
+        LLVM_InitializeAllTargetInfos();
+        LLVM_InitializeAllTargetMCs();
+        LLVM_InitializeAllAsmPrinters();
+    });
+}
+
+/// The canonical form of a target triple, with each component in its usual position.
+fn normalize_triple(triple: &str) -> Option<String> {
+    let triple = CString::new(triple).ok()?;
+    unsafe {
+        let normalized = LLVMNormalizeTargetTriple(triple.as_ptr());
+        let value = CStr::from_ptr(normalized).to_string_lossy().into_owned();
+        LLVMDisposeMessage(normalized);
+        Some(value)
+    }
+}
+
+/// Create a target machine for `target`, which must be disposed of by the caller.
+unsafe fn create_target_machine(
+    target: &TargetOptions,
+) -> Result<LLVMTargetMachineRef, LLVMCompileError> {
+    let c_string = |value: &str| {
+        CString::new(value)
+            .map_err(|_| LLVMCompileError(format!("invalid target option {value:?}")))
+    };
+    let target_triple = c_string(&target.triple)?;
+    let target_cpu = c_string(&target.cpu)?;
+    let target_cpu_features = c_string(&target.features)?;
+
+    let mut null = null_mut();
+    let error_buffer = addr_of_mut!(null);
+
+    let mut llvm_target: MaybeUninit<LLVMTargetRef> = MaybeUninit::uninit();
+
+    if LLVMGetTargetFromTriple(
+        target_triple.as_ptr(),
+        llvm_target.as_mut_ptr(),
+        error_buffer,
+    ) != 0
+    {
+        let error = CStr::from_ptr(*error_buffer);
+        let err = error.to_string_lossy().to_string();
+        LLVMDisposeMessage(*error_buffer);
+        return Err(LLVMCompileError(err));
+    } else if !(*error_buffer).is_null() {
+        LLVMDisposeMessage(*error_buffer);
+    }
+
+    let llvm_target = llvm_target.assume_init();
+
+    Ok(LLVMCreateTargetMachine(
+        llvm_target,
+        target_triple.as_ptr(),
+        target_cpu.as_ptr(),
+        target_cpu_features.as_ptr(),
+        LLVMCodeGenOptLevel::LLVMCodeGenLevelAggressive,
+        target.reloc_mode,
+        target.code_model,
+    ))
+}
+
+/// Translate a module lowered to the LLVM dialect into an LLVM module owned by `llvm_context`.
+unsafe fn translate_module(
+    module: &Module<'_>,
+    llvm_context: LLVMContextRef,
+) -> Result<LLVMModuleRef, LLVMCompileError> {
+    let llvm_module = mlirTranslateModuleToLLVMIR(module.as_operation().to_raw(), llvm_context);
+    if llvm_module.is_null() {
+        Err(LLVMCompileError(
+            "failed to translate the module to LLVM IR".to_string(),
+        ))
+    } else {
+        Ok(llvm_module)
+    }
+}
+
+/// Print the LLVM IR of a module lowered to the LLVM dialect.
+pub fn module_to_llvm_ir(module: &Module<'_>) -> Result<String, LLVMCompileError> {
+    unsafe {
+        let llvm_context = LLVMContextCreate();
+
+        let llvm_ir = translate_module(module, llvm_context).map(|llvm_module| {
+            let llvm_ir = LLVMPrintModuleToString(llvm_module);
+            let text = CStr::from_ptr(llvm_ir).to_string_lossy().into_owned();
+
+            LLVMDisposeMessage(llvm_ir);
+            LLVMDisposeModule(llvm_module);
+            text
+        });
+
+        LLVMContextDispose(llvm_context);
+        llvm_ir
+    }
+}
+
+/// Emit the assembly of a module lowered to the LLVM dialect.
+pub fn module_to_assembly(
+    module: &Module<'_>,
+    target: &TargetOptions,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let assembly = emit_module(module, target, LLVMCodeGenFileType::LLVMAssemblyFile)?;
+    Ok(String::from_utf8(assembly)?)
+}
+
+pub fn module_to_object(
+    module: &Module<'_>,
+    target: &TargetOptions,
+) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    Ok(emit_module(
+        module,
+        target,
+        LLVMCodeGenFileType::LLVMObjectFile,
+    )?)
+}
+
+fn emit_module(
+    module: &Module<'_>,
+    target: &TargetOptions,
+    file_type: LLVMCodeGenFileType,
+) -> Result<Vec<u8>, LLVMCompileError> {
+    initialize_targets();
+
+    unsafe {
+        let llvm_context = LLVMContextCreate();
+        let data = translate_module(module, llvm_context).and_then(|llvm_module| {
+            let data = create_target_machine(target).and_then(|machine| {
+                let data = emit_to_memory(machine, llvm_module, file_type);
+                LLVMDisposeTargetMachine(machine);
+                data
+            });
+            LLVMDisposeModule(llvm_module);
+            data
+        });
+        LLVMContextDispose(llvm_context);
+
+        data
+    }
+}
+
+unsafe fn emit_to_memory(
+    machine: LLVMTargetMachineRef,
+    llvm_module: LLVMModuleRef,
+    file_type: LLVMCodeGenFileType,
+) -> Result<Vec<u8>, LLVMCompileError> {
+    let mut null = null_mut();
+    let error_buffer = addr_of_mut!(null);
+
+    let mut out_buf: MaybeUninit<LLVMMemoryBufferRef> = MaybeUninit::uninit();
+
+    let ok = LLVMTargetMachineEmitToMemoryBuffer(
+        machine,
+        llvm_module,
+        file_type,
+        error_buffer,
+        out_buf.as_mut_ptr(),
+    );
+
+    if ok != 0 {
+        let error = CStr::from_ptr(*error_buffer);
+        let err = error.to_string_lossy().to_string();
+        LLVMDisposeMessage(*error_buffer);
+        return Err(LLVMCompileError(err));
+    } else if !(*error_buffer).is_null() {
+        LLVMDisposeMessage(*error_buffer);
+    }
+
+    let out_buf = out_buf.assume_init();
+
+    let out_buf_start: *const u8 = LLVMGetBufferStart(out_buf).cast();
+    let out_buf_size = LLVMGetBufferSize(out_buf);
+
+    // keep it in rust side
+    let data = std::slice::from_raw_parts(out_buf_start, out_buf_size).to_vec();
+
+    LLVMDisposeMemoryBuffer(out_buf);
+
+    Ok(data)
+}
+
+pub fn object_to_shared_lib(object: &[u8], output_filename: &Path) -> Result<(), std::io::Error> {
+    // linker seems to need a file and doesn't accept stdin
+    let mut file = NamedTempFile::new()?;
+    file.write_all(object)?;
+    let file = file.into_temp_path();
+
+    let args: &[&str] = {
+        #[cfg(target_os = "macos")]
+        {
+            &[
+                "-demangle",
+                "-no_deduplicate",
+                "-dynamic",
+                "-dylib",
+                "-L/usr/local/lib",
+                "-L/Library/Developer/CommandLineTools/SDKs/MacOSX.sdk/usr/lib",
+                &file.display().to_string(),
+                "-o",
+                &output_filename.display().to_string(),
+                "-lSystem",
+            ]
+        }
+        #[cfg(target_os = "linux")]
+        {
+            &[
+                "--hash-style=gnu",
+                "--eh-frame-hdr",
+                "-shared",
+                "-o",
+                &output_filename.display().to_string(),
+                "-L/lib/../lib64",
+                "-L/usr/lib/../lib64",
+                "-lc",
+                &file.display().to_string(),
+            ]
+        }
+        #[cfg(target_os = "windows")]
+        {
+            unimplemented!()
+        }
+    };
+
+    let mut linker = std::process::Command::new("ld");
+    let proc = linker.args(args.iter()).spawn()?;
+    proc.wait_with_output()?;
+    Ok(())
+}
+
+pub fn object_to_binary(object: &[u8], output_filename: &Path) -> Result<(), std::io::Error> {
+    // linker seems to need a file and doesn't accept stdin
+    let mut file = NamedTempFile::new()?;
+    file.write_all(object)?;
+    let file = file.into_temp_path();
+
+    let args: &[&str] = {
+        #[cfg(target_os = "macos")]
+        {
+            &[
+                "-L/usr/local/lib",
+                "-L/Library/Developer/CommandLineTools/SDKs/MacOSX.sdk/usr/lib",
+                &file.display().to_string(),
+                "-o",
+                &output_filename.display().to_string(),
+                "-lSystem",
+            ]
+        }
+        #[cfg(target_os = "linux")]
+        {
+            &[
+                "--hash-style=gnu",
+                "--eh-frame-hdr",
+                "-o",
+                &output_filename.display().to_string(),
+                "-L/lib/../lib64",
+                "-L/usr/lib/../lib64",
+                "-lc",
+                &file.display().to_string(),
+            ]
+        }
+        #[cfg(target_os = "windows")]
+        {
+            unimplemented!()
+        }
+    };
+
+    let mut linker = std::process::Command::new("ld");
+    let proc = linker.args(args.iter()).spawn()?;
+    proc.wait_with_output()?;
+    Ok(())
+}
@@ -0,0 +1,160 @@
+use super::{expressions::infer_type, statements::Local, BuildContext};
+use crate::{
+    ast::{
+        AtomicExpr, Expression, FnCallArgs, FnCallExpr, FunctionDecl, FunctionDef, Ident,
+        InstanceDef, MethodDef, TypeClassDecl, TypeParam, TypeSpec,
+    },
+    lexer,
+};
+use chumsky::{IterParser, Parser};
+use std::collections::HashMap;
+
+/// The typeclasses every module can use without importing them.
+const PERVASIVE_TYPECLASSES: &str = "
+    typeclass Printable(T: Free) is
+        method print(value: T): Unit;
+    end;
+";
+
+pub(super) fn pervasive_typeclasses() -> Vec<TypeClassDecl> {
+    let tokens = lexer::lex(PERVASIVE_TYPECLASSES)
+        .map(|(token, _span)| token)
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+
+    let decls = TypeClassDecl::parser()
+        .repeated()
+        .collect::<Vec<_>>()
+        .parse(&tokens)
+        .into_result();
+    decls.unwrap()
+}
+
+/// The symbol of an instance method, which is unique for each typeclass, method and type.
+pub(super) fn method_symbol(typeclass: &str, method: &str, arg: &TypeSpec) -> String {
+    let arg = arg
+        .to_string()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect::<String>();
+    format!("{typeclass}_{method}_{arg}")
+}
+
+/// An instance method as a function named after its symbol.
+pub(super) fn method_function(def: &InstanceDef, method: &MethodDef) -> FunctionDef {
+    FunctionDef {
+        decl: FunctionDecl {
+            doc_string: None,
+            pragmas: Vec::new(),
+            type_params: method.decl.type_params.clone(),
+            name: Ident::new(method_symbol(
+                &def.name.name,
+                &method.decl.name.name,
+                &def.arg,
+            )),
+            params: method.decl.params.clone(),
+            ret_type: method.decl.ret_ty.clone(),
+        },
+        body: method.body.clone(),
+    }
+}
+
+/// Methods of the typeclasses in scope, unless a function of the same name shadows them.
+pub(super) fn is_method(ctx: &BuildContext<'_>, name: &str) -> bool {
+    ctx.methods.contains_key(name) && !ctx.functions.contains_key(name)
+}
+
+/// Rewrite a method call into a call to the method of the instance for the type of its argument.
+/// Numeric literals are taken to be `Int64` or `Float64`.
+pub(super) fn resolve_method(
+    ctx: &BuildContext<'_>,
+    expr: &FnCallExpr,
+    locals: &HashMap<&str, Local<'_, '_>>,
+) -> Option<FnCallExpr> {
+    let typeclass = &ctx.methods[&expr.target.name];
+    let method = typeclass.methods.iter().find(|x| x.name == expr.target)?;
+    let index = method.params.iter().position(|param| {
+        matches!(&param.r#type, TypeSpec::Simple { name } if *name == typeclass.type_param.name)
+    })?;
+    let arg = match &expr.args {
+        FnCallArgs::Empty => return None,
+        FnCallArgs::Positional(args) => args.get(index)?,
+        FnCallArgs::Named(args) => args.get(&method.params[index].name)?,
+    };
+
+    let r#type = infer_type(ctx, arg, locals).or_else(|| literal_type(arg))?;
+    let instance = ctx.instances.iter().find(|instance| {
+        instance.typeclass == typeclass.name.name
+            && matches_type(&instance.arg, &r#type, &instance.type_params)
+    })?;
+    Some(FnCallExpr {
+        target: Ident::new(&instance.methods[&expr.target.name]),
+        args: expr.args.clone(),
+    })
+}
+
+fn literal_type(expr: &Expression) -> Option<TypeSpec> {
+    let name = match expr {
+        Expression::Atomic(AtomicExpr::ConstInt(_)) => "Int64",
+        Expression::Atomic(AtomicExpr::ConstFloat(_)) => "Float64",
+        Expression::Atomic(AtomicExpr::ConstChar(_)) => "Nat8",
+        _ => return None,
+    };
+    Some(TypeSpec::Simple {
+        name: Ident::new(name),
+    })
+}
+
+/// Whether a type is an instance of a pattern, in which type parameters match any type.
+fn matches_type(pattern: &TypeSpec, r#type: &TypeSpec, params: &[TypeParam]) -> bool {
+    match (pattern, r#type) {
+        (TypeSpec::Simple { name }, _) if params.iter().any(|x| x.name == *name) => true,
+        (TypeSpec::Simple { name: lhs }, TypeSpec::Simple { name: rhs }) => lhs == rhs,
+        (
+            TypeSpec::Generic {
+                name: lhs,
+                type_params: lhs_args,
+            },
+            TypeSpec::Generic {
+                name: rhs,
+                type_params: rhs_args,
+            },
+        ) => {
+            lhs == rhs
+                && lhs_args.len() == rhs_args.len()
+                && lhs_args
+                    .iter()
+                    .zip(rhs_args)
+                    .all(|(lhs, rhs)| matches_type(lhs, rhs, params))
+        }
+        (
+            TypeSpec::BorrowRead { lhs, rhs },
+            TypeSpec::BorrowRead {
+                lhs: type_lhs,
+                rhs: type_rhs,
+            },
+        )
+        | (
+            TypeSpec::BorrowWrite { lhs, rhs },
+            TypeSpec::BorrowWrite {
+                lhs: type_lhs,
+                rhs: type_rhs,
+            },
+        )
+        | (
+            TypeSpec::SpanRead { lhs, rhs },
+            TypeSpec::SpanRead {
+                lhs: type_lhs,
+                rhs: type_rhs,
+            },
+        )
+        | (
+            TypeSpec::SpanWrite { lhs, rhs },
+            TypeSpec::SpanWrite {
+                lhs: type_lhs,
+                rhs: type_rhs,
+            },
+        ) => matches_type(lhs, type_lhs, params) && matches_type(rhs, type_rhs, params),
+        _ => false,
+    }
+}
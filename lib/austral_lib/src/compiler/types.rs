@@ -0,0 +1,377 @@
+use super::BuildContext;
+use crate::{
+    ast::{Ident, RecordDecl, TypeParam, TypeSpec, UnionDecl},
+    error::{CodegenError, CodegenResult},
+    r#type::Ty,
+    type_system::{integer_bits, primitive_type},
+};
+use melior::{
+    dialect::llvm,
+    ir::{r#type::IntegerType, Type},
+    Context,
+};
+use std::collections::HashMap;
+
+/// Records are lowered to LLVM structs with their slots in declaration order. LLVM lays them out
+/// following the C rules of the target, so records can be passed to and from foreign functions as
+/// the equivalent C struct.
+fn build_record_type<'c>(ctx: &'c BuildContext<'c>, r#type: &TypeSpec) -> CodegenResult<Type<'c>> {
+    let (decl, args) = record_decl(ctx, r#type).expect("type should be a record");
+    let fields = decl
+        .slots
+        .iter()
+        .map(|slot| build_type(ctx, &substitute(&slot.r#type, &decl.type_params, args)))
+        .collect::<CodegenResult<Vec<_>>>()?;
+
+    Ok(llvm::r#type::r#struct(ctx, &fields, false))
+}
+
+/// How the values of a union are represented.
+pub(super) enum UnionLayout {
+    /// No case has any fields, so the tag is the whole value.
+    Tag { bits: u32 },
+    /// One empty case and one holding a single pointer. Pointers are never null, so null stands
+    /// for the empty case and the value is just the pointer.
+    Nullable { empty: usize, full: usize },
+    /// A tag followed by storage for the largest case, which every case reads and writes as a
+    /// struct of its fields.
+    Tagged {
+        tag_bits: u32,
+        size: u64,
+        align: u64,
+    },
+}
+
+pub(super) fn union_layout(
+    ctx: &BuildContext<'_>,
+    r#type: &TypeSpec,
+) -> CodegenResult<UnionLayout> {
+    let (decl, args) = union_decl(ctx, r#type).expect("type should be a union");
+    let cases = decl
+        .cases
+        .iter()
+        .map(|case| {
+            case.fields
+                .iter()
+                .map(|field| substitute(&field.r#type, &decl.type_params, args))
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+
+    let is_pointer =
+        |x: &TypeSpec| matches!(x, TypeSpec::Generic { name, .. } if name.name == "Pointer");
+    match cases.as_slice() {
+        [a, b] if a.is_empty() && matches!(b.as_slice(), [x] if is_pointer(x)) => {
+            return Ok(UnionLayout::Nullable { empty: 0, full: 1 });
+        }
+        [a, b] if b.is_empty() && matches!(a.as_slice(), [x] if is_pointer(x)) => {
+            return Ok(UnionLayout::Nullable { empty: 1, full: 0 });
+        }
+        _ => {}
+    }
+
+    let tag_bits = tag_bits(decl);
+    if cases.iter().all(Vec::is_empty) {
+        return Ok(UnionLayout::Tag { bits: tag_bits });
+    }
+
+    let mut size = 0;
+    let mut align = 1;
+    for fields in &cases {
+        let fields = fields
+            .iter()
+            .map(|x| type_layout(ctx, x))
+            .collect::<CodegenResult<Vec<_>>>()?;
+        let (case_size, case_align) = struct_layout(fields);
+        size = size.max(case_size);
+        align = align.max(case_align);
+    }
+    Ok(UnionLayout::Tagged {
+        tag_bits,
+        size: size.next_multiple_of(align),
+        align,
+    })
+}
+
+/// The tag is the smallest integer that can number every case.
+pub(super) fn tag_bits(decl: &UnionDecl) -> u32 {
+    match decl.cases.len() {
+        0..=0x100 => 8,
+        0x101..=0x1_0000 => 16,
+        _ => 32,
+    }
+}
+
+pub(super) fn build_union_type<'c>(
+    ctx: &'c BuildContext<'c>,
+    r#type: &TypeSpec,
+) -> CodegenResult<Type<'c>> {
+    Ok(match union_layout(ctx, r#type)? {
+        UnionLayout::Tag { bits } => IntegerType::new(ctx, bits).into(),
+        UnionLayout::Nullable { .. } => llvm::r#type::opaque_pointer(ctx),
+        UnionLayout::Tagged {
+            tag_bits,
+            size,
+            align,
+        } => {
+            let storage = llvm::r#type::array(
+                IntegerType::new(ctx, (align * 8) as u32).into(),
+                (size / align) as u32,
+            );
+            llvm::r#type::r#struct(
+                ctx,
+                &[IntegerType::new(ctx, tag_bits).into(), storage],
+                false,
+            )
+        }
+    })
+}
+
+/// The size and alignment in bytes of a type on the target.
+pub(super) fn type_layout(ctx: &BuildContext<'_>, r#type: &TypeSpec) -> CodegenResult<(u64, u64)> {
+    let layout = &ctx.data_layout;
+    Ok(match r#type {
+        TypeSpec::Simple { name } => match name.name.as_str() {
+            "ExitCode" => layout.integer(32),
+            "RootCapability" => layout.integer(8),
+            name => match primitive_type(name) {
+                Some(Ty::Unit | Ty::Boolean) => layout.integer(8),
+                Some(Ty::Integer(_, width)) => layout.integer(integer_bits(width)),
+                Some(Ty::SingleFloat) => layout.float32,
+                Some(Ty::DoubleFloat) => layout.float64,
+                _ => aggregate_layout(ctx, r#type)?,
+            },
+        },
+        TypeSpec::Generic { name, .. } => match name.name.as_str() {
+            "Address" | "Pointer" | "Fn" => layout.pointer,
+            _ => aggregate_layout(ctx, r#type)?,
+        },
+        TypeSpec::BorrowRead { .. } | TypeSpec::BorrowWrite { .. } => layout.pointer,
+        TypeSpec::SpanRead { .. } | TypeSpec::SpanWrite { .. } => {
+            struct_layout([layout.pointer, layout.integer(64)])
+        }
+    })
+}
+
+fn aggregate_layout(ctx: &BuildContext<'_>, r#type: &TypeSpec) -> CodegenResult<(u64, u64)> {
+    if let Some((decl, args)) = record_decl(ctx, r#type) {
+        let slots = decl
+            .slots
+            .iter()
+            .map(|slot| type_layout(ctx, &substitute(&slot.r#type, &decl.type_params, args)))
+            .collect::<CodegenResult<Vec<_>>>()?;
+        return Ok(struct_layout(slots));
+    }
+    if union_decl(ctx, r#type).is_none() {
+        return Err(unknown_type(r#type));
+    }
+
+    Ok(match union_layout(ctx, r#type)? {
+        UnionLayout::Tag { bits } => ctx.data_layout.integer(bits),
+        UnionLayout::Nullable { .. } => ctx.data_layout.pointer,
+        UnionLayout::Tagged {
+            tag_bits,
+            size,
+            align,
+        } => struct_layout([ctx.data_layout.integer(tag_bits), (size, align)]),
+    })
+}
+
+/// Place each field at the next multiple of its alignment, and pad the end to the largest one.
+fn struct_layout(fields: impl IntoIterator<Item = (u64, u64)>) -> (u64, u64) {
+    let (size, align) =
+        fields
+            .into_iter()
+            .fold((0, 1), |(size, align), (field_size, field_align)| {
+                (
+                    size.next_multiple_of(field_align) + field_size,
+                    align.max(field_align),
+                )
+            });
+    (size.next_multiple_of(align), align)
+}
+
+/// Return the declaration of a record type along with its type arguments.
+fn record_decl<'r>(
+    ctx: &'r BuildContext<'_>,
+    r#type: &'r TypeSpec,
+) -> Option<(&'r RecordDecl, &'r [TypeSpec])> {
+    type_decl(&ctx.records, r#type)
+}
+
+/// Return the declaration of a union type along with its type arguments.
+pub(super) fn union_decl<'r>(
+    ctx: &'r BuildContext<'_>,
+    r#type: &'r TypeSpec,
+) -> Option<(&'r UnionDecl, &'r [TypeSpec])> {
+    type_decl(&ctx.unions, r#type)
+}
+
+fn type_decl<'r, T>(
+    decls: &'r HashMap<String, T>,
+    r#type: &'r TypeSpec,
+) -> Option<(&'r T, &'r [TypeSpec])> {
+    match r#type {
+        TypeSpec::Simple { name } => decls.get(&name.name).map(|x| (x, &[][..])),
+        TypeSpec::Generic { name, type_params } => {
+            decls.get(&name.name).map(|x| (x, type_params.as_slice()))
+        }
+        _ => None,
+    }
+}
+
+/// Find a slot of a record type, returning its position and its type once the record's type
+/// arguments are substituted.
+pub(super) fn record_slot(
+    ctx: &BuildContext<'_>,
+    r#type: &TypeSpec,
+    name: &str,
+) -> Option<(usize, TypeSpec)> {
+    let (decl, args) = record_decl(ctx, r#type)?;
+    decl.slots
+        .iter()
+        .position(|slot| slot.name.name == name)
+        .map(|index| {
+            let r#type = substitute(&decl.slots[index].r#type, &decl.type_params, args);
+            (index, r#type)
+        })
+}
+
+/// Replace the type parameters in a type by their arguments.
+pub(super) fn substitute(r#type: &TypeSpec, params: &[TypeParam], args: &[TypeSpec]) -> TypeSpec {
+    let map = |x: &TypeSpec| Box::new(substitute(x, params, args));
+    match r#type {
+        TypeSpec::Simple { name } => params
+            .iter()
+            .position(|param| param.name == *name)
+            .map_or_else(|| r#type.clone(), |index| args[index].clone()),
+        TypeSpec::Generic { name, type_params } => TypeSpec::Generic {
+            name: name.clone(),
+            type_params: type_params
+                .iter()
+                .map(|x| substitute(x, params, args))
+                .collect(),
+        },
+        TypeSpec::BorrowRead { lhs, rhs } => TypeSpec::BorrowRead {
+            lhs: map(lhs),
+            rhs: map(rhs),
+        },
+        TypeSpec::BorrowWrite { lhs, rhs } => TypeSpec::BorrowWrite {
+            lhs: map(lhs),
+            rhs: map(rhs),
+        },
+        TypeSpec::SpanRead { lhs, rhs } => TypeSpec::SpanRead {
+            lhs: map(lhs),
+            rhs: map(rhs),
+        },
+        TypeSpec::SpanWrite { lhs, rhs } => TypeSpec::SpanWrite {
+            lhs: map(lhs),
+            rhs: map(rhs),
+        },
+    }
+}
+
+pub(super) fn mentions_type_params(r#type: &TypeSpec, params: &[TypeParam]) -> bool {
+    match r#type {
+        TypeSpec::Simple { name } => params.iter().any(|param| param.name == *name),
+        TypeSpec::Generic { type_params, .. } => {
+            type_params.iter().any(|x| mentions_type_params(x, params))
+        }
+        TypeSpec::BorrowRead { lhs, rhs }
+        | TypeSpec::BorrowWrite { lhs, rhs }
+        | TypeSpec::SpanRead { lhs, rhs }
+        | TypeSpec::SpanWrite { lhs, rhs } => {
+            mentions_type_params(lhs, params) || mentions_type_params(rhs, params)
+        }
+    }
+}
+
+pub(super) fn build_type<'c>(
+    ctx: &'c BuildContext<'c>,
+    r#type: &TypeSpec,
+) -> CodegenResult<Type<'c>> {
+    Ok(match r#type {
+        TypeSpec::Simple { name } => match name.name.as_str() {
+            "ExitCode" => IntegerType::new(ctx, 32).into(),
+            // Capabilities carry no data, they only exist for the type checker.
+            "RootCapability" => IntegerType::new(ctx, 1).into(),
+            name => match primitive_type(name) {
+                // `Unit` has a single value, `nil`, lowered to `false`.
+                Some(Ty::Unit | Ty::Boolean) => IntegerType::new(ctx, 1).into(),
+                Some(Ty::Integer(_, width)) => IntegerType::new(ctx, integer_bits(width)).into(),
+                Some(Ty::SingleFloat) => Type::float32(ctx),
+                Some(Ty::DoubleFloat) => Type::float64(ctx),
+                None if ctx.records.contains_key(name) => build_record_type(ctx, r#type)?,
+                None if ctx.unions.contains_key(name) => build_union_type(ctx, r#type)?,
+                // Type parameters, and types declared in other modules.
+                _ => return Err(unknown_type(r#type)),
+            },
+        },
+        TypeSpec::Generic { name, .. } => match name.name.as_str() {
+            // Addresses, pointers and function pointers are all opaque pointers to LLVM, the
+            // pointee type only matters to the type checker.
+            "Address" | "Pointer" | "Fn" => llvm::r#type::opaque_pointer(ctx),
+            name if ctx.records.contains_key(name) => build_record_type(ctx, r#type)?,
+            name if ctx.unions.contains_key(name) => build_union_type(ctx, r#type)?,
+            _ => return Err(unknown_type(r#type)),
+        },
+        TypeSpec::BorrowRead { .. } | TypeSpec::BorrowWrite { .. } => {
+            llvm::r#type::opaque_pointer(ctx)
+        }
+        // Spans are a pointer to their first element and their length.
+        TypeSpec::SpanRead { .. } | TypeSpec::SpanWrite { .. } => llvm::r#type::r#struct(
+            ctx,
+            &[
+                llvm::r#type::opaque_pointer(ctx),
+                IntegerType::new(ctx, 64).into(),
+            ],
+            false,
+        ),
+    })
+}
+
+/// Foreign functions take spans as the pointer to their first element, like C strings and arrays.
+pub(super) fn build_foreign_param_type<'c>(
+    ctx: &'c BuildContext<'c>,
+    r#type: &TypeSpec,
+) -> CodegenResult<Type<'c>> {
+    match r#type {
+        TypeSpec::SpanRead { .. } | TypeSpec::SpanWrite { .. } => {
+            Ok(llvm::r#type::opaque_pointer(ctx))
+        }
+        _ => build_type(ctx, r#type),
+    }
+}
+
+fn unknown_type(r#type: &TypeSpec) -> CodegenError {
+    match r#type {
+        TypeSpec::Simple { name } | TypeSpec::Generic { name, .. } => {
+            CodegenError::UnknownType(name.name.clone())
+        }
+        _ => unreachable!("borrows and spans have a known type"),
+    }
+}
+
+/// The type of string literals.
+pub(super) fn static_str_type() -> TypeSpec {
+    TypeSpec::SpanRead {
+        lhs: Box::new(TypeSpec::Simple {
+            name: Ident::new("Nat8"),
+        }),
+        rhs: Box::new(TypeSpec::Simple {
+            name: Ident::new("Static"),
+        }),
+    }
+}
+
+/// Strings are passed to and from the runtime the way spans are lowered.
+pub(super) fn string_type(ctx: &Context) -> Type<'_> {
+    llvm::r#type::r#struct(
+        ctx,
+        &[
+            llvm::r#type::opaque_pointer(ctx),
+            IntegerType::new(ctx, 64).into(),
+        ],
+        false,
+    )
+}
@@ -0,0 +1,463 @@
+use crate::{
+    ast::{
+        ArithExpr, AtomicExpr, CastExpr, CmpExpr, CompoundExpr, ConstantDef, Expression, Ident,
+        LogicExpr, ModuleDef, ModuleDefItem, TypeSpec,
+    },
+    error::{ConstantError as Error, ConstantResult as Result},
+    r#type::Ty,
+    type_system::{integer_bounds, primitive_type},
+};
+use std::{
+    cmp::Ordering,
+    collections::HashMap,
+    fmt::{self, Display, Formatter},
+};
+
+/// Constants provided by the language, as (name, type). Their value is the bound of the type
+/// they're named after.
+const BUILTIN_CONSTANTS: &[(&str, &str)] = &[
+    ("minimum_nat8", "Nat8"),
+    ("maximum_nat8", "Nat8"),
+    ("minimum_nat16", "Nat16"),
+    ("maximum_nat16", "Nat16"),
+    ("minimum_nat32", "Nat32"),
+    ("maximum_nat32", "Nat32"),
+    ("minimum_nat64", "Nat64"),
+    ("maximum_nat64", "Nat64"),
+    ("minimum_int8", "Int8"),
+    ("maximum_int8", "Int8"),
+    ("minimum_int16", "Int16"),
+    ("maximum_int16", "Int16"),
+    ("minimum_int32", "Int32"),
+    ("maximum_int32", "Int32"),
+    ("minimum_int64", "Int64"),
+    ("maximum_int64", "Int64"),
+];
+
+/// The value of a constant expression.
+///
+/// Character literals are folded into their code point, since they are integers in Austral.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ConstValue {
+    Unit,
+    Bool(bool),
+    Int(i128),
+    Float(f64),
+    Str(String),
+}
+
+impl Display for ConstValue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unit => f.write_str("nil"),
+            Self::Bool(value) => write!(f, "{value}"),
+            Self::Int(value) => write!(f, "{value}"),
+            Self::Float(value) => write!(f, "{value}"),
+            Self::Str(value) => write!(f, "{value:?}"),
+        }
+    }
+}
+
+/// A constant folded to its value.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Constant {
+    pub value: ConstValue,
+    pub r#type: TypeSpec,
+}
+
+/// Evaluate every constant defined in the module.
+pub fn evaluate_module(module: &ModuleDef) -> Result<HashMap<String, Constant>> {
+    let mut evaluator = Evaluator {
+        definitions: module
+            .contents
+            .iter()
+            .filter_map(|item| match item {
+                ModuleDefItem::Constant(def) => Some((def.name.name.as_str(), def)),
+                _ => None,
+            })
+            .collect(),
+        values: HashMap::new(),
+        in_progress: Vec::new(),
+    };
+
+    let names = evaluator.definitions.keys().copied().collect::<Vec<_>>();
+    names
+        .into_iter()
+        .map(|name| {
+            let value = evaluator.constant(name)?;
+            let constant = Constant {
+                value,
+                r#type: evaluator.definitions[name].r#type.clone(),
+            };
+            Ok((name.to_string(), constant))
+        })
+        .collect()
+}
+
+/// Return a constant provided by the language, such as `maximum_nat8`.
+pub fn builtin_constant(name: &str) -> Option<Constant> {
+    let (_, type_name) = BUILTIN_CONSTANTS.iter().find(|(x, _)| *x == name)?;
+    let Some(Ty::Integer(signedness, width)) = primitive_type(type_name) else {
+        unreachable!()
+    };
+
+    let (min, max) = integer_bounds(signedness, width);
+    Some(Constant {
+        value: ConstValue::Int(if name.starts_with("minimum") {
+            min
+        } else {
+            max
+        }),
+        r#type: TypeSpec::Simple {
+            name: Ident::new(*type_name),
+        },
+    })
+}
+
+struct Evaluator<'a> {
+    definitions: HashMap<&'a str, &'a ConstantDef>,
+    values: HashMap<&'a str, ConstValue>,
+    /// The constants currently being evaluated, used to detect cycles.
+    in_progress: Vec<&'a str>,
+}
+
+impl<'a> Evaluator<'a> {
+    fn constant(&mut self, name: &str) -> Result<ConstValue> {
+        if let Some(value) = self.values.get(name) {
+            return Ok(value.clone());
+        }
+
+        let Some((&name, &def)) = self.definitions.get_key_value(name) else {
+            return builtin_constant(name)
+                .map(|x| x.value)
+                .ok_or_else(|| Error::UnknownConstant(name.to_string()));
+        };
+        if self.in_progress.contains(&name) {
+            return Err(Error::CyclicConstant(name.to_string()));
+        }
+
+        self.in_progress.push(name);
+        let value = self.expression(&def.value, Some(&def.r#type))?;
+        let value = self.conform(value, &def.r#type)?;
+        self.in_progress.pop();
+
+        self.values.insert(name, value.clone());
+        Ok(value)
+    }
+
+    /// The name of the constant being evaluated, for error messages.
+    fn current(&self) -> String {
+        self.in_progress.last().unwrap().to_string()
+    }
+
+    /// Evaluate an expression. Integer results are checked against the expected type, if known.
+    fn expression(&mut self, expr: &Expression, r#type: Option<&TypeSpec>) -> Result<ConstValue> {
+        match expr {
+            Expression::Atomic(expr) => self.atomic(expr, r#type),
+            Expression::Compound(expr) => match expr {
+                CompoundExpr::Arith(expr) => {
+                    let value = match expr {
+                        ArithExpr::Add(lhs, rhs) => {
+                            self.arith(lhs, rhs, r#type, i128::checked_add, |x, y| x + y)?
+                        }
+                        ArithExpr::Sub(lhs, rhs) => {
+                            self.arith(lhs, rhs, r#type, i128::checked_sub, |x, y| x - y)?
+                        }
+                        ArithExpr::Mul(lhs, rhs) => {
+                            self.arith(lhs, rhs, r#type, i128::checked_mul, |x, y| x * y)?
+                        }
+                        ArithExpr::Div(lhs, rhs) => {
+                            if self.atomic(rhs, r#type)? == ConstValue::Int(0) {
+                                return Err(Error::DivisionByZero(self.current()));
+                            }
+                            self.arith(lhs, rhs, r#type, i128::checked_div, |x, y| x / y)?
+                        }
+                        ArithExpr::Neg(expr) => match self.atomic(expr, None)? {
+                            ConstValue::Int(value) => ConstValue::Int(-value),
+                            ConstValue::Float(value) => ConstValue::Float(-value),
+                            _ => return Err(Error::InvalidOperands(self.current())),
+                        },
+                    };
+                    match r#type {
+                        Some(r#type) if matches!(value, ConstValue::Int(_)) => {
+                            self.conform(value, r#type)
+                        }
+                        _ => Ok(value),
+                    }
+                }
+                CompoundExpr::Cmp(expr) => {
+                    let (lhs, rhs, accept): (_, _, fn(Ordering) -> bool) = match expr {
+                        CmpExpr::Eq(lhs, rhs) => (lhs, rhs, Ordering::is_eq),
+                        CmpExpr::NotEq(lhs, rhs) => (lhs, rhs, Ordering::is_ne),
+                        CmpExpr::Lt(lhs, rhs) => (lhs, rhs, Ordering::is_lt),
+                        CmpExpr::LtEq(lhs, rhs) => (lhs, rhs, Ordering::is_le),
+                        CmpExpr::Gt(lhs, rhs) => (lhs, rhs, Ordering::is_gt),
+                        CmpExpr::GtEq(lhs, rhs) => (lhs, rhs, Ordering::is_ge),
+                    };
+
+                    let ordering = match (self.atomic(lhs, None)?, self.atomic(rhs, None)?) {
+                        (ConstValue::Unit, ConstValue::Unit) => Some(Ordering::Equal),
+                        (ConstValue::Bool(x), ConstValue::Bool(y)) => x.partial_cmp(&y),
+                        (ConstValue::Int(x), ConstValue::Int(y)) => x.partial_cmp(&y),
+                        (ConstValue::Float(x), ConstValue::Float(y)) => x.partial_cmp(&y),
+                        (ConstValue::Str(x), ConstValue::Str(y)) => x.partial_cmp(&y),
+                        _ => return Err(Error::InvalidOperands(self.current())),
+                    };
+                    // Comparisons involving NaN are always false, except for inequality.
+                    Ok(ConstValue::Bool(
+                        ordering.map_or(matches!(expr, CmpExpr::NotEq(..)), accept),
+                    ))
+                }
+                CompoundExpr::Logic(expr) => {
+                    let value = match expr {
+                        LogicExpr::And(lhs, rhs) => self.boolean(lhs)? && self.boolean(rhs)?,
+                        LogicExpr::Or(lhs, rhs) => self.boolean(lhs)? || self.boolean(rhs)?,
+                        LogicExpr::Not(expr) => !self.boolean(expr)?,
+                    };
+                    Ok(ConstValue::Bool(value))
+                }
+                CompoundExpr::Select(expr) => {
+                    let condition = match self.expression(&expr.condition, None)? {
+                        ConstValue::Bool(value) => value,
+                        _ => return Err(Error::InvalidOperands(self.current())),
+                    };
+                    if condition {
+                        self.expression(&expr.value_true, r#type)
+                    } else {
+                        self.expression(&expr.value_false, r#type)
+                    }
+                }
+                CompoundExpr::Cast(expr) => self.cast(expr),
+            },
+        }
+    }
+
+    fn atomic(&mut self, expr: &AtomicExpr, r#type: Option<&TypeSpec>) -> Result<ConstValue> {
+        match expr {
+            AtomicExpr::ConstNil => Ok(ConstValue::Unit),
+            AtomicExpr::ConstBool(value) => Ok(ConstValue::Bool(*value)),
+            AtomicExpr::ConstChar(value) => Ok(ConstValue::Int(*value as i128)),
+            AtomicExpr::ConstInt(value) => Ok(ConstValue::Int(*value as i128)),
+            AtomicExpr::ConstFloat(value) => Ok(ConstValue::Float(*value)),
+            AtomicExpr::ConstStr(value) => Ok(ConstValue::Str(value.clone())),
+            AtomicExpr::Path(expr) if expr.extra.is_empty() => self.constant(&expr.first.name),
+            AtomicExpr::Paren(expr) => self.expression(expr, r#type),
+            _ => Err(Error::NotConstant(self.current())),
+        }
+    }
+
+    fn arith(
+        &mut self,
+        lhs: &AtomicExpr,
+        rhs: &AtomicExpr,
+        r#type: Option<&TypeSpec>,
+        int_op: fn(i128, i128) -> Option<i128>,
+        float_op: fn(f64, f64) -> f64,
+    ) -> Result<ConstValue> {
+        match (self.atomic(lhs, r#type)?, self.atomic(rhs, r#type)?) {
+            (ConstValue::Int(x), ConstValue::Int(y)) => int_op(x, y)
+                .map(ConstValue::Int)
+                .ok_or_else(|| Error::InvalidOperands(self.current())),
+            (ConstValue::Float(x), ConstValue::Float(y)) => Ok(ConstValue::Float(float_op(x, y))),
+            _ => Err(Error::InvalidOperands(self.current())),
+        }
+    }
+
+    fn boolean(&mut self, expr: &AtomicExpr) -> Result<bool> {
+        match self.atomic(expr, None)? {
+            ConstValue::Bool(value) => Ok(value),
+            _ => Err(Error::InvalidOperands(self.current())),
+        }
+    }
+
+    fn cast(&mut self, expr: &CastExpr) -> Result<ConstValue> {
+        let value = self.atomic(&expr.value, None)?;
+        let value = match (value, primitive_type(&type_name(&expr.r#type))) {
+            (ConstValue::Int(value), Some(Ty::SingleFloat | Ty::DoubleFloat)) => {
+                ConstValue::Float(value as f64)
+            }
+            (ConstValue::Float(value), Some(Ty::Integer(..))) => {
+                ConstValue::Int(value.trunc() as i128)
+            }
+            (value @ ConstValue::Int(_), Some(Ty::Integer(..)))
+            | (value @ ConstValue::Float(_), Some(Ty::SingleFloat | Ty::DoubleFloat))
+            | (value @ ConstValue::Bool(_), Some(Ty::Boolean)) => value,
+            _ => {
+                return Err(Error::InvalidCast {
+                    name: self.current(),
+                    r#type: expr.r#type.to_string(),
+                })
+            }
+        };
+
+        self.conform(value, &expr.r#type)
+    }
+
+    /// Check that a value is of the given type, rounding floats to single precision if needed.
+    fn conform(&self, value: ConstValue, r#type: &TypeSpec) -> Result<ConstValue> {
+        let mismatch = || Error::TypeMismatch {
+            name: self.current(),
+            r#type: r#type.to_string(),
+        };
+
+        match (value, primitive_type(&type_name(r#type))) {
+            (ConstValue::Int(value), Some(Ty::Integer(signedness, width))) => {
+                let (min, max) = integer_bounds(signedness, width);
+                if value < min || value > max {
+                    return Err(Error::OutOfRange {
+                        name: self.current(),
+                        value: value.to_string(),
+                        r#type: r#type.to_string(),
+                    });
+                }
+                Ok(ConstValue::Int(value))
+            }
+            (ConstValue::Float(value), Some(Ty::SingleFloat)) => {
+                Ok(ConstValue::Float(value as f32 as f64))
+            }
+            (value @ ConstValue::Float(_), Some(Ty::DoubleFloat))
+            | (value @ ConstValue::Bool(_), Some(Ty::Boolean))
+            | (value @ ConstValue::Unit, Some(Ty::Unit)) => Ok(value),
+            (value @ ConstValue::Str(_), None) if is_string_type(r#type) => Ok(value),
+            _ => Err(mismatch()),
+        }
+    }
+}
+
+/// Return whether string literals conform to a type, i.e. whether it's a span of bytes.
+fn is_string_type(r#type: &TypeSpec) -> bool {
+    matches!(r#type, TypeSpec::SpanRead { lhs, .. } if type_name(lhs) == "Nat8")
+}
+
+/// The name of a simple type, or an empty string for any other type.
+fn type_name(r#type: &TypeSpec) -> String {
+    match r#type {
+        TypeSpec::Simple { name } => name.name.clone(),
+        _ => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::lexer::lex;
+    use chumsky::Parser;
+
+    fn evaluate(constants: &str) -> Result<HashMap<String, ConstValue>> {
+        let source = format!("module body Test is {constants} end module body.");
+        let tokens = lex(&source)
+            .map(|(token, _)| token.unwrap())
+            .collect::<Vec<_>>();
+        let module = ModuleDef::parser().parse(&tokens).into_result().unwrap();
+
+        evaluate_module(&module).map(|constants| {
+            constants
+                .into_iter()
+                .map(|(name, constant)| (name, constant.value))
+                .collect()
+        })
+    }
+
+    #[test]
+    fn literals() {
+        let values = evaluate(
+            r#"constant a : Nat8 := 'a';
+            constant b : Bool := true;
+            constant x : Float64 := 2.5;
+            constant s : Span[Nat8, Static] := "hello";"#,
+        )
+        .unwrap();
+
+        assert_eq!(values["a"], ConstValue::Int(97));
+        assert_eq!(values["b"], ConstValue::Bool(true));
+        assert_eq!(values["x"], ConstValue::Float(2.5));
+        assert_eq!(values["s"], ConstValue::Str("hello".to_string()));
+    }
+
+    #[test]
+    fn arithmetic_and_references() {
+        let values = evaluate(
+            "constant c : Int32 := b * 2;
+            constant a : Int32 := 10;
+            constant b : Int32 := a - 3;
+            constant d : Bool := (c > a) and (not (a = b));
+            constant e : Float64 := (c : Float64) / 4.0;
+            constant f : Int32 := if d then -c else c;",
+        )
+        .unwrap();
+
+        assert_eq!(values["c"], ConstValue::Int(14));
+        assert_eq!(values["d"], ConstValue::Bool(true));
+        assert_eq!(values["e"], ConstValue::Float(3.5));
+        assert_eq!(values["f"], ConstValue::Int(-14));
+    }
+
+    #[test]
+    fn builtin_constants() {
+        let values = evaluate(
+            "constant a : Nat8 := maximum_nat8;
+            constant b : Int64 := minimum_int64;",
+        )
+        .unwrap();
+
+        assert_eq!(values["a"], ConstValue::Int(255));
+        assert_eq!(values["b"], ConstValue::Int(i64::MIN as i128));
+
+        assert_eq!(
+            evaluate("constant a : Nat8 := maximum_nat8 + 1;"),
+            Err(Error::OutOfRange {
+                name: "a".to_string(),
+                value: "256".to_string(),
+                r#type: "Nat8".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn invalid_constants() {
+        assert!(matches!(
+            evaluate("constant a : Int32 := b; constant b : Int32 := a;"),
+            Err(Error::CyclicConstant(_))
+        ));
+        assert_eq!(
+            evaluate("constant a : Int32 := 1 / 0;"),
+            Err(Error::DivisionByZero("a".to_string()))
+        );
+        assert_eq!(
+            evaluate("constant a : Int32 := f();"),
+            Err(Error::NotConstant("a".to_string()))
+        );
+        assert_eq!(
+            evaluate("constant a : Int32 := b;"),
+            Err(Error::UnknownConstant("b".to_string()))
+        );
+        assert_eq!(
+            evaluate("constant a : Bool := 1;"),
+            Err(Error::TypeMismatch {
+                name: "a".to_string(),
+                r#type: "Bool".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn string_constants() {
+        let values = evaluate(r#"constant s : Span[Nat8, Static] := "x";"#).unwrap();
+        assert_eq!(values["s"], ConstValue::Str("x".to_string()));
+
+        assert_eq!(
+            evaluate(r#"constant c : Option[Int32] := "x";"#),
+            Err(Error::TypeMismatch {
+                name: "c".to_string(),
+                r#type: "Option[Int32]".to_string(),
+            })
+        );
+        assert_eq!(
+            evaluate(r#"constant c : Span[Int32, Static] := "x";"#),
+            Err(Error::TypeMismatch {
+                name: "c".to_string(),
+                r#type: "Span[Int32, Static]".to_string(),
+            })
+        );
+    }
+}
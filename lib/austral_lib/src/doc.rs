@@ -0,0 +1,843 @@
+//! Documentation for the public interface of a set of modules, rendered as HTML or Markdown pages
+//! from their docstrings. Types named within signatures link to their declarations whenever those
+//! are part of the set, be they declared by the same module or imported from another one.
+
+use crate::{
+    ast::{
+        ConstantDecl, DocString, FunctionDecl, Ident, InstanceDecl, MethodDecl, ModuleDecl,
+        ModuleDeclItem, Param, RecordDecl, Slot, TypeClassDecl, TypeDecl, TypeParam, TypeSpec,
+        UnionDecl, Universe,
+    },
+    r#type,
+};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Write,
+};
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Format {
+    Html,
+    Markdown,
+}
+
+impl Format {
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Html => "html",
+            Self::Markdown => "md",
+        }
+    }
+}
+
+/// A rendered page, named relative to the root of the documentation.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Page {
+    pub file_name: String,
+    pub contents: String,
+}
+
+/// Render a page per module, plus an index linking to all of them.
+pub fn render(modules: &[ModuleDecl], format: Format) -> Vec<Page> {
+    let index = Index::new(modules);
+
+    let mut pages = vec![Page {
+        file_name: format!("index.{}", format.extension()),
+        contents: render_index(modules, format),
+    }];
+    pages.extend(modules.iter().map(|module| Page {
+        file_name: page_name(&module.name.name, format),
+        contents: render_module(&index, module, format),
+    }));
+
+    pages
+}
+
+fn page_name(module: &str, format: Format) -> String {
+    format!("{module}.{}", format.extension())
+}
+
+/// The declarations of the module set, which signatures can link to.
+struct Index<'a> {
+    decls: HashMap<&'a str, HashSet<&'a str>>,
+    instances: Vec<(&'a ModuleDecl, &'a InstanceDecl)>,
+}
+
+impl<'a> Index<'a> {
+    fn new(modules: &'a [ModuleDecl]) -> Self {
+        let mut decls = HashMap::<_, HashSet<_>>::new();
+        let mut instances = Vec::new();
+        for module in modules {
+            for item in &module.contents {
+                match item {
+                    ModuleDeclItem::Instance(decl) => instances.push((module, decl)),
+                    item => {
+                        decls
+                            .entry(module.name.name.as_str())
+                            .or_default()
+                            .insert(item_name(item));
+                    }
+                }
+            }
+        }
+
+        Self { decls, instances }
+    }
+
+    /// Resolve `name` as seen from `module`: its own declarations come first, then its imports.
+    /// The result is the module and name of the declaration.
+    fn resolve(&self, module: &ModuleDecl, name: &str) -> Option<(String, String)> {
+        if self.declares(&module.name.name, name) {
+            return Some((module.name.name.clone(), name.to_string()));
+        }
+
+        module.imports.iter().find_map(|import| {
+            let source = import
+                .module
+                .iter()
+                .map(|ident| ident.name.as_str())
+                .collect::<Vec<_>>()
+                .join(".");
+            let symbol = import.symbols.iter().find(|symbol| {
+                symbol
+                    .rename_into
+                    .as_ref()
+                    .unwrap_or(&symbol.import_name)
+                    .name
+                    == name
+            })?;
+            let name = &symbol.import_name.name;
+
+            self.declares(&source, name)
+                .then(|| (source.clone(), name.clone()))
+        })
+    }
+
+    fn declares(&self, module: &str, name: &str) -> bool {
+        self.decls
+            .get(module)
+            .is_some_and(|names| names.contains(name))
+    }
+
+    /// The instances whose argument is the type `name` of `module`.
+    fn instances_of(&self, module: &str, name: &str) -> Vec<(&'a ModuleDecl, &'a InstanceDecl)> {
+        self.instances
+            .iter()
+            .copied()
+            .filter(|(instance_module, instance)| {
+                let arg = match &instance.arg {
+                    TypeSpec::Simple { name } | TypeSpec::Generic { name, .. } => &name.name,
+                    _ => return false,
+                };
+                self.resolve(instance_module, arg)
+                    .is_some_and(|target| target == (module.to_string(), name.to_string()))
+            })
+            .collect()
+    }
+
+    /// The instances of the typeclass `name` of `module`.
+    fn instances_of_class(
+        &self,
+        module: &str,
+        name: &str,
+    ) -> Vec<(&'a ModuleDecl, &'a InstanceDecl)> {
+        self.instances
+            .iter()
+            .copied()
+            .filter(|(instance_module, instance)| {
+                self.resolve(instance_module, &instance.name.name)
+                    .is_some_and(|target| target == (module.to_string(), name.to_string()))
+            })
+            .collect()
+    }
+}
+
+fn item_name(item: &ModuleDeclItem) -> &str {
+    match item {
+        ModuleDeclItem::Constant(decl) => &decl.name.name,
+        ModuleDeclItem::Function(decl) => &decl.name.name,
+        ModuleDeclItem::Instance(decl) => &decl.name.name,
+        ModuleDeclItem::Record(decl) => &decl.name.name,
+        ModuleDeclItem::Type(decl) => &decl.name.name,
+        ModuleDeclItem::TypeClass(decl) => &decl.name.name,
+        ModuleDeclItem::Union(decl) => &decl.name.name,
+    }
+}
+
+/// The anchor of an instance, which is named after its typeclass and argument.
+fn instance_anchor(instance: &InstanceDecl) -> String {
+    let anchor = format!("instance-{}-{}", instance.name, instance.arg);
+    anchor
+        .split(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+fn universe_name(universe: &Universe) -> String {
+    r#type::Universe::from(universe.0).to_string()
+}
+
+/// A piece of a signature: either source text, or the name of a type which may be linked.
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum Fragment {
+    Text(String),
+    Name(String),
+}
+
+/// The source of a declaration's signature, as written in a module interface.
+#[derive(Default)]
+struct Signature<'a> {
+    fragments: Vec<Fragment>,
+    /// Type parameters in scope, which shadow declarations with the same name.
+    type_params: Vec<&'a str>,
+}
+
+impl<'a> Signature<'a> {
+    fn text(&mut self, text: &str) -> &mut Self {
+        match self.fragments.last_mut() {
+            Some(Fragment::Text(last)) => last.push_str(text),
+            _ => self.fragments.push(Fragment::Text(text.to_string())),
+        }
+        self
+    }
+
+    fn name(&mut self, name: &str) -> &mut Self {
+        if self.type_params.contains(&name) {
+            self.text(name)
+        } else {
+            self.fragments.push(Fragment::Name(name.to_string()));
+            self
+        }
+    }
+
+    fn type_spec(&mut self, spec: &TypeSpec) -> &mut Self {
+        let (prefix, lhs, rhs) = match spec {
+            TypeSpec::Simple { name } => return self.name(&name.name),
+            TypeSpec::Generic { name, type_params } => {
+                self.name(&name.name).text("[");
+                for (i, param) in type_params.iter().enumerate() {
+                    if i != 0 {
+                        self.text(", ");
+                    }
+                    self.type_spec(param);
+                }
+                return self.text("]");
+            }
+            TypeSpec::BorrowRead { lhs, rhs } => ("&", lhs, rhs),
+            TypeSpec::BorrowWrite { lhs, rhs } => ("&!", lhs, rhs),
+            TypeSpec::SpanRead { lhs, rhs } => ("Span", lhs, rhs),
+            TypeSpec::SpanWrite { lhs, rhs } => ("Span!", lhs, rhs),
+        };
+
+        self.text(prefix).text("[").type_spec(lhs).text(", ");
+        self.type_spec(rhs).text("]")
+    }
+
+    /// Bring `params` into scope, then write them the way they're written after `generic`.
+    fn type_params(&mut self, params: &'a [TypeParam]) -> &mut Self {
+        self.type_params
+            .extend(params.iter().map(|param| param.name.name.as_str()));
+        for (i, param) in params.iter().enumerate() {
+            if i != 0 {
+                self.text(", ");
+            }
+            self.type_param(param);
+        }
+        self
+    }
+
+    fn type_param(&mut self, param: &TypeParam) -> &mut Self {
+        self.text(&format!(
+            "{}: {}",
+            param.name,
+            universe_name(&param.universe)
+        ));
+        if !param.params.is_empty() {
+            self.text("(");
+            for (i, constraint) in param.params.iter().enumerate() {
+                if i != 0 {
+                    self.text(", ");
+                }
+                self.name(&constraint.name);
+            }
+            self.text(")");
+        }
+        self
+    }
+
+    fn generic(&mut self, params: &'a [TypeParam]) -> &mut Self {
+        if params.is_empty() {
+            self
+        } else {
+            self.text("generic [").type_params(params).text("]\n")
+        }
+    }
+
+    fn params(&mut self, params: &[Param]) -> &mut Self {
+        self.text("(");
+        for (i, param) in params.iter().enumerate() {
+            if i != 0 {
+                self.text(", ");
+            }
+            self.text(&format!("{}: ", param.name))
+                .type_spec(&param.r#type);
+        }
+        self.text(")")
+    }
+
+    fn type_decl(&mut self, keyword: &str, name: &Ident, params: &'a [TypeParam]) -> &mut Self {
+        self.text(&format!("{keyword} {name}"));
+        if !params.is_empty() {
+            self.text("[").type_params(params).text("]");
+        }
+        self
+    }
+}
+
+/// The markup of a page, in either format.
+struct Writer {
+    format: Format,
+    out: String,
+}
+
+impl Writer {
+    fn escape(&self, text: &str) -> String {
+        match self.format {
+            Format::Html => text
+                .replace('&', "&amp;")
+                .replace('<', "&lt;")
+                .replace('>', "&gt;")
+                .replace('"', "&quot;"),
+            Format::Markdown => text.to_string(),
+        }
+    }
+
+    fn heading(&mut self, level: usize, anchor: Option<&str>, text: &str) {
+        let text = self.escape(text);
+        match (self.format, anchor) {
+            (Format::Html, Some(anchor)) => {
+                writeln!(self.out, "<h{level} id=\"{anchor}\">{text}</h{level}>").unwrap()
+            }
+            (Format::Html, None) => writeln!(self.out, "<h{level}>{text}</h{level}>").unwrap(),
+            (Format::Markdown, anchor) => {
+                if let Some(anchor) = anchor {
+                    writeln!(self.out, "<a id=\"{anchor}\"></a>\n").unwrap();
+                }
+                writeln!(self.out, "{} {text}\n", "#".repeat(level)).unwrap();
+            }
+        }
+    }
+
+    fn link(&self, text: &str, href: &str) -> String {
+        match self.format {
+            Format::Html => format!("<a href=\"{href}\">{}</a>", self.escape(text)),
+            Format::Markdown => format!("[{text}]({href})"),
+        }
+    }
+
+    fn docs(&mut self, doc_string: Option<&DocString>) {
+        let Some(doc_string) = doc_string.filter(|doc| !doc.contents().is_empty()) else {
+            return;
+        };
+
+        match self.format {
+            Format::Html => {
+                for paragraph in doc_string.contents().split("\n\n") {
+                    writeln!(self.out, "<p>{}</p>", self.escape(paragraph)).unwrap();
+                }
+            }
+            Format::Markdown => writeln!(self.out, "{}\n", doc_string.contents()).unwrap(),
+        }
+    }
+
+    /// Write a signature, linking the names `href` finds a target for.
+    fn signature(&mut self, signature: &Signature, href: impl Fn(&str) -> Option<String>) {
+        // Names without a target are plain text, so that the text around them isn't split.
+        let mut pieces: Vec<(String, Option<String>)> = Vec::new();
+        for fragment in &signature.fragments {
+            let (text, href) = match fragment {
+                Fragment::Text(text) => (text, None),
+                Fragment::Name(name) => (name, href(name)),
+            };
+            match (pieces.last_mut(), href) {
+                (Some((last, None)), None) => last.push_str(text),
+                (_, href) => pieces.push((text.clone(), href)),
+            }
+        }
+
+        let mut code = String::new();
+        for (text, href) in pieces {
+            match (self.format, href) {
+                (Format::Html, Some(href)) => code.push_str(&self.link(&text, &href)),
+                (Format::Html, None) => code.push_str(&self.escape(&text)),
+                (Format::Markdown, Some(href)) => {
+                    code.push_str(&self.link(&format!("`{text}`"), &href))
+                }
+                // Markdown has no links within code blocks, so the code is split in spans.
+                (Format::Markdown, None) => {
+                    for (i, line) in text.split('\n').enumerate() {
+                        if i != 0 {
+                            code.push_str("<br>\n");
+                        }
+                        if !line.is_empty() {
+                            write!(code, "`{line}`").unwrap();
+                        }
+                    }
+                }
+            }
+        }
+
+        match self.format {
+            Format::Html => writeln!(self.out, "<pre><code>{code}</code></pre>").unwrap(),
+            Format::Markdown => writeln!(self.out, "{code}\n").unwrap(),
+        }
+    }
+
+    fn list(&mut self, title: &str, items: &[String]) {
+        if items.is_empty() {
+            return;
+        }
+
+        match self.format {
+            Format::Html => {
+                if !title.is_empty() {
+                    writeln!(self.out, "<p>{}</p>", self.escape(title)).unwrap();
+                }
+                writeln!(self.out, "<ul>").unwrap();
+                for item in items {
+                    writeln!(self.out, "<li>{item}</li>").unwrap();
+                }
+                writeln!(self.out, "</ul>").unwrap();
+            }
+            Format::Markdown => {
+                if !title.is_empty() {
+                    writeln!(self.out, "{title}\n").unwrap();
+                }
+                for item in items {
+                    writeln!(self.out, "- {item}").unwrap();
+                }
+                writeln!(self.out).unwrap();
+            }
+        }
+    }
+
+    fn begin(&mut self, title: &str) {
+        if self.format == Format::Html {
+            let title = self.escape(title);
+            writeln!(
+                self.out,
+                "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{title}</title>\n</head>\n<body>"
+            )
+            .unwrap();
+        }
+    }
+
+    fn finish(mut self) -> String {
+        if self.format == Format::Html {
+            writeln!(self.out, "</body>\n</html>").unwrap();
+        }
+        self.out
+    }
+}
+
+fn render_index(modules: &[ModuleDecl], format: Format) -> String {
+    let mut writer = Writer {
+        format,
+        out: String::new(),
+    };
+
+    writer.begin("Modules");
+    writer.heading(1, None, "Modules");
+    let items = modules
+        .iter()
+        .map(|module| {
+            let name = &module.name.name;
+            let link = writer.link(name, &page_name(name, format));
+            let summary = module
+                .doc_string
+                .as_ref()
+                .and_then(|doc| doc.contents().split("\n\n").next())
+                .filter(|summary| !summary.is_empty());
+            match summary {
+                Some(summary) => format!("{link}: {}", writer.escape(summary)),
+                None => link,
+            }
+        })
+        .collect::<Vec<_>>();
+    writer.list("", &items);
+
+    writer.finish()
+}
+
+fn render_module(index: &Index, module: &ModuleDecl, format: Format) -> String {
+    let mut page = ModulePage {
+        index,
+        module,
+        writer: Writer {
+            format,
+            out: String::new(),
+        },
+    };
+
+    let title = format!("Module {}", module.name);
+    page.writer.begin(&title);
+    let index_link = page
+        .writer
+        .link("Index", &format!("index.{}", format.extension()));
+    match format {
+        Format::Html => writeln!(page.writer.out, "<nav>{index_link}</nav>").unwrap(),
+        Format::Markdown => writeln!(page.writer.out, "{index_link}\n").unwrap(),
+    }
+    page.writer.heading(1, None, &title);
+    page.writer.docs(module.doc_string.as_ref());
+
+    for item in &module.contents {
+        match item {
+            ModuleDeclItem::Constant(decl) => page.constant(decl),
+            ModuleDeclItem::Function(decl) => page.function(decl),
+            ModuleDeclItem::Instance(decl) => page.instance(decl),
+            ModuleDeclItem::Record(decl) => page.record(decl),
+            ModuleDeclItem::Type(decl) => page.r#type(decl),
+            ModuleDeclItem::TypeClass(decl) => page.type_class(decl),
+            ModuleDeclItem::Union(decl) => page.union(decl),
+        }
+    }
+
+    page.writer.finish()
+}
+
+struct ModulePage<'a> {
+    index: &'a Index<'a>,
+    module: &'a ModuleDecl,
+    writer: Writer,
+}
+
+impl<'a> ModulePage<'a> {
+    fn href(&self, module: &str, anchor: &str) -> String {
+        if module == self.module.name.name {
+            format!("#{anchor}")
+        } else {
+            format!("{}#{anchor}", page_name(module, self.writer.format))
+        }
+    }
+
+    fn signature(&mut self, signature: &Signature) {
+        let (index, module) = (self.index, self.module);
+        let format = self.writer.format;
+        self.writer.signature(signature, |name| {
+            let (target, name) = index.resolve(module, name)?;
+            Some(if target == module.name.name {
+                format!("#{name}")
+            } else {
+                format!("{}#{name}", page_name(&target, format))
+            })
+        });
+    }
+
+    /// List the instances whose typeclass or argument is the declaration `name`.
+    fn instances(&mut self, instances: Vec<(&ModuleDecl, &InstanceDecl)>) {
+        let items = instances
+            .into_iter()
+            .map(|(module, instance)| {
+                let text = format!("{}({})", instance.name, instance.arg);
+                let href = self.href(&module.name.name, &instance_anchor(instance));
+                self.writer.link(&text, &href)
+            })
+            .collect::<Vec<_>>();
+        self.writer.list("Instances:", &items);
+    }
+
+    fn constant(&mut self, decl: &ConstantDecl) {
+        self.writer
+            .heading(2, Some(&decl.name.name), &format!("constant {}", decl.name));
+        let mut signature = Signature::default();
+        signature
+            .text(&format!("constant {}: ", decl.name))
+            .type_spec(&decl.r#type)
+            .text(";");
+        self.signature(&signature);
+        self.writer.docs(decl.doc_string.as_ref());
+    }
+
+    fn r#type(&mut self, decl: &TypeDecl) {
+        self.writer
+            .heading(2, Some(&decl.name.name), &format!("type {}", decl.name));
+        let mut signature = Signature::default();
+        signature.text(&format!(
+            "type {}: {};",
+            decl.name,
+            universe_name(&decl.universe)
+        ));
+        self.signature(&signature);
+        self.writer.docs(decl.doc_string.as_ref());
+        self.instances(
+            self.index
+                .instances_of(&self.module.name.name, &decl.name.name),
+        );
+    }
+
+    fn record(&mut self, decl: &RecordDecl) {
+        self.writer
+            .heading(2, Some(&decl.name.name), &format!("record {}", decl.name));
+        let mut signature = Signature::default();
+        signature
+            .type_decl("record", &decl.name, &decl.type_params)
+            .text(&format!(": {};", universe_name(&decl.universe)));
+        self.signature(&signature);
+        self.writer.docs(decl.doc_string.as_ref());
+        self.instances(
+            self.index
+                .instances_of(&self.module.name.name, &decl.name.name),
+        );
+
+        for slot in &decl.slots {
+            self.slot(3, &decl.name, &decl.type_params, slot);
+        }
+    }
+
+    fn union(&mut self, decl: &UnionDecl) {
+        self.writer
+            .heading(2, Some(&decl.name.name), &format!("union {}", decl.name));
+        let mut signature = Signature::default();
+        signature
+            .type_decl("union", &decl.name, &decl.type_params)
+            .text(&format!(": {};", universe_name(&decl.universe)));
+        self.signature(&signature);
+        self.writer.docs(decl.doc_string.as_ref());
+        self.instances(
+            self.index
+                .instances_of(&self.module.name.name, &decl.name.name),
+        );
+
+        for case in &decl.cases {
+            let anchor = format!("{}.{}", decl.name, case.name);
+            self.writer
+                .heading(3, Some(&anchor), &format!("case {}", case.name));
+            let mut signature = Signature::default();
+            signature.type_params.extend(
+                decl.type_params
+                    .iter()
+                    .map(|param| param.name.name.as_str()),
+            );
+            if case.fields.is_empty() {
+                signature.text(&format!("case {};", case.name));
+            } else {
+                signature.text(&format!("case {} is", case.name));
+                for field in &case.fields {
+                    signature
+                        .text(&format!("\n    {}: ", field.name))
+                        .type_spec(&field.r#type)
+                        .text(";");
+                }
+            }
+            self.signature(&signature);
+            self.writer.docs(case.doc_string.as_ref());
+
+            for field in &case.fields {
+                self.slot(4, &Ident::new(&anchor), &decl.type_params, field);
+            }
+        }
+    }
+
+    fn slot(&mut self, level: usize, parent: &Ident, type_params: &[TypeParam], slot: &Slot) {
+        let anchor = format!("{parent}.{}", slot.name);
+        self.writer.heading(level, Some(&anchor), &slot.name.name);
+        let mut signature = Signature::default();
+        signature
+            .type_params
+            .extend(type_params.iter().map(|param| param.name.name.as_str()));
+        signature
+            .text(&format!("{}: ", slot.name))
+            .type_spec(&slot.r#type)
+            .text(";");
+        self.signature(&signature);
+        self.writer.docs(slot.doc_string.as_ref());
+    }
+
+    fn function(&mut self, decl: &FunctionDecl) {
+        self.writer
+            .heading(2, Some(&decl.name.name), &format!("function {}", decl.name));
+        let mut signature = Signature::default();
+        signature
+            .generic(&decl.type_params)
+            .text(&format!("function {}", decl.name))
+            .params(&decl.params)
+            .text(": ")
+            .type_spec(&decl.ret_type)
+            .text(";");
+        self.signature(&signature);
+        self.writer.docs(decl.doc_string.as_ref());
+    }
+
+    fn method(&mut self, parent: &str, type_params: &[&str], decl: &MethodDecl) {
+        let anchor = format!("{parent}.{}", decl.name);
+        self.writer
+            .heading(3, Some(&anchor), &format!("method {}", decl.name));
+        let mut signature = Signature::default();
+        signature.type_params.extend_from_slice(type_params);
+        signature
+            .generic(&decl.type_params)
+            .text(&format!("method {}", decl.name))
+            .params(&decl.params)
+            .text(": ")
+            .type_spec(&decl.ret_ty)
+            .text(";");
+        self.signature(&signature);
+        self.writer.docs(decl.doc_string.as_ref());
+    }
+
+    fn type_class(&mut self, decl: &TypeClassDecl) {
+        self.writer.heading(
+            2,
+            Some(&decl.name.name),
+            &format!("typeclass {}", decl.name),
+        );
+        let mut signature = Signature::default();
+        signature
+            .text(&format!("typeclass {}(", decl.name))
+            .type_params(std::slice::from_ref(&decl.type_param))
+            .text(");");
+        self.signature(&signature);
+        self.writer.docs(decl.doc_string.as_ref());
+        self.instances(
+            self.index
+                .instances_of_class(&self.module.name.name, &decl.name.name),
+        );
+
+        for method in &decl.methods {
+            self.method(&decl.name.name, &[&decl.type_param.name.name], method);
+        }
+    }
+
+    fn instance(&mut self, decl: &InstanceDecl) {
+        let anchor = instance_anchor(decl);
+        let title = format!("instance {}({})", decl.name, decl.arg);
+        self.writer.heading(2, Some(&anchor), &title);
+        let mut signature = Signature::default();
+        signature
+            .generic(&decl.type_params)
+            .text("instance ")
+            .name(&decl.name.name)
+            .text("(")
+            .type_spec(&decl.arg)
+            .text(");");
+        self.signature(&signature);
+        self.writer.docs(decl.doc_string.as_ref());
+
+        let type_params = decl
+            .type_params
+            .iter()
+            .map(|param| param.name.name.as_str())
+            .collect::<Vec<_>>();
+        for method in &decl.methods {
+            self.method(&anchor, &type_params, method);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::lexer::lex;
+    use chumsky::Parser;
+
+    fn parse(source: &str) -> ModuleDecl {
+        let tokens = lex(source)
+            .map(|(token, _)| token.unwrap())
+            .collect::<Vec<_>>();
+        let module = ModuleDecl::parser().parse(&tokens).into_result().unwrap();
+        module
+    }
+
+    fn modules() -> Vec<ModuleDecl> {
+        vec![
+            parse(
+                r#"
+                """
+                Shapes.
+
+                More about shapes.
+                """
+                module Shapes is
+                    """
+                    A point.
+                    """
+                    record Point[T: Free]: Free is
+                        """
+                        Horizontal <coordinate>.
+                        """
+                        x: T;
+                        y: Float64;
+                    end;
+
+                    typeclass Area(T: Free) is
+                        method area(value: T): Float64;
+                    end;
+
+                    instance Area(Point);
+                end module.
+                "#,
+            ),
+            parse(
+                r#"
+                import Shapes (Point as P, Area);
+                module Canvas is
+                    function draw(point: &[P, R]): Unit;
+                end module.
+                "#,
+            ),
+        ]
+    }
+
+    fn page<'a>(pages: &'a [Page], file_name: &str) -> &'a str {
+        &pages
+            .iter()
+            .find(|page| page.file_name == file_name)
+            .unwrap()
+            .contents
+    }
+
+    #[test]
+    fn render_html() {
+        let pages = render(&modules(), Format::Html);
+        assert_eq!(pages.len(), 3);
+
+        let index = page(&pages, "index.html");
+        assert!(index.contains("<li><a href=\"Shapes.html\">Shapes</a>: Shapes.</li>"));
+        assert!(index.contains("<li><a href=\"Canvas.html\">Canvas</a></li>"));
+
+        let shapes = page(&pages, "Shapes.html");
+        assert!(shapes.contains("<p>Shapes.</p>\n<p>More about shapes.</p>"));
+        assert!(shapes.contains("<h2 id=\"Point\">record Point</h2>"));
+        assert!(shapes.contains("<pre><code>record Point[T: Free]: Free;</code></pre>"));
+        assert!(shapes.contains("<h3 id=\"Point.x\">x</h3>\n<pre><code>x: T;</code></pre>"));
+        assert!(shapes.contains("<p>Horizontal &lt;coordinate&gt;.</p>"));
+        assert!(shapes.contains("<li><a href=\"#instance-Area-Point\">Area(Point)</a></li>"));
+        assert!(shapes.contains(
+            "<pre><code>instance <a href=\"#Area\">Area</a>(<a href=\"#Point\">Point</a>);</code></pre>"
+        ));
+        assert!(shapes.contains("<h3 id=\"Area.area\">method area</h3>"));
+
+        let canvas = page(&pages, "Canvas.html");
+        assert!(canvas.contains(
+            "function draw(point: &amp;[<a href=\"Shapes.html#Point\">P</a>, R]): Unit;"
+        ));
+    }
+
+    #[test]
+    fn render_markdown() {
+        let pages = render(&modules(), Format::Markdown);
+
+        let index = page(&pages, "index.md");
+        assert!(index.contains("- [Shapes](Shapes.md): Shapes."));
+
+        let shapes = page(&pages, "Shapes.md");
+        assert!(shapes.contains("<a id=\"Point\"></a>\n\n## record Point\n"));
+        assert!(shapes.contains("Horizontal <coordinate>.\n"));
+        assert!(shapes.contains("- [Area(Point)](#instance-Area-Point)"));
+
+        let canvas = page(&pages, "Canvas.md");
+        assert!(canvas.contains("`function draw(point: &[`[`P`](Shapes.md#Point)`, R]): Unit;`"));
+    }
+}
@@ -0,0 +1,273 @@
+//! `@embed(T, "code", args...)` splices code into a function. The backend emits MLIR rather than
+//! C, so the code is an Austral expression in which `$1` to `$n` stand for the arguments. This
+//! covers the arithmetic and comparison idioms embeds are used for, and gets the same lowering as
+//! any other expression.
+
+use crate::{
+    ast::{
+        ArithExpr, AtomicExpr, CmpExpr, CompoundExpr, Expression, FnCallArgs, IntrinExpr,
+        LogicExpr, ModuleDef, ModuleDefItem, PathSegment, Statement,
+    },
+    error::{EmbedError as Error, EmbedResult as Result},
+    escape, lexer,
+};
+use chumsky::Parser;
+
+/// Check that the code of every `@embed` is an expression over its arguments.
+pub fn check_module_def(module: &ModuleDef) -> Result<()> {
+    for item in &module.contents {
+        match item {
+            ModuleDefItem::Function(def) => check_statements(&def.body)?,
+            ModuleDefItem::Instance(def) => {
+                for method in &def.methods {
+                    check_statements(&method.body)?;
+                }
+            }
+            ModuleDefItem::Constant(def) => check_expression(&def.value)?,
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
+
+/// Parse the code of an `@embed` with `arity` arguments. Placeholders are replaced by the names
+/// [`placeholder`] returns.
+pub fn parse_embed(code: &str, arity: usize) -> Result<Expression> {
+    let mut source = String::with_capacity(code.len());
+    let mut chars = code.chars().peekable();
+    let mut in_string = false;
+    while let Some(c) = chars.next() {
+        // The code was unescaped as a literal already, so the control characters within its own
+        // literals have to be escaped again for it to lex.
+        if in_string && c == '\\' {
+            source.push(c);
+            source.extend(chars.next());
+            continue;
+        } else if in_string && c.is_control() {
+            escape::write_escaped(&mut source, c).unwrap();
+            continue;
+        } else if c != '$' {
+            in_string ^= c == '"';
+            source.push(c);
+            continue;
+        }
+
+        let mut digits = String::new();
+        while let Some(digit) = chars.next_if(char::is_ascii_digit) {
+            digits.push(digit);
+        }
+        match digits.parse::<usize>() {
+            Ok(index) if (1..=arity).contains(&index) => source.push_str(&placeholder(index)),
+            _ => {
+                return Err(Error::InvalidPlaceholder {
+                    code: code.to_string(),
+                    placeholder: format!("${digits}"),
+                })
+            }
+        }
+    }
+
+    let invalid = || Error::InvalidExpression(code.to_string());
+    let tokens = lexer::lex(&source)
+        .map(|(token, _)| token)
+        .collect::<std::result::Result<Vec<_>, _>>()
+        .map_err(|_| invalid())?;
+    let expr = Expression::parser().parse(&tokens).into_result();
+    expr.map_err(|_| invalid())
+}
+
+/// The variable a placeholder is bound to, which can't clash with the names of the function since
+/// those aren't in scope within the embedded code.
+pub fn placeholder(index: usize) -> String {
+    format!("embed_arg_{index}")
+}
+
+fn check_statements(stmts: &[Statement]) -> Result<()> {
+    stmts.iter().try_for_each(check_statement)
+}
+
+fn check_statement(stmt: &Statement) -> Result<()> {
+    match stmt {
+        Statement::Assign(stmt) => {
+            check_path(&stmt.target.extra)?;
+            check_expression(&stmt.value)
+        }
+        Statement::Borrow(stmt) => check_statements(&stmt.body),
+        Statement::Case(stmt) => {
+            check_expression(&stmt.value)?;
+            stmt.variants
+                .iter()
+                .try_for_each(|variant| check_statements(&variant.block))
+        }
+        Statement::Discard(expr) | Statement::Return(expr) => check_expression(expr),
+        Statement::For(stmt) => {
+            check_expression(&stmt.range.start)?;
+            check_expression(&stmt.range.end)?;
+            check_statements(&stmt.contents)
+        }
+        Statement::If(stmt) => {
+            check_expression(&stmt.value)?;
+            check_statements(&stmt.contents)?;
+            stmt.r#else.as_deref().map_or(Ok(()), check_statements)
+        }
+        Statement::Let(stmt) => check_expression(&stmt.value),
+        Statement::While(stmt) => {
+            check_expression(&stmt.value)?;
+            check_statements(&stmt.contents)
+        }
+    }
+}
+
+fn check_expression(expr: &Expression) -> Result<()> {
+    match expr {
+        Expression::Atomic(expr) => check_atomic(expr),
+        Expression::Compound(expr) => match expr {
+            CompoundExpr::Cmp(
+                CmpExpr::Eq(lhs, rhs)
+                | CmpExpr::NotEq(lhs, rhs)
+                | CmpExpr::Lt(lhs, rhs)
+                | CmpExpr::LtEq(lhs, rhs)
+                | CmpExpr::Gt(lhs, rhs)
+                | CmpExpr::GtEq(lhs, rhs),
+            )
+            | CompoundExpr::Logic(LogicExpr::And(lhs, rhs) | LogicExpr::Or(lhs, rhs))
+            | CompoundExpr::Arith(
+                ArithExpr::Add(lhs, rhs)
+                | ArithExpr::Sub(lhs, rhs)
+                | ArithExpr::Mul(lhs, rhs)
+                | ArithExpr::Div(lhs, rhs),
+            ) => {
+                check_atomic(lhs)?;
+                check_atomic(rhs)
+            }
+            CompoundExpr::Logic(LogicExpr::Not(expr))
+            | CompoundExpr::Arith(ArithExpr::Neg(expr)) => check_atomic(expr),
+            CompoundExpr::Select(expr) => {
+                check_expression(&expr.condition)?;
+                check_expression(&expr.value_true)?;
+                check_expression(&expr.value_false)
+            }
+            CompoundExpr::Cast(expr) => check_atomic(&expr.value),
+        },
+    }
+}
+
+fn check_atomic(expr: &AtomicExpr) -> Result<()> {
+    match expr {
+        AtomicExpr::FnCall(expr) => match &expr.args {
+            FnCallArgs::Empty => Ok(()),
+            FnCallArgs::Positional(args) => args.iter().try_for_each(check_expression),
+            FnCallArgs::Named(args) => args.values().try_for_each(check_expression),
+        },
+        AtomicExpr::Path(expr) | AtomicExpr::RefPath(expr) => check_path(&expr.extra),
+        AtomicExpr::Paren(expr) => check_expression(expr),
+        AtomicExpr::Intrinsic(IntrinExpr::Embed { exp, args, .. }) => {
+            args.iter().try_for_each(check_expression)?;
+            parse_embed(exp, args.len()).map(|_| ())
+        }
+        AtomicExpr::Deref(expr) => check_atomic(expr),
+        AtomicExpr::ConstNil
+        | AtomicExpr::ConstBool(_)
+        | AtomicExpr::ConstChar(_)
+        | AtomicExpr::ConstInt(_)
+        | AtomicExpr::ConstFloat(_)
+        | AtomicExpr::ConstStr(_)
+        | AtomicExpr::SizeOf(_)
+        | AtomicExpr::BorrowRead(_)
+        | AtomicExpr::BorrowWrite(_)
+        | AtomicExpr::ReBorrow(_) => Ok(()),
+    }
+}
+
+fn check_path(segments: &[PathSegment]) -> Result<()> {
+    segments.iter().try_for_each(|segment| match segment {
+        PathSegment::SlotAccess(_) | PathSegment::PtrSlotAccess(_) => Ok(()),
+        PathSegment::ArrayIndex(expr) => check_expression(expr),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        ast::{FnCallExpr, Ident, PathExpr},
+        lexer::lex,
+    };
+
+    fn check(source: &str) -> Result<()> {
+        let tokens = lex(source)
+            .map(|(token, _)| token.unwrap())
+            .collect::<Vec<_>>();
+        let module = ModuleDef::parser().parse(&tokens).into_result().unwrap();
+
+        check_module_def(&module)
+    }
+
+    fn path(name: &str) -> AtomicExpr {
+        AtomicExpr::Path(PathExpr {
+            first: Ident::new(name),
+            extra: Vec::new(),
+        })
+    }
+
+    #[test]
+    fn placeholders() {
+        assert_eq!(
+            parse_embed("$1 + $2", 2),
+            Ok(Expression::Compound(CompoundExpr::Arith(ArithExpr::Add(
+                path("embed_arg_1"),
+                path("embed_arg_2"),
+            ))))
+        );
+        assert_eq!(
+            parse_embed("f($1)", 1),
+            Ok(Expression::Atomic(AtomicExpr::FnCall(FnCallExpr {
+                target: Ident::new("f"),
+                args: FnCallArgs::Positional(vec![Expression::Atomic(path("embed_arg_1"))]),
+            })))
+        );
+    }
+
+    #[test]
+    fn invalid_placeholders() {
+        let invalid = |placeholder: &str| {
+            Err(Error::InvalidPlaceholder {
+                code: format!("{placeholder} + 1"),
+                placeholder: placeholder.to_string(),
+            })
+        };
+        assert_eq!(parse_embed("$3 + 1", 2), invalid("$3"));
+        assert_eq!(parse_embed("$0 + 1", 2), invalid("$0"));
+        assert_eq!(parse_embed("$ + 1", 2), invalid("$"));
+    }
+
+    #[test]
+    fn unescaped_literals() {
+        assert!(parse_embed("f(\"%i\n\", $1)", 1).is_ok());
+        assert!(parse_embed("f(\"\\\"\t\", $1)", 1).is_ok());
+    }
+
+    #[test]
+    fn invalid_expression() {
+        assert_eq!(
+            parse_embed("$1 +", 1),
+            Err(Error::InvalidExpression("$1 +".to_string()))
+        );
+    }
+
+    #[test]
+    fn embeds_in_module() {
+        let source = include_str!("../../../programs/suites/001-trivial/002-embed/Test.aum");
+        assert_eq!(check(source), Ok(()));
+
+        let source = source.replacen("$2", "$3", 1);
+        assert_eq!(
+            check(&source),
+            Err(Error::InvalidPlaceholder {
+                code: "$1 + $3".to_string(),
+                placeholder: "$3".to_string(),
+            })
+        );
+    }
+}
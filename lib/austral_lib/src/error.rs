@@ -1,3 +1,21 @@
-pub use self::lexer::{Error as LexerError, Result as LexerResult};
+pub use self::{
+    capability::{Error as CapabilityError, Result as CapabilityResult},
+    codegen::{Error as CodegenError, Result as CodegenResult},
+    constant::{Error as ConstantError, Result as ConstantResult},
+    embed::{Error as EmbedError, Result as EmbedResult},
+    lexer::{Error as LexerError, Result as LexerResult},
+    linearity::{Error as LinearityError, Result as LinearityResult},
+    literal::{Error as LiteralError, Result as LiteralResult},
+    pragma::{Error as PragmaError, Result as PragmaResult},
+    universe::{Error as UniverseError, Result as UniverseResult},
+};
 
+mod capability;
+mod codegen;
+mod constant;
+mod embed;
 mod lexer;
+mod linearity;
+mod literal;
+mod pragma;
+mod universe;
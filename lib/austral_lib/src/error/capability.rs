@@ -0,0 +1,17 @@
+use thiserror::Error;
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Debug, Error, PartialEq)]
+pub enum Error {
+    #[error("Function `{0}` is a foreign import, which is only allowed in modules marked with `pragma Unsafe_Module`")]
+    ForeignImport(String),
+    #[error("Function `{0}` uses `@embed`, which is only allowed in modules marked with `pragma Unsafe_Module`")]
+    Embed(String),
+    #[error("Function `{0}` accesses memory through a pointer, which is only allowed in modules marked with `pragma Unsafe_Module`")]
+    PointerAccess(String),
+    #[error("Module `{0}` can only be imported from modules marked with `pragma Unsafe_Module`")]
+    UnsafeImport(String),
+    #[error("The `main` function must have the signature `main(): ExitCode` or `main(root: RootCapability): ExitCode`")]
+    InvalidMainSignature,
+}
@@ -0,0 +1,60 @@
+use super::{ConstantError, EmbedError};
+use thiserror::Error;
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Debug, Error, PartialEq)]
+pub enum Error {
+    #[error("Can't compile for the target: {0}")]
+    Target(String),
+    #[error("Can't link a binary for `{0}` with the host's linker, emit an object file instead")]
+    CrossLinking(String),
+    #[error(
+        "Type `{0}` has no definition, opaque types can only be declared in module interfaces"
+    )]
+    OpaqueType(String),
+    #[error("Unknown type `{0}`")]
+    UnknownType(String),
+    #[error("Unknown variable `{0}`")]
+    UnknownVariable(String),
+    #[error("Unknown function `{0}`")]
+    UnknownFunction(String),
+    #[error("Unknown slot `{0}`")]
+    UnknownSlot(String),
+    #[error("Unknown field `{0}`")]
+    UnknownField(String),
+    #[error("Cannot assign to `{0}`, which is not declared with `var`")]
+    ImmutableAssignment(String),
+    #[error("Only records can be destructured")]
+    NotARecord,
+    #[error("The value of a `case` statement must be a union")]
+    NotAUnion,
+    #[error("Only pointers can be dereferenced")]
+    NotAPointer,
+    #[error("Only spans can be indexed")]
+    NotASpan,
+    #[error("Missing argument `{argument}` in the call to `{function}`")]
+    MissingArgument { function: String, argument: String },
+    #[error("`{0}` must be called with named arguments")]
+    NamedArguments(String),
+    #[error("`{0}` must be called with positional arguments")]
+    PositionalArguments(String),
+    #[error("Wrong number of arguments in the call to `{0}`")]
+    ArgumentCount(String),
+    #[error(
+        "Literal `{0}` has no expected type and doesn't fit in `Int32`, give it one with `: T`"
+    )]
+    UntypedLiteral(String),
+    #[error("Only literals can be given a type with `: {0}`, other values must already have it")]
+    TypeAscription(String),
+    #[error("The bounds of the `for` loop over `{name}` must be `Index`, not `{r#type}`")]
+    ForBound { name: String, r#type: String },
+    #[error("Cannot infer the type arguments of `{0}`")]
+    UnknownTypeArguments(String),
+    #[error("No instance for the call to `{0}`")]
+    NoInstance(String),
+    #[error(transparent)]
+    Constant(#[from] ConstantError),
+    #[error(transparent)]
+    Embed(#[from] EmbedError),
+}
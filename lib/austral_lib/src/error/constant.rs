@@ -0,0 +1,27 @@
+use thiserror::Error;
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Debug, Error, PartialEq)]
+pub enum Error {
+    #[error("Unknown constant `{0}`")]
+    UnknownConstant(String),
+    #[error("Constant `{0}` is defined in terms of itself")]
+    CyclicConstant(String),
+    #[error("The value of constant `{0}` cannot be computed at compile time")]
+    NotConstant(String),
+    #[error("Division by zero in constant `{0}`")]
+    DivisionByZero(String),
+    #[error("Value `{value}` of constant `{name}` does not fit in type `{r#type}`")]
+    OutOfRange {
+        name: String,
+        value: String,
+        r#type: String,
+    },
+    #[error("Constant `{name}` has a value which is not of type `{r#type}`")]
+    TypeMismatch { name: String, r#type: String },
+    #[error("Invalid operands in constant `{0}`")]
+    InvalidOperands(String),
+    #[error("Constant `{name}` cannot be cast to type `{r#type}`")]
+    InvalidCast { name: String, r#type: String },
+}
@@ -0,0 +1,11 @@
+use thiserror::Error;
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Debug, Error, PartialEq)]
+pub enum Error {
+    #[error("`@embed` code `{0}` is not a valid expression")]
+    InvalidExpression(String),
+    #[error("`@embed` code `{code}` uses placeholder `{placeholder}`, which doesn't name one of its arguments")]
+    InvalidPlaceholder { code: String, placeholder: String },
+}
@@ -7,4 +7,6 @@ pub type Result<'a, T> = std::result::Result<T, Error<'a>>;
 pub enum Error<'a> {
     #[error("Unexpected input: \"{0}\" at offset {}..{}", .1.start, .1.end)]
     UnexpectedInput(Cow<'a, str>, Range<usize>),
+    #[error("Invalid escape sequence: \"{0}\" at offset {}..{}", .1.start, .1.end)]
+    InvalidEscape(Cow<'a, str>, Range<usize>),
 }
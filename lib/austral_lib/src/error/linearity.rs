@@ -0,0 +1,11 @@
+use thiserror::Error;
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Debug, Error, PartialEq)]
+pub enum Error {
+    #[error("Function `{0}` must consume each of its linear values (such as the `RootCapability`) exactly once")]
+    InvalidUse(String),
+    #[error("The linearity checker doesn't support `{0}` statements yet")]
+    UnsupportedStatement(String),
+}
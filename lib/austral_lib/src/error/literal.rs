@@ -0,0 +1,11 @@
+use thiserror::Error;
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Debug, Error, PartialEq)]
+pub enum Error {
+    #[error("Literal `{literal}` is out of range for type `{r#type}`")]
+    OutOfRange { literal: String, r#type: String },
+    #[error("Literal `{literal}` cannot be used as a value of type `{r#type}`")]
+    Mismatch { literal: String, r#type: String },
+}
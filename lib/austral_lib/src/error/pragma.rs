@@ -0,0 +1,28 @@
+use crate::pragma_check::Placement;
+use thiserror::Error;
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Debug, Error, PartialEq)]
+pub enum Error {
+    #[error("Unknown pragma `{0}`")]
+    UnknownPragma(String),
+    #[error("Pragma `{name}` cannot be used on a {placement}")]
+    MisplacedPragma { name: String, placement: Placement },
+    #[error("Pragma `{0}` is given more than once")]
+    DuplicatePragma(String),
+    #[error("Pragma `{pragma}` requires the argument `{argument}`")]
+    MissingArgument { pragma: String, argument: String },
+    #[error("Pragma `{pragma}` does not take the argument `{argument}`")]
+    UnexpectedArgument { pragma: String, argument: String },
+    #[error("Pragma `{0}` only takes named arguments")]
+    PositionalArguments(String),
+    #[error("Argument `{argument}` of pragma `{pragma}` must be a string literal")]
+    InvalidArgument { pragma: String, argument: String },
+    #[error("Function `{0}` cannot be both a foreign import and a foreign export")]
+    ConflictingPragmas(String),
+    #[error("Foreign import `{0}` cannot have a body")]
+    ForeignImportBody(String),
+    #[error("Foreign function `{0}` cannot have type parameters")]
+    GenericForeignFunction(String),
+}
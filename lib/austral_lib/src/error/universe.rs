@@ -0,0 +1,43 @@
+use crate::r#type::Universe;
+use thiserror::Error;
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Debug, Error, PartialEq)]
+pub enum Error {
+    #[error("Unknown type `{0}`")]
+    UnknownType(String),
+    #[error("Type `{name}` expects {expected} type argument(s), but {actual} were given")]
+    WrongArity {
+        name: String,
+        expected: usize,
+        actual: usize,
+    },
+    #[error("Type `{0}` cannot be declared in the Region universe")]
+    RegionDeclaration(String),
+    #[error("Slot `{slot}` of free type `{decl}` has a type in the {universe} universe")]
+    NonFreeSlot {
+        decl: String,
+        slot: String,
+        universe: Universe,
+    },
+    #[error("Type parameter `{param}` of `{name}` expects a type in the {expected} universe, but `{argument}` is in the {actual} universe")]
+    UniverseMismatch {
+        name: String,
+        param: String,
+        argument: String,
+        expected: Universe,
+        actual: Universe,
+    },
+    #[error("Region `{0}` can only appear inside a reference or span type")]
+    MisplacedRegion(String),
+    #[error("Expected a region, but found `{0}`")]
+    ExpectedRegion(String),
+    #[error("Type `{argument}` does not implement the typeclass `{typeclass}` required by the type parameter `{param}` of `{name}`")]
+    MissingInstance {
+        name: String,
+        param: String,
+        argument: String,
+        typeclass: String,
+    },
+}
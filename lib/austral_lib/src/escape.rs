@@ -1 +1,145 @@
+use std::{
+    borrow::Cow,
+    fmt::{self, Write},
+    iter::Peekable,
+    ops::Range,
+    str::CharIndices,
+};
+
+/// The contents of a string literal, with its escape sequences resolved. It's displayed in its
+/// escaped form, quotes included, so that it reads like the literal it came from.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
 pub struct EscapedString(String);
+
+impl EscapedString {
+    pub fn new(value: impl Into<String>) -> Self {
+        Self(value.into())
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    pub fn into_string(self) -> String {
+        self.0
+    }
+}
+
+impl fmt::Display for EscapedString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_char('"')?;
+        self.0.chars().try_for_each(|c| write_escaped(f, c))?;
+        f.write_char('"')
+    }
+}
+
+/// Write `c` the way it has to be written within a string literal.
+pub fn write_escaped(out: &mut impl Write, c: char) -> fmt::Result {
+    match c {
+        '\n' => out.write_str(r"\n"),
+        '\r' => out.write_str(r"\r"),
+        '\t' => out.write_str(r"\t"),
+        '\0' => out.write_str(r"\0"),
+        '\\' => out.write_str(r"\\"),
+        '"' => out.write_str(r#"\""#),
+        c if c.is_control() => write!(out, r"\u{{{:x}}}", c as u32),
+        c => out.write_char(c),
+    }
+}
+
+/// Resolve the escape sequences of `value`, which starts at `offset` within the source. On failure,
+/// the range of the first invalid escape sequence within the source is returned.
+///
+/// The supported sequences are `\n`, `\r`, `\t`, `\0`, `\\`, `\"`, `\'`, `\xNN` for ASCII
+/// characters and `\u{N}` with up to six hexadecimal digits for any other character.
+pub fn unescape(value: &str, offset: usize) -> Result<Cow<'_, str>, Range<usize>> {
+    if !value.contains('\\') {
+        return Ok(Cow::Borrowed(value));
+    }
+
+    let mut target = String::with_capacity(value.len());
+    let mut chars = value.char_indices().peekable();
+    while let Some((start, c)) = chars.next() {
+        if c != '\\' {
+            target.push(c);
+            continue;
+        }
+
+        let unescaped = match chars.next().map(|(_, c)| c) {
+            Some('n') => Some('\n'),
+            Some('r') => Some('\r'),
+            Some('t') => Some('\t'),
+            Some('0') => Some('\0'),
+            Some(c @ ('\\' | '"' | '\'')) => Some(c),
+            Some('x') => hex_digits(&mut chars, 2)
+                .filter(|digits| digits.len() == 2)
+                .and_then(|digits| u8::from_str_radix(&digits, 16).ok())
+                .filter(u8::is_ascii)
+                .map(char::from),
+            Some('u') if chars.next_if(|&(_, c)| c == '{').is_some() => hex_digits(&mut chars, 6)
+                .filter(|_| chars.next_if(|&(_, c)| c == '}').is_some())
+                .and_then(|digits| u32::from_str_radix(&digits, 16).ok())
+                .and_then(char::from_u32),
+            _ => None,
+        };
+
+        match unescaped {
+            Some(c) => target.push(c),
+            None => {
+                let end = chars.peek().map_or(value.len(), |&(end, _)| end);
+                return Err(offset + start..offset + end);
+            }
+        }
+    }
+
+    Ok(Cow::Owned(target))
+}
+
+/// Read a run of hexadecimal digits, which must be between 1 and `max` digits long.
+fn hex_digits(chars: &mut Peekable<CharIndices>, max: usize) -> Option<String> {
+    let mut digits = String::new();
+    while let Some((_, digit)) = chars.next_if(|(_, c)| c.is_ascii_hexdigit()) {
+        digits.push(digit);
+    }
+    (1..=max).contains(&digits.len()).then_some(digits)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn escapes() {
+        assert_eq!(unescape("plain", 0), Ok(Cow::Borrowed("plain")));
+        assert_eq!(
+            unescape(r#"a\n\r\t\0\\\"\'b"#, 0).as_deref(),
+            Ok("a\n\r\t\0\\\"'b")
+        );
+        assert_eq!(unescape(r"\x41\x7f", 0).as_deref(), Ok("A\x7f"));
+        assert_eq!(unescape(r"\u{48}\u{e9}\u{1F600}", 0).as_deref(), Ok("Hé😀"));
+    }
+
+    #[test]
+    fn invalid_escapes() {
+        assert_eq!(unescape(r"ab\q", 10), Err(12..14));
+        assert_eq!(unescape(r"\x4", 0), Err(0..3));
+        assert_eq!(unescape(r"\x80", 0), Err(0..4));
+        assert_eq!(unescape(r"\u{110000}", 0), Err(0..10));
+        assert_eq!(unescape(r"\u{}", 0), Err(0..3));
+        assert_eq!(unescape(r"\u41", 0), Err(0..2));
+        assert_eq!(unescape(r"a\", 0), Err(1..2));
+    }
+
+    #[test]
+    fn display() {
+        let string = EscapedString::new("say \"hi\"\n\t\\\u{1b}");
+        assert_eq!(string.to_string(), r#""say \"hi\"\n\t\\\u{1b}""#);
+        let literal = string.to_string();
+        let contents = literal
+            .strip_prefix('"')
+            .unwrap()
+            .strip_suffix('"')
+            .unwrap();
+        assert_eq!(unescape(contents, 0).as_deref(), Ok(string.as_str()));
+    }
+}
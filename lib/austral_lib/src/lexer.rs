@@ -1,4 +1,7 @@
-use crate::error::{LexerError, LexerResult as Result};
+use crate::{
+    error::{LexerError, LexerResult as Result},
+    escape,
+};
 use logos::{Lexer, Logos};
 use serde::{Deserialize, Serialize};
 use std::{borrow::Cow, ops::Range};
@@ -6,19 +9,30 @@ use std::{borrow::Cow, ops::Range};
 pub fn lex(input: &str) -> impl '_ + Iterator<Item = (Result<Token>, Range<usize>)> {
     Token::lexer(input).spanned().map(|(token, span)| {
         (
-            token.map_err(|_| {
-                LexerError::UnexpectedInput(
-                    Cow::Borrowed(std::str::from_utf8(&input.as_bytes()[span.clone()]).unwrap()),
-                    span.clone(),
-                )
+            token.map_err(|error| match error {
+                TokenError::UnexpectedInput => {
+                    LexerError::UnexpectedInput(Cow::Borrowed(&input[span.clone()]), span.clone())
+                }
+                TokenError::InvalidEscape(range) => {
+                    LexerError::InvalidEscape(Cow::Borrowed(&input[range.clone()]), range)
+                }
             }),
             span,
         )
     })
 }
 
+/// Why a token couldn't be read.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum TokenError {
+    #[default]
+    UnexpectedInput,
+    /// An invalid escape sequence, with its range within the input.
+    InvalidEscape(Range<usize>),
+}
+
 #[derive(Clone, Debug, Deserialize, Logos, PartialEq, Serialize)]
-#[logos(skip r"\s+")]
+#[logos(error = TokenError, skip r"\s+")]
 pub enum Token<'a> {
     #[token(r"(")]
     LParen,
@@ -176,14 +190,16 @@ pub enum Token<'a> {
     #[token(r"false")]
     False,
 
-    #[regex(r"'([^']|\\')'", |lex| lex.slice().chars().nth(1).unwrap())]
+    #[regex(r"'([^'\\]|\\.[^']*)'", read_char)]
     Char(char),
-    #[regex(r"[0-9]+", |lex| lex.slice().parse::<u64>().unwrap())]
+    // Literals which overflow a `u64` are rejected here, narrower types are checked later.
+    #[regex(r"[0-9]+", |lex| lex.slice().parse::<u64>().ok())]
     Decimal(u64),
     // TODO: Hexadecimal constant.
     // TODO: Binary constant.
     // TODO: Octal constant.
-    #[regex(r"[+-]?[0-9]+\.[0-9]*(?:[eE][+-]?[0-9]+)?", |lex| lex.slice().parse::<f64>().unwrap())]
+    // Negative literals are parsed as a negation, like integers.
+    #[regex(r"[0-9]+\.[0-9]*(?:[eE][+-]?[0-9]+)?", |lex| lex.slice().parse::<f64>().unwrap())]
     Float(f64),
 
     #[regex(r"[A-Za-z_][A-Za-z0-9_]*", |lex| lex.slice())]
@@ -202,37 +218,43 @@ pub enum Universe {
     Type,
 }
 
-fn read_string<'a>(lex: &mut Lexer<'a, Token<'a>>) -> Option<Cow<'a, str>> {
+fn read_string<'a>(
+    lex: &mut Lexer<'a, Token<'a>>,
+) -> std::result::Result<Cow<'a, str>, TokenError> {
     let mut iter = lex.remainder().chars().peekable();
     let mut count = 0;
 
     // Parse contents.
     let raw_contents;
     loop {
-        match iter.next()? {
+        match iter.next().ok_or(TokenError::UnexpectedInput)? {
             '"' => {
                 raw_contents = std::str::from_utf8(&lex.remainder().as_bytes()[..count]).unwrap();
                 lex.bump(count + 1);
                 break;
             }
-            '\\' if iter.next_if_eq(&'"').is_some() => count += 2,
-            '\n' => return None,
+            '\\' => count += 1 + iter.next().map_or(0, char::len_utf8),
+            '\n' => return Err(TokenError::UnexpectedInput),
             ch => count += ch.len_utf8(),
         }
     }
 
     // Unescape contents.
-    Some(unescape_string(raw_contents))
+    unescape(lex, raw_contents, 1)
 }
 
-fn read_triple_string<'a>(lex: &mut Lexer<'a, Token<'a>>) -> Option<Cow<'a, str>> {
+/// Docstrings are prose, which quotes code and regular expressions, so their contents are kept
+/// as written. Only `\"` is skipped, so that a docstring can contain quotes.
+fn read_triple_string<'a>(
+    lex: &mut Lexer<'a, Token<'a>>,
+) -> std::result::Result<Cow<'a, str>, TokenError> {
     let mut iter = lex.remainder().chars().peekable();
     let mut count = 0;
 
     // Parse contents.
     let raw_contents;
     loop {
-        match iter.next()? {
+        match iter.next().ok_or(TokenError::UnexpectedInput)? {
             '"' if iter
                 .next_if_eq(&'"')
                 .is_some_and(|_| iter.next_if_eq(&'"').is_some()) =>
@@ -246,48 +268,44 @@ fn read_triple_string<'a>(lex: &mut Lexer<'a, Token<'a>>) -> Option<Cow<'a, str>
         }
     }
 
-    // Unescape contents.
-    Some(unescape_string(raw_contents))
+    Ok(Cow::Borrowed(raw_contents))
 }
 
-fn unescape_string(value: &str) -> Cow<str> {
-    let mut target: Option<String> = None;
-    let mut offset = 0;
+fn read_char<'a>(lex: &mut Lexer<'a, Token<'a>>) -> std::result::Result<char, TokenError> {
+    let slice = lex.slice();
+    let contents = unescape(lex, &slice[1..slice.len() - 1], 1)?;
 
-    let make_target = |offset| {
-        move || {
-            std::str::from_utf8(&value.as_bytes()[..offset])
-                .unwrap()
-                .to_string()
-        }
-    };
+    let mut chars = contents.chars();
+    match (chars.next(), chars.next()) {
+        (Some(ch), None) => Ok(ch),
+        _ => Err(TokenError::UnexpectedInput),
+    }
+}
 
-    let mut is_escaped = false;
-    for ch in value.chars() {
-        if is_escaped {
-            match ch {
-                '\\' => target.get_or_insert_with(make_target(offset)).push(ch),
-                '"' => target.get_or_insert_with(make_target(offset)).push('"'),
-                _ => {
-                    offset += '\\'.len_utf8();
-                    if let Some(target) = &mut target {
-                        target.extend(['\\', ch]);
-                    }
-                }
-            }
+/// Unescape the contents of a literal, which start `quotes` bytes into the token.
+fn unescape<'a>(
+    lex: &Lexer<'a, Token<'a>>,
+    contents: &'a str,
+    quotes: usize,
+) -> std::result::Result<Cow<'a, str>, TokenError> {
+    escape::unescape(contents, lex.span().start + quotes).map_err(TokenError::InvalidEscape)
+}
 
-            is_escaped = false;
-            offset += ch.len_utf8();
-        } else if ch == '\\' {
-            is_escaped = true;
-        } else {
-            if let Some(target) = &mut target {
-                target.push(ch);
-            }
+#[cfg(test)]
+mod test {
+    use super::*;
 
-            offset += ch.len_utf8();
-        }
+    #[test]
+    fn docstrings_are_raw() {
+        let tokens = lex(r#""""Matches `\d+` and `\"`.""" "a\tb""#)
+            .map(|(token, _)| token.unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            tokens,
+            [
+                Token::TripleString(Cow::Borrowed(r#"Matches `\d+` and `\"`."#)),
+                Token::String(Cow::Borrowed("a\tb")),
+            ]
+        );
     }
-
-    target.map(Cow::Owned).unwrap_or(Cow::Borrowed(value))
 }
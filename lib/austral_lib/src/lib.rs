@@ -1,16 +1,23 @@
 pub mod ast;
 pub mod backend;
+pub mod capability_check;
 pub mod common;
 pub mod compiler;
+pub mod constant_eval;
 pub mod db;
+pub mod doc;
+pub mod embed_check;
 pub mod error;
 pub mod escape;
 pub mod lexer;
 pub mod linearity_check;
+pub mod literal_check;
+pub mod pragma_check;
 pub mod span;
 pub mod stages;
 pub mod r#type;
 pub mod type_system;
+pub mod universe_check;
 
 /// The Jar combines all the features provided by the salsa database.
 /// Every tracked function, interned value, query and input must be listed here.
@@ -1,10 +1,21 @@
 use crate::{
-    common::Identifier,
-    r#type::{Ty, Universe},
-    stages::{TExpr, TStmt},
-    type_system::type_universe,
+    ast::{
+        ArithExpr, AtomicExpr, BorrowMode, BorrowMutMode, CmpExpr, CompoundExpr, Expression,
+        FnCallArgs, Ident, IntrinExpr, LetStmtTarget, LogicExpr, ModuleDef, ModuleDefItem, Param,
+        PathExpr, PathSegment, Statement, TypeParam, TypeSpec,
+    },
+    common::{
+        ArithmeticOperator, BorrowingMode, ComparisonOperator, DeclId, Identifier, ModuleName,
+        Mutability, QIdent,
+    },
+    error::{LinearityError as Error, LinearityResult as Result},
+    escape::EscapedString,
+    r#type::{Ty, Universe, ValueParameter},
+    span::Span,
+    stages::{BorrowStmtKind, CaseRef, TExpr, TStmt, TypedBinding, TypedWhen},
+    type_system::{primitive_type, type_universe},
 };
-use std::collections::HashMap;
+use std::{collections::HashMap, ops::Add};
 
 #[derive(Debug, Default)]
 pub struct Appearances {
@@ -14,6 +25,19 @@ pub struct Appearances {
     pub path: i32,
 }
 
+impl Add for Appearances {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        Self {
+            consumed: self.consumed + rhs.consumed,
+            read: self.read + rhs.read,
+            write: self.write + rhs.write,
+            path: self.path + rhs.path,
+        }
+    }
+}
+
 #[derive(Default, Clone, PartialEq)]
 pub enum VarState {
     #[default]
@@ -56,72 +80,158 @@ impl Partitions {
 
 pub type StateTable = HashMap<Identifier, (i32, VarState)>;
 
-pub fn check_statement(state_table: &mut StateTable, stmt: &TStmt, depth: i32) -> bool {
+/// Check that every function and method of a module consumes each of its linear values exactly
+/// once.
+pub fn check_module_def(module: &ModuleDef) -> Result<()> {
+    let mut lowering = Lowering::new(module);
+    for item in &module.contents {
+        match item {
+            ModuleDefItem::Function(def) => {
+                lowering.check(&def.name, &def.type_params, &def.params, &def.body)?;
+            }
+            ModuleDefItem::Instance(def) => {
+                for method in &def.methods {
+                    let type_params = def.type_params.iter().chain(&method.type_params);
+                    lowering.check(&method.name, type_params, &method.params, &method.body)?;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
+
+/// Check a function body, where every linear parameter (e.g. the `RootCapability` passed to
+/// `main`) must be consumed exactly once.
+pub fn check_function(params: &[ValueParameter], body: &TStmt) -> Result<bool> {
+    let mut state_table = StateTable::new();
+    for ValueParameter(name, ty) in params {
+        if type_universe(ty) == Universe::LinearUniverse {
+            state_table.insert(name.clone(), (0, VarState::Unconsumed));
+        }
+    }
+    check_statement(&mut state_table, body, 0)
+}
+
+pub fn check_statement(state_table: &mut StateTable, stmt: &TStmt, depth: i32) -> Result<bool> {
     match stmt {
-        TStmt::TSkip(_) => true,
+        TStmt::TSkip(_) => Ok(true),
         TStmt::TLet(_, name, expr, _, ty, body) => {
             // This is an internal error because the compiler is expected to catch redefinitions before this.
             debug_assert!(!state_table.contains_key(name));
-            check_expression(state_table, depth, expr);
-            if type_universe(ty) == Universe::LinearUniverse {
+            let expr_result = check_expression(state_table, depth, expr);
+            let body_result = if type_universe(ty) == Universe::LinearUniverse {
                 state_table.insert(name.clone(), (depth, VarState::Unconsumed));
                 let result = check_statement(state_table, body, depth);
                 // the body extends until the end of the block (scope)
                 state_table.remove(name);
-                result
+                result?
             } else {
-                check_statement(state_table, body, depth)
-            }
-        }
-        TStmt::TAssign(_, lvalue, rvalue) => {
-            check_expression(state_table, depth, lvalue)
-                && check_expression(state_table, depth, rvalue)
-        }
-        TStmt::TAssignTmp(..) => {
-            panic!("TODO: Implement check_statement for TAssignTmp")
+                check_statement(state_table, body, depth)?
+            };
+            Ok(expr_result && body_result)
         }
-        TStmt::TBlock(..) => {
-            panic!("TODO: Implement check_statement for TBlock")
-        }
-        TStmt::TDiscarding(..) => {
-            panic!("TODO: Implement check_statement for TDiscarding")
-        }
-        TStmt::TReturn(_, expr) => {
-            check_expression(state_table, depth, expr) && state_table.is_empty()
-        }
-        TStmt::TLetTmp(..) => {
-            panic!("TODO: Implement check_statement for TLetTmp")
+        TStmt::TAssign(_, lvalue, rvalue) => Ok(check_expression(state_table, depth, lvalue)
+            && check_expression(state_table, depth, rvalue)),
+        TStmt::TAssignTmp(..) => Err(Error::UnsupportedStatement("TAssignTmp".to_string())),
+        TStmt::TBlock(_, first, second) => Ok(check_statement(state_table, first, depth)?
+            && check_statement(state_table, second, depth)?),
+        TStmt::TDiscarding(_, expr) => Ok(check_expression(state_table, depth, expr)),
+        TStmt::TReturn(_, expr) => Ok(check_expression(state_table, depth, expr)
+            && state_table
+                .values()
+                .all(|(_, state)| *state == VarState::Consumed)),
+        TStmt::TLetTmp(..) => Err(Error::UnsupportedStatement("TLetTmp".to_string())),
+        TStmt::TBorrow {
+            original,
+            body,
+            mode,
+            ..
+        } => {
+            let borrowed = match mode {
+                BorrowStmtKind::Read => VarState::BorrowedRead,
+                BorrowStmtKind::Write | BorrowStmtKind::Reborrow => VarState::BorrowedWrite,
+            };
+            match state_table.get(original).cloned() {
+                Some((var_depth, VarState::Unconsumed)) => {
+                    state_table.insert(original.clone(), (var_depth, borrowed.clone()));
+                    let result = check_statement(state_table, body, depth);
+                    // The original can't be used while it's borrowed.
+                    let still_borrowed = state_table.get(original) == Some(&(var_depth, borrowed));
+                    state_table.insert(original.clone(), (var_depth, VarState::Unconsumed));
+                    Ok(result? && still_borrowed)
+                }
+                Some(_) => Ok(false),
+                None => check_statement(state_table, body, depth),
+            }
         }
-        TStmt::TBorrow { .. } => {
-            panic!("TODO: Implement check_statement for TBorrow")
+        TStmt::TDestructure(_, _, bindings, expr, body) => {
+            Ok(check_expression(state_table, depth, expr)
+                && check_bindings(state_table, bindings, body, depth)?)
         }
-        TStmt::TDestructure(_, _, _, _, _) => todo!(),
-        TStmt::TAssignVar(_, _, _) => todo!(),
-        TStmt::TInitialAssign(_, _) => todo!(),
+        TStmt::TAssignVar(..) => Err(Error::UnsupportedStatement("TAssignVar".to_string())),
+        TStmt::TInitialAssign(..) => Err(Error::UnsupportedStatement("TInitialAssign".to_string())),
         TStmt::TIf(_, _cond, then_stmt, else_stmt) => {
             let cond_result = true; //check_expression(state_table, depth, cond);
             let mut then_table = state_table.clone();
             let mut else_table = state_table.clone();
-            let then_result = check_statement(&mut then_table, then_stmt, depth);
-            let else_result = check_statement(&mut else_table, else_stmt, depth);
+            let then_result = check_statement(&mut then_table, then_stmt, depth)?;
+            let else_result = check_statement(&mut else_table, else_stmt, depth)?;
             *state_table = then_table;
-            cond_result && then_result && else_result && *state_table == else_table
+            Ok(cond_result && then_result && else_result && *state_table == else_table)
         }
-        TStmt::TCase(_, _, _, _) => {
-            todo!()
+        TStmt::TCase(_, expr, whens, _) => {
+            let expr_result = check_expression(state_table, depth, expr);
+            // Every branch must leave the linear values in the same state.
+            let mut branches = Vec::new();
+            for TypedWhen(_, bindings, body) in whens {
+                let mut table = state_table.clone();
+                let result = check_bindings(&mut table, bindings, body, depth)?;
+                branches.push((result, table));
+            }
+            let mut branches = branches.into_iter();
+            let Some((mut result, table)) = branches.next() else {
+                return Ok(expr_result);
+            };
+            for (branch_result, branch_table) in branches {
+                result &= branch_result && branch_table == table;
+            }
+            *state_table = table;
+            Ok(expr_result && result)
         }
         TStmt::TWhile(_, _cond, body) => {
             //check_expression(state_table, depth, cond) &&
             check_statement(state_table, body, depth + 1)
         }
-        TStmt::TFor(_, _, start, end, body) => {
-            check_expression(state_table, depth, start)
-                && check_expression(state_table, depth, end)
-                && check_statement(state_table, body, depth + 1)
-        }
+        TStmt::TFor(_, _, start, end, body) => Ok(check_expression(state_table, depth, start)
+            && check_expression(state_table, depth, end)
+            && check_statement(state_table, body, depth + 1)?),
     }
 }
 
+/// Check a statement in the scope of some bindings, the linear ones of which must be consumed by
+/// the statement.
+fn check_bindings(
+    state_table: &mut StateTable,
+    bindings: &[TypedBinding],
+    body: &TStmt,
+    depth: i32,
+) -> Result<bool> {
+    let linear = bindings
+        .iter()
+        .filter(|binding| type_universe(&binding.ty) == Universe::LinearUniverse)
+        .collect::<Vec<_>>();
+    for binding in &linear {
+        state_table.insert(binding.rename.clone(), (depth, VarState::Unconsumed));
+    }
+    let result = check_statement(state_table, body, depth);
+    for binding in &linear {
+        state_table.remove(&binding.rename);
+    }
+    result
+}
+
 fn check_expression(state_table: &mut StateTable, depth: i32, texpr: &TExpr) -> bool {
     // For each variable in the table, check if the variable is used correctly in
     // the expression
@@ -137,13 +247,20 @@ fn check_expression(state_table: &mut StateTable, depth: i32, texpr: &TExpr) ->
 }
 
 fn count(name: &Identifier, texpr: &TExpr) -> Appearances {
+    let count_all = |exprs: &[Box<TExpr>]| {
+        exprs
+            .iter()
+            .fold(Appearances::default(), |acc, expr| acc + count(name, expr))
+    };
+
     match texpr {
         TExpr::TNilConstant
         | TExpr::TBoolConstant(_)
         | TExpr::TIntConstant(_)
         | TExpr::TFloatConstant(_)
         | TExpr::TConstVar(_, _) // Constants variables can't be linear.
-        | TExpr::TStringConstant(_) => Appearances::default(),
+        | TExpr::TStringConstant(_)
+        | TExpr::TSizeOf(_) => Appearances::default(),
 
         TExpr::TParamVar(var_name, _)
         | TExpr::TLocalVar(var_name, _)
@@ -157,31 +274,64 @@ fn count(name: &Identifier, texpr: &TExpr) -> Appearances {
                 Appearances::default()
             },
 
-        TExpr::TVarMethodCall {
-            source_module_name: _,
-            typeclass_id: _,
-            params: _,
-            method_name: _,
-            args: _,
-            dispatch_ty: _,
-            rt: _,
-        } => todo!(),
-        TExpr::TFptrCall(_, _, _) => todo!(),
-        TExpr::TCast(_, _) => todo!(),
-        TExpr::TComparison(_, _, _) => todo!(),
-        TExpr::TConjunction(_, _) => todo!(),
-        TExpr::TDisjunction(_, _) => todo!(),
-        TExpr::TNegation(_) => todo!(),
-        TExpr::TIfExpression(_, _, _) => todo!(),
-        TExpr::TRecordConstructor(_, _) => todo!(),
-        TExpr::TUnionConstructor(_, _, _) => todo!(),
-        TExpr::TSlotAccessor(_, _, _) => todo!(),
-        TExpr::TPointerSlotAccessor(_, _, _) => todo!(),
-        TExpr::TArrayIndex(_, _, _) => todo!(),
-        TExpr::TSpanIndex(_, _, _) => todo!(),
-        TExpr::TEmbed(_, _, _) => todo!(),
-        TExpr::TDeref(_) => todo!(),
-        TExpr::TSizeOf(_) => todo!(),
+        TExpr::TBorrowExpr(mode, var_name) => match (var_name == name, mode) {
+            (false, _) => Appearances::default(),
+            (true, BorrowingMode::ReadBorrow) => Appearances {
+                read: 1,
+                ..Appearances::default()
+            },
+            (true, BorrowingMode::WriteBorrow) => Appearances {
+                write: 1,
+                ..Appearances::default()
+            },
+        },
+
+        TExpr::TFuncall(_, _, args, _)
+        | TExpr::TVarMethodCall { args, .. }
+        | TExpr::TFptrCall(_, args, _)
+        | TExpr::TEmbed(_, _, args) => count_all(args),
+
+        TExpr::TCast(expr, _) | TExpr::TNegation(expr) | TExpr::TDeref(expr) => count(name, expr),
+
+        TExpr::TArithmetic(_, lhs, rhs)
+        | TExpr::TComparison(_, lhs, rhs)
+        | TExpr::TConjunction(lhs, rhs)
+        | TExpr::TDisjunction(lhs, rhs) => count(name, lhs) + count(name, rhs),
+
+        TExpr::TIfExpression(condition, value_true, value_false) => {
+            count(name, condition) + count(name, value_true) + count(name, value_false)
+        }
+
+        TExpr::TRecordConstructor(_, slots) | TExpr::TUnionConstructor(_, _, slots) => slots
+            .iter()
+            .fold(Appearances::default(), |acc, (_, expr)| acc + count(name, expr)),
+
+        // Reading through a path doesn't consume the variable at its root.
+        TExpr::TSlotAccessor(expr, _, _) | TExpr::TPointerSlotAccessor(expr, _, _) => {
+            count_path(name, expr)
+        }
+        TExpr::TArrayIndex(expr, index, _) | TExpr::TSpanIndex(expr, index, _) => {
+            count_path(name, expr) + count(name, index)
+        }
+    }
+}
+
+/// Count the appearances of a variable within the base of a path.
+fn count_path(name: &Identifier, texpr: &TExpr) -> Appearances {
+    match texpr {
+        TExpr::TParamVar(var_name, _) | TExpr::TLocalVar(var_name, _) if var_name == name => {
+            Appearances {
+                path: 1,
+                ..Appearances::default()
+            }
+        }
+        TExpr::TSlotAccessor(expr, _, _) | TExpr::TPointerSlotAccessor(expr, _, _) => {
+            count_path(name, expr)
+        }
+        TExpr::TArrayIndex(expr, index, _) | TExpr::TSpanIndex(expr, index, _) => {
+            count_path(name, expr) + count(name, index)
+        }
+        texpr => count(name, texpr),
     }
 }
 
@@ -202,30 +352,535 @@ fn check_var_in_expr(
     let tup = (state, Partitions::partition(consumed));
 
     match tup {
+        // A consumed variable can't be borrowed or read from either.
+        (VarState::Consumed, Partitions::Zero) => apps.read + apps.write + apps.path == 0,
         (_, Partitions::Zero) => true,
         (VarState::Unconsumed, Partitions::One) => {
             if state_table.get(name) != Some(&(depth, VarState::Unconsumed)) {
                 return false;
             }
-            state_table.remove(name);
+            // Keep the variable around so that consuming it again is an error.
+            state_table.insert(name.clone(), (depth, VarState::Consumed));
             true
         }
         _ => false,
     }
 }
 
+/// A variable in scope while lowering a function body.
+#[derive(Clone)]
+enum Var {
+    Param(Ty),
+    Local(Ty),
+}
+
+/// The type parameters and variables in scope while lowering a function body.
+#[derive(Clone, Default)]
+struct Scope<'a> {
+    type_params: HashMap<&'a str, Universe>,
+    vars: HashMap<&'a str, Var>,
+}
+
+impl<'a> Scope<'a> {
+    fn declare(&mut self, name: &'a Ident, ty: Ty) {
+        self.vars.insert(&name.name, Var::Local(ty));
+    }
+}
+
+/// Lowers function bodies into the typed representation the checker works on. Only the types of
+/// variables matter to the checker, so every other expression is given the type `Unit`.
+struct Lowering<'a> {
+    module: ModuleName,
+    /// Declared universes of the types of the module.
+    universes: HashMap<&'a str, Universe>,
+    /// Names of the functions and constants of the module.
+    values: Vec<&'a str>,
+    /// Source module and original name of every imported symbol, by local name.
+    imports: HashMap<&'a str, (ModuleName, &'a Ident)>,
+    /// Identifiers given to the functions called so far.
+    decls: HashMap<&'a str, DeclId>,
+}
+
+impl<'a> Lowering<'a> {
+    fn new(module: &'a ModuleDef) -> Self {
+        let mut universes = HashMap::new();
+        let mut values = Vec::new();
+        for item in &module.contents {
+            match item {
+                ModuleDefItem::Record(x) => {
+                    universes.insert(x.name.name.as_str(), x.universe.0.into());
+                }
+                ModuleDefItem::Union(x) => {
+                    universes.insert(x.name.name.as_str(), x.universe.0.into());
+                }
+                ModuleDefItem::Type(x) => {
+                    universes.insert(x.name.name.as_str(), x.universe.0.into());
+                }
+                ModuleDefItem::Constant(x) => values.push(x.name.name.as_str()),
+                ModuleDefItem::Function(x) => values.push(x.name.name.as_str()),
+                ModuleDefItem::Instance(_) | ModuleDefItem::TypeClass(_) => {}
+            }
+        }
+
+        let mut imports = HashMap::new();
+        for import in &module.imports {
+            let source = import
+                .module
+                .iter()
+                .map(|x| x.name.as_str())
+                .collect::<Vec<_>>()
+                .join(".");
+            for symbol in &import.symbols {
+                let local = symbol.rename_into.as_ref().unwrap_or(&symbol.import_name);
+                imports.insert(
+                    local.name.as_str(),
+                    (ModuleName(source.clone()), &symbol.import_name),
+                );
+            }
+        }
+
+        Self {
+            module: ModuleName(module.name.name.clone()),
+            universes,
+            values,
+            imports,
+            decls: HashMap::new(),
+        }
+    }
+
+    fn check(
+        &mut self,
+        name: &Ident,
+        type_params: impl IntoIterator<Item = &'a TypeParam>,
+        params: &'a [Param],
+        body: &'a [Statement],
+    ) -> Result<()> {
+        let mut scope = Scope::default();
+        for param in type_params {
+            scope
+                .type_params
+                .insert(&param.name.name, param.universe.0.into());
+        }
+
+        let mut value_params = Vec::new();
+        for param in params {
+            let ty = self.lower_type(&scope, &param.r#type);
+            scope.vars.insert(&param.name.name, Var::Param(ty.clone()));
+            value_params.push(ValueParameter(identifier(&param.name), ty));
+        }
+
+        let body = self.lower_block(&scope, body);
+        match check_function(&value_params, &body)? {
+            true => Ok(()),
+            false => Err(Error::InvalidUse(name.name.clone())),
+        }
+    }
+
+    /// Lower a block, where each `let` statement scopes over the rest of the block.
+    fn lower_block(&mut self, scope: &Scope<'a>, stmts: &'a [Statement]) -> TStmt {
+        let Some((stmt, rest)) = stmts.split_first() else {
+            return TStmt::TSkip(Span::default());
+        };
+
+        let first = match stmt {
+            Statement::Assign(stmt) => TStmt::TAssign(
+                Span::default(),
+                Box::new(self.lower_path(scope, &stmt.target)),
+                Box::new(self.lower_expr(scope, &stmt.value)),
+            ),
+            Statement::Borrow(stmt) => {
+                let orig_type = self.lower_type(scope, &stmt.r#type);
+                let mut inner = scope.clone();
+                inner
+                    .type_params
+                    .insert(&stmt.reg.name, Universe::RegionUniverse);
+                let region = Box::new(self.lower_type(
+                    &inner,
+                    &TypeSpec::Simple {
+                        name: stmt.reg.clone(),
+                    },
+                ));
+                let ref_type = match stmt.mut_mode {
+                    BorrowMutMode::Read => Ty::ReadRef(Box::new(orig_type.clone()), region),
+                    BorrowMutMode::Write => Ty::WriteRef(Box::new(orig_type.clone()), region),
+                };
+                inner.declare(&stmt.name, ref_type.clone());
+
+                TStmt::TBorrow {
+                    span: Span::default(),
+                    original: identifier(&stmt.orig),
+                    rename: identifier(&stmt.name),
+                    region: identifier(&stmt.reg),
+                    orig_type,
+                    ref_type,
+                    body: Box::new(self.lower_block(&inner, &stmt.body)),
+                    mode: match stmt.mode {
+                        BorrowMode::Read => BorrowStmtKind::Read,
+                        BorrowMode::Write => BorrowStmtKind::Write,
+                        BorrowMode::ReBorrow => BorrowStmtKind::Reborrow,
+                    },
+                }
+            }
+            Statement::Case(stmt) => {
+                let whens = stmt
+                    .variants
+                    .iter()
+                    .map(|variant| {
+                        let mut inner = scope.clone();
+                        let bindings = self.lower_bindings(&mut inner, &variant.bindings);
+                        let body = self.lower_block(&inner, &variant.block);
+                        TypedWhen(identifier(&variant.ident), bindings, Box::new(body))
+                    })
+                    .collect();
+                let case_ref = match &stmt.value {
+                    Expression::Atomic(
+                        AtomicExpr::BorrowRead(_)
+                        | AtomicExpr::BorrowWrite(_)
+                        | AtomicExpr::ReBorrow(_),
+                    ) => CaseRef::CaseRefValue,
+                    _ => CaseRef::CasePlain,
+                };
+
+                TStmt::TCase(
+                    Span::default(),
+                    Box::new(self.lower_expr(scope, &stmt.value)),
+                    whens,
+                    case_ref,
+                )
+            }
+            Statement::Discard(expr) => {
+                TStmt::TDiscarding(Span::default(), Box::new(self.lower_expr(scope, expr)))
+            }
+            Statement::For(stmt) => {
+                let mut inner = scope.clone();
+                inner.declare(&stmt.name, self.lower_type(scope, &named_type("Index")));
+
+                TStmt::TFor(
+                    Span::default(),
+                    identifier(&stmt.name),
+                    Box::new(self.lower_expr(scope, &stmt.range.start)),
+                    Box::new(self.lower_expr(scope, &stmt.range.end)),
+                    Box::new(self.lower_block(&inner, &stmt.contents)),
+                )
+            }
+            Statement::If(stmt) => TStmt::TIf(
+                Span::default(),
+                Box::new(self.lower_expr(scope, &stmt.value)),
+                Box::new(self.lower_block(scope, &stmt.contents)),
+                Box::new(self.lower_block(scope, stmt.r#else.as_deref().unwrap_or_default())),
+            ),
+            Statement::Let(stmt) => {
+                let value = Box::new(self.lower_expr(scope, &stmt.value));
+                let mutability = match stmt.is_mutable {
+                    true => Mutability::Mutable,
+                    false => Mutability::Immutable,
+                };
+
+                let mut inner = scope.clone();
+                return match &stmt.target {
+                    LetStmtTarget::Simple { name, r#type } => {
+                        let ty = self.lower_type(scope, r#type);
+                        inner.declare(name, ty.clone());
+                        let body = self.lower_block(&inner, rest);
+                        TStmt::TLet(
+                            Span::default(),
+                            identifier(name),
+                            value,
+                            mutability,
+                            ty,
+                            Box::new(body),
+                        )
+                    }
+                    LetStmtTarget::Destructure(bindings) => {
+                        let bindings = self.lower_bindings(&mut inner, bindings);
+                        let body = self.lower_block(&inner, rest);
+                        TStmt::TDestructure(
+                            Span::default(),
+                            mutability,
+                            bindings,
+                            value,
+                            Box::new(body),
+                        )
+                    }
+                };
+            }
+            Statement::Return(expr) => {
+                TStmt::TReturn(Span::default(), Box::new(self.lower_expr(scope, expr)))
+            }
+            Statement::While(stmt) => TStmt::TWhile(
+                Span::default(),
+                Box::new(self.lower_expr(scope, &stmt.value)),
+                Box::new(self.lower_block(scope, &stmt.contents)),
+            ),
+        };
+
+        match rest.is_empty() {
+            true => first,
+            false => TStmt::TBlock(
+                Span::default(),
+                Box::new(first),
+                Box::new(self.lower_block(scope, rest)),
+            ),
+        }
+    }
+
+    fn lower_bindings(
+        &mut self,
+        scope: &mut Scope<'a>,
+        bindings: &'a [crate::ast::Binding],
+    ) -> Vec<TypedBinding> {
+        bindings
+            .iter()
+            .map(|binding| {
+                let ty = self.lower_type(scope, &binding.r#type);
+                let rename = binding.rename.as_ref().unwrap_or(&binding.name);
+                scope.declare(rename, ty.clone());
+                TypedBinding {
+                    name: identifier(&binding.name),
+                    ty,
+                    rename: identifier(rename),
+                }
+            })
+            .collect()
+    }
+
+    fn lower_expr(&mut self, scope: &Scope<'a>, expr: &'a Expression) -> TExpr {
+        let expr = match expr {
+            Expression::Atomic(expr) => return self.lower_atomic(scope, expr),
+            Expression::Compound(expr) => expr,
+        };
+
+        let mut binary = |make: fn(Box<TExpr>, Box<TExpr>) -> TExpr, lhs, rhs| {
+            let lhs = Box::new(self.lower_atomic(scope, lhs));
+            make(lhs, Box::new(self.lower_atomic(scope, rhs)))
+        };
+        match expr {
+            CompoundExpr::Cmp(expr) => {
+                let (op, lhs, rhs) = match expr {
+                    CmpExpr::Eq(lhs, rhs) => (ComparisonOperator::Equal, lhs, rhs),
+                    CmpExpr::NotEq(lhs, rhs) => (ComparisonOperator::NotEqual, lhs, rhs),
+                    CmpExpr::Lt(lhs, rhs) => (ComparisonOperator::LessThan, lhs, rhs),
+                    CmpExpr::LtEq(lhs, rhs) => (ComparisonOperator::LessThanOrEqual, lhs, rhs),
+                    CmpExpr::Gt(lhs, rhs) => (ComparisonOperator::GreaterThan, lhs, rhs),
+                    CmpExpr::GtEq(lhs, rhs) => (ComparisonOperator::GreaterThanOrEqual, lhs, rhs),
+                };
+                TExpr::TComparison(
+                    op,
+                    Box::new(self.lower_atomic(scope, lhs)),
+                    Box::new(self.lower_atomic(scope, rhs)),
+                )
+            }
+            CompoundExpr::Logic(LogicExpr::And(lhs, rhs)) => binary(TExpr::TConjunction, lhs, rhs),
+            CompoundExpr::Logic(LogicExpr::Or(lhs, rhs)) => binary(TExpr::TDisjunction, lhs, rhs),
+            CompoundExpr::Logic(LogicExpr::Not(expr)) => {
+                TExpr::TNegation(Box::new(self.lower_atomic(scope, expr)))
+            }
+            CompoundExpr::Arith(expr) => {
+                let (op, lhs, rhs) = match expr {
+                    ArithExpr::Add(lhs, rhs) => (ArithmeticOperator::Add, lhs, rhs),
+                    ArithExpr::Sub(lhs, rhs) => (ArithmeticOperator::Subtract, lhs, rhs),
+                    ArithExpr::Mul(lhs, rhs) => (ArithmeticOperator::Multiply, lhs, rhs),
+                    ArithExpr::Div(lhs, rhs) => (ArithmeticOperator::Divide, lhs, rhs),
+                    // Negation is a subtraction from zero.
+                    ArithExpr::Neg(expr) => {
+                        (ArithmeticOperator::Subtract, &AtomicExpr::ConstInt(0), expr)
+                    }
+                };
+                TExpr::TArithmetic(
+                    op,
+                    Box::new(self.lower_atomic(scope, lhs)),
+                    Box::new(self.lower_atomic(scope, rhs)),
+                )
+            }
+            CompoundExpr::Select(expr) => TExpr::TIfExpression(
+                Box::new(self.lower_expr(scope, &expr.condition)),
+                Box::new(self.lower_expr(scope, &expr.value_true)),
+                Box::new(self.lower_expr(scope, &expr.value_false)),
+            ),
+            CompoundExpr::Cast(expr) => TExpr::TCast(
+                Box::new(self.lower_atomic(scope, &expr.value)),
+                self.lower_type(scope, &expr.r#type),
+            ),
+        }
+    }
+
+    fn lower_atomic(&mut self, scope: &Scope<'a>, expr: &'a AtomicExpr) -> TExpr {
+        match expr {
+            AtomicExpr::ConstNil => TExpr::TNilConstant,
+            AtomicExpr::ConstBool(value) => TExpr::TBoolConstant(*value),
+            AtomicExpr::ConstChar(value) => TExpr::TIntConstant(u32::from(*value).to_string()),
+            AtomicExpr::ConstInt(value) => TExpr::TIntConstant(value.to_string()),
+            AtomicExpr::ConstFloat(value) => TExpr::TFloatConstant(value.to_string()),
+            AtomicExpr::ConstStr(value) => {
+                TExpr::TStringConstant(EscapedString::new(value.as_str()))
+            }
+            AtomicExpr::FnCall(expr) => {
+                let args = match &expr.args {
+                    FnCallArgs::Empty => Vec::new(),
+                    FnCallArgs::Positional(args) => args.iter().collect(),
+                    FnCallArgs::Named(args) => {
+                        let mut args = args.iter().collect::<Vec<_>>();
+                        args.sort_by(|(lhs, _), (rhs, _)| lhs.name.cmp(&rhs.name));
+                        args.into_iter().map(|(_, arg)| arg).collect()
+                    }
+                };
+                let args = args
+                    .into_iter()
+                    .map(|arg| Box::new(self.lower_expr(scope, arg)))
+                    .collect();
+
+                let next_id = DeclId(self.decls.len() as i32);
+                let id = self
+                    .decls
+                    .entry(&expr.target.name)
+                    .or_insert(next_id)
+                    .clone();
+                TExpr::TFuncall(id, self.qualify(&expr.target), args, Ty::Unit)
+            }
+            AtomicExpr::Path(expr) | AtomicExpr::RefPath(expr) => self.lower_path(scope, expr),
+            AtomicExpr::Paren(expr) => self.lower_expr(scope, expr),
+            AtomicExpr::Intrinsic(IntrinExpr::Embed { r#type, exp, args }) => TExpr::TEmbed(
+                self.lower_type(scope, r#type),
+                exp.clone(),
+                args.iter()
+                    .map(|arg| Box::new(self.lower_expr(scope, arg)))
+                    .collect(),
+            ),
+            AtomicExpr::SizeOf(r#type) => TExpr::TSizeOf(self.lower_type(scope, r#type)),
+            AtomicExpr::BorrowRead(name) => {
+                TExpr::TBorrowExpr(BorrowingMode::ReadBorrow, identifier(name))
+            }
+            AtomicExpr::BorrowWrite(name) | AtomicExpr::ReBorrow(name) => {
+                TExpr::TBorrowExpr(BorrowingMode::WriteBorrow, identifier(name))
+            }
+            AtomicExpr::Deref(expr) => TExpr::TDeref(Box::new(self.lower_atomic(scope, expr))),
+        }
+    }
+
+    fn lower_path(&mut self, scope: &Scope<'a>, expr: &'a PathExpr) -> TExpr {
+        let base = match scope.vars.get(expr.first.name.as_str()) {
+            Some(Var::Param(ty)) => TExpr::TParamVar(identifier(&expr.first), ty.clone()),
+            Some(Var::Local(ty)) => TExpr::TLocalVar(identifier(&expr.first), ty.clone()),
+            None => TExpr::TConstVar(self.qualify(&expr.first), Ty::Unit),
+        };
+
+        expr.extra.iter().fold(base, |base, segment| match segment {
+            PathSegment::SlotAccess(slot) => {
+                TExpr::TSlotAccessor(Box::new(base), identifier(slot), Ty::Unit)
+            }
+            PathSegment::PtrSlotAccess(slot) => {
+                TExpr::TPointerSlotAccessor(Box::new(base), identifier(slot), Ty::Unit)
+            }
+            PathSegment::ArrayIndex(index) => TExpr::TArrayIndex(
+                Box::new(base),
+                Box::new(self.lower_expr(scope, index)),
+                Ty::Unit,
+            ),
+        })
+    }
+
+    fn lower_type(&self, scope: &Scope<'a>, r#type: &TypeSpec) -> Ty {
+        let lower = |r#type| Box::new(self.lower_type(scope, r#type));
+        let (name, args) = match r#type {
+            TypeSpec::Simple { name } => (name, [].as_slice()),
+            TypeSpec::Generic { name, type_params } => (name, type_params.as_slice()),
+            TypeSpec::BorrowRead { lhs, rhs } => return Ty::ReadRef(lower(lhs), lower(rhs)),
+            TypeSpec::BorrowWrite { lhs, rhs } => return Ty::WriteRef(lower(lhs), lower(rhs)),
+            TypeSpec::SpanRead { lhs, rhs } => return Ty::Span(lower(lhs), lower(rhs)),
+            TypeSpec::SpanWrite { lhs, rhs } => return Ty::SpanMut(lower(lhs), lower(rhs)),
+        };
+
+        if let Some(universe) = scope.type_params.get(name.name.as_str()) {
+            return Ty::NamedType(self.qualify(name), Vec::new(), *universe);
+        }
+        if let Some(ty) = primitive_type(&name.name) {
+            return ty;
+        }
+
+        let args = args
+            .iter()
+            .map(|arg| self.lower_type(scope, arg))
+            .collect::<Vec<_>>();
+        match (name.name.as_str(), args.as_slice()) {
+            ("Pointer", [arg]) => return Ty::Pointer(Box::new(arg.clone())),
+            ("Address", [arg]) => return Ty::Address(Box::new(arg.clone())),
+            _ => {}
+        }
+
+        let has_universe = |universe| args.iter().any(|arg| type_universe(arg) == universe);
+        let universe = match self.universes.get(name.name.as_str()) {
+            // Types declared in the `Type` universe take the universe of their arguments.
+            Some(Universe::TypeUniverse) if has_universe(Universe::LinearUniverse) => {
+                Universe::LinearUniverse
+            }
+            Some(Universe::TypeUniverse) if has_universe(Universe::TypeUniverse) => {
+                Universe::TypeUniverse
+            }
+            Some(Universe::TypeUniverse) => Universe::FreeUniverse,
+            Some(universe) => *universe,
+            None if name.name == "RootCapability" => Universe::LinearUniverse,
+            None => Universe::FreeUniverse,
+        };
+        Ty::NamedType(self.qualify(name), args, universe)
+    }
+
+    /// Resolve a name to the module which declares it.
+    fn qualify(&self, name: &Ident) -> QIdent {
+        let (source, original) = match self.imports.get(name.name.as_str()) {
+            Some((source, original)) => (source.clone(), *original),
+            None if self.values.contains(&name.name.as_str())
+                || self.universes.contains_key(name.name.as_str()) =>
+            {
+                (self.module.clone(), name)
+            }
+            None => (ModuleName(String::from("Austral.Pervasive")), name),
+        };
+
+        QIdent {
+            source,
+            original: identifier(original),
+            local: identifier(name),
+        }
+    }
+}
+
+fn identifier(name: &Ident) -> Identifier {
+    Identifier::new(&name.name)
+}
+
+fn named_type(name: &str) -> TypeSpec {
+    TypeSpec::Simple {
+        name: Ident {
+            name: name.to_string(),
+        },
+    }
+}
+
 #[cfg(test)]
 mod test {
     use crate::linearity_check::VarState;
     use crate::span::Span;
     use crate::{
-        common::{Identifier, Mutability},
-        linearity_check::check_statement,
-        r#type::Ty,
+        ast::ModuleDef,
+        common::{DeclId, Identifier, ModuleName, Mutability, QIdent},
+        error::LinearityError as Error,
+        lexer::lex,
+        linearity_check::{check_function, check_module_def, check_statement},
+        r#type::{Ty, Universe, ValueParameter},
         stages::{TExpr, TStmt},
     };
+    use chumsky::Parser;
     use std::collections::HashMap;
 
+    fn check(source: &str) -> crate::error::LinearityResult<()> {
+        let tokens = lex(source)
+            .map(|(token, _)| token.unwrap())
+            .collect::<Vec<_>>();
+        let module = ModuleDef::parser().parse(&tokens).into_result().unwrap();
+
+        check_module_def(&module)
+    }
+
     #[test]
     fn test_let() {
         let mut state_table = HashMap::new();
@@ -241,7 +896,7 @@ mod test {
             )),
         );
         let result = check_statement(&mut state_table, &stmt, 0);
-        assert!(!result);
+        assert_eq!(result, Ok(false));
     }
 
     #[should_panic]
@@ -276,7 +931,7 @@ mod test {
             )),
         );
         let result = check_statement(&mut state_table, &stmt, 0);
-        assert!(result);
+        assert_eq!(result, Ok(true));
     }
 
     #[test]
@@ -300,6 +955,184 @@ mod test {
             )),
         );
         let result = check_statement(&mut state_table, &stmt, 0);
-        assert!(!result);
+        assert_eq!(result, Ok(false));
+    }
+
+    #[test]
+    fn unsupported_statement() {
+        let mut state_table = HashMap::new();
+        let stmt = TStmt::TLetTmp(
+            Identifier::new("t"),
+            Ty::Boolean,
+            Box::new(TExpr::TBoolConstant(true)),
+        );
+        let result = check_statement(&mut state_table, &stmt, 0);
+        assert_eq!(
+            result,
+            Err(Error::UnsupportedStatement("TLetTmp".to_string()))
+        );
+    }
+
+    fn pervasive(name: &str) -> QIdent {
+        QIdent {
+            source: ModuleName(String::from("Austral.Pervasive")),
+            original: Identifier::new(name),
+            local: Identifier::new(name),
+        }
+    }
+
+    fn root_capability() -> Ty {
+        Ty::NamedType(
+            pervasive("RootCapability"),
+            vec![],
+            Universe::LinearUniverse,
+        )
+    }
+
+    fn surrender_root() -> TStmt {
+        TStmt::TDiscarding(
+            Span::default(),
+            Box::new(TExpr::TFuncall(
+                DeclId(0),
+                pervasive("surrenderRoot"),
+                vec![Box::new(TExpr::TParamVar(
+                    Identifier::new("root"),
+                    root_capability(),
+                ))],
+                Ty::Unit,
+            )),
+        )
+    }
+
+    fn exit_success() -> TStmt {
+        TStmt::TReturn(
+            Span::default(),
+            Box::new(TExpr::TFuncall(
+                DeclId(1),
+                pervasive("ExitSuccess"),
+                vec![],
+                Ty::NamedType(pervasive("ExitCode"), vec![], Universe::FreeUniverse),
+            )),
+        )
+    }
+
+    #[test]
+    fn test_root_capability_consumed() {
+        let params = [ValueParameter(Identifier::new("root"), root_capability())];
+        let body = TStmt::TBlock(
+            Span::default(),
+            Box::new(surrender_root()),
+            Box::new(exit_success()),
+        );
+        assert_eq!(check_function(&params, &body), Ok(true));
+    }
+
+    #[test]
+    fn test_root_capability_not_consumed() {
+        let params = [ValueParameter(Identifier::new("root"), root_capability())];
+        assert_eq!(check_function(&params, &exit_success()), Ok(false));
+    }
+
+    #[test]
+    fn test_root_capability_consumed_twice() {
+        let params = [ValueParameter(Identifier::new("root"), root_capability())];
+        let body = TStmt::TBlock(
+            Span::default(),
+            Box::new(surrender_root()),
+            Box::new(TStmt::TBlock(
+                Span::default(),
+                Box::new(surrender_root()),
+                Box::new(exit_success()),
+            )),
+        );
+        assert_eq!(check_function(&params, &body), Ok(false));
+    }
+
+    #[test]
+    fn root_capability_in_module() {
+        let source = include_str!("../../../programs/suites/001-trivial/007-root-cap/Test.aum");
+        assert_eq!(check(source), Ok(()));
+        let source = include_str!("../../../programs/suites/001-trivial/010-cli/Test.aum");
+        assert_eq!(check(source), Ok(()));
+
+        let result = check(
+            "module body Test is
+                function main(root: RootCapability): ExitCode is
+                    return ExitSuccess();
+                end;
+            end module body.",
+        );
+        assert_eq!(result, Err(Error::InvalidUse("main".to_string())));
+
+        let result = check(
+            "module body Test is
+                function main(root: RootCapability): ExitCode is
+                    for i from 0 to 3 do
+                        surrenderRoot(root);
+                    end for;
+                    return ExitSuccess();
+                end;
+            end module body.",
+        );
+        assert_eq!(result, Err(Error::InvalidUse("main".to_string())));
+    }
+
+    #[test]
+    fn root_capability_consumed_in_lets() {
+        let source = |body| {
+            format!(
+                "module body Test is
+                    function main(root: RootCapability): ExitCode is
+                        {body}
+                        return ExitSuccess();
+                    end;
+                end module body."
+            )
+        };
+
+        assert_eq!(
+            check(&source("let a: Unit := surrenderRoot(root);")),
+            Ok(())
+        );
+        assert_eq!(
+            check(&source(
+                "let a: Unit := surrenderRoot(root);
+                let b: Unit := surrenderRoot(root);"
+            )),
+            Err(Error::InvalidUse("main".to_string()))
+        );
+    }
+
+    #[test]
+    fn linear_values_in_branches() {
+        let source = |else_branch| {
+            format!(
+                "module body Test is
+                    record Token: Linear is
+                        id: Int32;
+                    end;
+
+                    function consume(token: Token): Unit is
+                        let {{ id: Int32 }} := token;
+                        return nil;
+                    end;
+
+                    function f(token: Token, flag: Bool): Unit is
+                        if flag then
+                            consume(token);
+                        else
+                            {else_branch}
+                        end if;
+                        return nil;
+                    end;
+                end module body."
+            )
+        };
+
+        assert_eq!(check(&source("consume(token);")), Ok(()));
+        assert_eq!(
+            check(&source("printLn(flag);")),
+            Err(Error::InvalidUse("f".to_string()))
+        );
     }
 }
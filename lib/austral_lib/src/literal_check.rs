@@ -0,0 +1,430 @@
+use crate::{
+    ast::{
+        ArithExpr, AtomicExpr, CmpExpr, CompoundExpr, Expression, FnCallArgs, FnCallExpr, Ident,
+        IntrinExpr, LetStmtTarget, LogicExpr, ModuleDef, ModuleDefItem, PathSegment, Statement,
+        TypeSpec,
+    },
+    error::{LiteralError as Error, LiteralResult as Result},
+    r#type::Ty,
+    type_system::{integer_bounds, primitive_type},
+};
+use std::collections::HashMap;
+
+/// The parameters and return type of a function, as seen by call sites.
+struct Signature<'a> {
+    params: Vec<(&'a str, &'a TypeSpec)>,
+    ret_type: &'a TypeSpec,
+}
+
+struct Checker<'a> {
+    functions: HashMap<&'a str, Signature<'a>>,
+    /// The declared type of every variable in scope, including constants and parameters.
+    locals: HashMap<String, TypeSpec>,
+}
+
+/// Check that every numeric literal fits the type it's used at.
+pub fn check_module_def(module: &ModuleDef) -> Result<()> {
+    let mut checker = Checker {
+        functions: HashMap::new(),
+        locals: HashMap::new(),
+    };
+
+    for item in &module.contents {
+        match item {
+            ModuleDefItem::Function(def) => {
+                let signature = Signature {
+                    params: def
+                        .params
+                        .iter()
+                        .map(|x| (x.name.name.as_str(), &x.r#type))
+                        .collect(),
+                    ret_type: &def.ret_type,
+                };
+                checker.functions.insert(&def.name.name, signature);
+            }
+            ModuleDefItem::Constant(def) => {
+                checker
+                    .locals
+                    .insert(def.name.name.clone(), def.r#type.clone());
+            }
+            _ => {}
+        }
+    }
+
+    for item in &module.contents {
+        match item {
+            ModuleDefItem::Function(def) => {
+                let scope = checker.locals.clone();
+                for param in &def.params {
+                    checker
+                        .locals
+                        .insert(param.name.name.clone(), param.r#type.clone());
+                }
+                checker.statements(&def.body, &def.ret_type)?;
+                checker.locals = scope;
+            }
+            ModuleDefItem::Instance(def) => {
+                for method in &def.methods {
+                    let scope = checker.locals.clone();
+                    for param in &method.params {
+                        checker
+                            .locals
+                            .insert(param.name.name.clone(), param.r#type.clone());
+                    }
+                    checker.statements(&method.body, &method.ret_ty)?;
+                    checker.locals = scope;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
+
+fn simple_type(name: &str) -> TypeSpec {
+    TypeSpec::Simple {
+        name: Ident::new(name),
+    }
+}
+
+impl<'a> Checker<'a> {
+    /// Check a block, whose variables go out of scope at its end.
+    fn statements(&mut self, stmts: &[Statement], ret_type: &TypeSpec) -> Result<()> {
+        let scope = self.locals.clone();
+        for stmt in stmts {
+            self.statement(stmt, ret_type)?;
+        }
+        self.locals = scope;
+
+        Ok(())
+    }
+
+    fn statement(&mut self, stmt: &Statement, ret_type: &TypeSpec) -> Result<()> {
+        match stmt {
+            Statement::Assign(stmt) => {
+                let r#type = if stmt.target.extra.is_empty() {
+                    self.locals.get(&stmt.target.first.name).cloned()
+                } else {
+                    None
+                };
+                self.path_segments(&stmt.target.extra)?;
+                self.expression(&stmt.value, r#type.as_ref())
+            }
+            Statement::Borrow(stmt) => self.statements(&stmt.body, ret_type),
+            Statement::Case(stmt) => {
+                self.expression(&stmt.value, None)?;
+                for variant in &stmt.variants {
+                    let scope = self.locals.clone();
+                    for binding in &variant.bindings {
+                        let name = binding.rename.as_ref().unwrap_or(&binding.name);
+                        self.locals
+                            .insert(name.name.clone(), binding.r#type.clone());
+                    }
+                    self.statements(&variant.block, ret_type)?;
+                    self.locals = scope;
+                }
+                Ok(())
+            }
+            Statement::Discard(expr) => self.expression(expr, None),
+            Statement::For(stmt) => {
+                let index = simple_type("Index");
+                self.expression(&stmt.range.start, Some(&index))?;
+                self.expression(&stmt.range.end, Some(&index))?;
+
+                let scope = self.locals.clone();
+                self.locals.insert(stmt.name.name.clone(), index);
+                self.statements(&stmt.contents, ret_type)?;
+                self.locals = scope;
+                Ok(())
+            }
+            Statement::If(stmt) => {
+                self.expression(&stmt.value, Some(&simple_type("Bool")))?;
+                self.statements(&stmt.contents, ret_type)?;
+                match &stmt.r#else {
+                    Some(stmts) => self.statements(stmts, ret_type),
+                    None => Ok(()),
+                }
+            }
+            Statement::Let(stmt) => match &stmt.target {
+                LetStmtTarget::Simple { name, r#type } => {
+                    self.expression(&stmt.value, Some(r#type))?;
+                    self.locals.insert(name.name.clone(), r#type.clone());
+                    Ok(())
+                }
+                LetStmtTarget::Destructure(bindings) => {
+                    self.expression(&stmt.value, None)?;
+                    for binding in bindings {
+                        let name = binding.rename.as_ref().unwrap_or(&binding.name);
+                        self.locals
+                            .insert(name.name.clone(), binding.r#type.clone());
+                    }
+                    Ok(())
+                }
+            },
+            Statement::Return(expr) => self.expression(expr, Some(ret_type)),
+            Statement::While(stmt) => {
+                self.expression(&stmt.value, Some(&simple_type("Bool")))?;
+                self.statements(&stmt.contents, ret_type)
+            }
+        }
+    }
+
+    fn expression(&self, expr: &Expression, r#type: Option<&TypeSpec>) -> Result<()> {
+        match expr {
+            Expression::Atomic(expr) => self.atomic(expr, r#type),
+            Expression::Compound(expr) => match expr {
+                CompoundExpr::Arith(ArithExpr::Neg(AtomicExpr::ConstInt(value))) => {
+                    check_integer(-(*value as i128), r#type)
+                }
+                CompoundExpr::Arith(ArithExpr::Neg(expr)) => self.atomic(expr, r#type),
+                CompoundExpr::Arith(
+                    ArithExpr::Add(lhs, rhs)
+                    | ArithExpr::Sub(lhs, rhs)
+                    | ArithExpr::Mul(lhs, rhs)
+                    | ArithExpr::Div(lhs, rhs),
+                ) => {
+                    let inferred = r#type
+                        .cloned()
+                        .or_else(|| self.infer(lhs))
+                        .or_else(|| self.infer(rhs));
+                    self.atomic(lhs, inferred.as_ref())?;
+                    self.atomic(rhs, inferred.as_ref())
+                }
+                CompoundExpr::Cmp(
+                    CmpExpr::Eq(lhs, rhs)
+                    | CmpExpr::NotEq(lhs, rhs)
+                    | CmpExpr::Lt(lhs, rhs)
+                    | CmpExpr::LtEq(lhs, rhs)
+                    | CmpExpr::Gt(lhs, rhs)
+                    | CmpExpr::GtEq(lhs, rhs),
+                ) => {
+                    let inferred = self.infer(lhs).or_else(|| self.infer(rhs));
+                    self.atomic(lhs, inferred.as_ref())?;
+                    self.atomic(rhs, inferred.as_ref())
+                }
+                CompoundExpr::Logic(LogicExpr::And(lhs, rhs) | LogicExpr::Or(lhs, rhs)) => {
+                    let bool_type = simple_type("Bool");
+                    self.atomic(lhs, Some(&bool_type))?;
+                    self.atomic(rhs, Some(&bool_type))
+                }
+                CompoundExpr::Logic(LogicExpr::Not(expr)) => {
+                    self.atomic(expr, Some(&simple_type("Bool")))
+                }
+                CompoundExpr::Select(expr) => {
+                    self.expression(&expr.condition, Some(&simple_type("Bool")))?;
+                    self.expression(&expr.value_true, r#type)?;
+                    self.expression(&expr.value_false, r#type)
+                }
+                CompoundExpr::Cast(expr) => match &expr.value {
+                    // A literal with a type ascription, such as `3.14 : Float32`.
+                    AtomicExpr::ConstInt(_)
+                    | AtomicExpr::ConstFloat(_)
+                    | AtomicExpr::ConstChar(_) => self.atomic(&expr.value, Some(&expr.r#type)),
+                    value => self.atomic(value, None),
+                },
+            },
+        }
+    }
+
+    fn atomic(&self, expr: &AtomicExpr, r#type: Option<&TypeSpec>) -> Result<()> {
+        match expr {
+            AtomicExpr::ConstInt(value) => check_integer(*value as i128, r#type),
+            AtomicExpr::ConstChar(value) => check_integer(*value as i128, r#type),
+            AtomicExpr::ConstFloat(value) => check_float(*value, r#type),
+            AtomicExpr::FnCall(expr) => self.call(expr),
+            AtomicExpr::Path(expr) | AtomicExpr::RefPath(expr) => self.path_segments(&expr.extra),
+            AtomicExpr::Paren(expr) => self.expression(expr, r#type),
+            AtomicExpr::Intrinsic(IntrinExpr::Embed { args, .. }) => {
+                args.iter().try_for_each(|arg| self.expression(arg, None))
+            }
+            AtomicExpr::Deref(expr) => self.atomic(expr, None),
+            AtomicExpr::ConstNil
+            | AtomicExpr::ConstBool(_)
+            | AtomicExpr::ConstStr(_)
+            | AtomicExpr::SizeOf(_)
+            | AtomicExpr::BorrowRead(_)
+            | AtomicExpr::BorrowWrite(_)
+            | AtomicExpr::ReBorrow(_) => Ok(()),
+        }
+    }
+
+    fn call(&self, expr: &FnCallExpr) -> Result<()> {
+        let signature = self.functions.get(expr.target.name.as_str());
+        match &expr.args {
+            FnCallArgs::Empty => Ok(()),
+            FnCallArgs::Positional(args) => args.iter().enumerate().try_for_each(|(i, arg)| {
+                let r#type = signature.and_then(|x| x.params.get(i)).map(|x| x.1);
+                self.expression(arg, r#type)
+            }),
+            FnCallArgs::Named(args) => args.iter().try_for_each(|(name, arg)| {
+                let r#type = signature
+                    .and_then(|x| x.params.iter().find(|(param, _)| *param == name.name))
+                    .map(|x| x.1);
+                self.expression(arg, r#type)
+            }),
+        }
+    }
+
+    fn path_segments(&self, segments: &[PathSegment]) -> Result<()> {
+        segments.iter().try_for_each(|segment| match segment {
+            PathSegment::ArrayIndex(expr) => self.expression(expr, Some(&simple_type("Index"))),
+            PathSegment::SlotAccess(_) | PathSegment::PtrSlotAccess(_) => Ok(()),
+        })
+    }
+
+    /// Infer the type of an operand from the variables and functions in scope, so literals on the
+    /// other side of a binary operator can be checked against it.
+    fn infer(&self, expr: &AtomicExpr) -> Option<TypeSpec> {
+        match expr {
+            AtomicExpr::Path(expr) if expr.extra.is_empty() => {
+                self.locals.get(&expr.first.name).cloned()
+            }
+            AtomicExpr::FnCall(expr) => self
+                .functions
+                .get(expr.target.name.as_str())
+                .map(|x| x.ret_type.clone()),
+            AtomicExpr::Paren(expr) => match expr.as_ref() {
+                Expression::Atomic(expr) => self.infer(expr),
+                Expression::Compound(CompoundExpr::Cast(expr)) => Some(expr.r#type.clone()),
+                Expression::Compound(CompoundExpr::Arith(
+                    ArithExpr::Add(lhs, rhs)
+                    | ArithExpr::Sub(lhs, rhs)
+                    | ArithExpr::Mul(lhs, rhs)
+                    | ArithExpr::Div(lhs, rhs),
+                )) => self.infer(lhs).or_else(|| self.infer(rhs)),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+}
+
+/// Check that an integer literal (or a negated one) fits the expected type.
+fn check_integer(value: i128, r#type: Option<&TypeSpec>) -> Result<()> {
+    let Some(r#type) = r#type else {
+        return Ok(());
+    };
+
+    match primitive_type(&r#type.to_string()) {
+        Some(Ty::Integer(signedness, width)) => {
+            let (min, max) = integer_bounds(signedness, width);
+            if value < min || value > max {
+                return Err(Error::OutOfRange {
+                    literal: value.to_string(),
+                    r#type: r#type.to_string(),
+                });
+            }
+            Ok(())
+        }
+        Some(Ty::SingleFloat | Ty::DoubleFloat | Ty::Boolean | Ty::Unit) => Err(Error::Mismatch {
+            literal: value.to_string(),
+            r#type: r#type.to_string(),
+        }),
+        // The literal is used at a non-primitive type, which the type checker will report.
+        _ => Ok(()),
+    }
+}
+
+/// Check that a float literal is used at a float type that can represent it.
+fn check_float(value: f64, r#type: Option<&TypeSpec>) -> Result<()> {
+    let Some(r#type) = r#type else {
+        return Ok(());
+    };
+
+    match primitive_type(&r#type.to_string()) {
+        Some(Ty::SingleFloat) if value.abs() > f32::MAX as f64 => Err(Error::OutOfRange {
+            literal: value.to_string(),
+            r#type: r#type.to_string(),
+        }),
+        Some(Ty::Integer(..) | Ty::Boolean | Ty::Unit) => Err(Error::Mismatch {
+            literal: value.to_string(),
+            r#type: r#type.to_string(),
+        }),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::lexer::lex;
+    use chumsky::Parser;
+
+    fn check(body: &str) -> Result<()> {
+        let source = format!(
+            "module body Test is
+                function f(x: Int8): Int8 is
+                    return x;
+                end;
+
+                function main(): ExitCode is
+                    {body}
+                    return ExitSuccess();
+                end;
+            end module body."
+        );
+        let tokens = lex(&source)
+            .map(|(token, _)| token.unwrap())
+            .collect::<Vec<_>>();
+        let module = ModuleDef::parser().parse(&tokens).into_result().unwrap();
+
+        check_module_def(&module)
+    }
+
+    fn out_of_range(literal: &str, r#type: &str) -> Result<()> {
+        Err(Error::OutOfRange {
+            literal: literal.to_string(),
+            r#type: r#type.to_string(),
+        })
+    }
+
+    #[test]
+    fn integer_ranges() {
+        assert_eq!(check("let a: Int8 := 127;"), Ok(()));
+        assert_eq!(check("let a: Int8 := -128;"), Ok(()));
+        assert_eq!(check("let a: Nat64 := 18446744073709551615;"), Ok(()));
+        assert_eq!(check("let a: Int8 := 300;"), out_of_range("300", "Int8"));
+        assert_eq!(check("let a: Int8 := -129;"), out_of_range("-129", "Int8"));
+        assert_eq!(check("let a: Nat8 := -1;"), out_of_range("-1", "Nat8"));
+        assert_eq!(check("let a: Index := -1;"), out_of_range("-1", "Index"));
+    }
+
+    #[test]
+    fn inferred_types() {
+        assert_eq!(
+            check("let a: Nat8 := 1; let b: Bool := a < 256;"),
+            out_of_range("256", "Nat8")
+        );
+        assert_eq!(
+            check("let a: Nat16 := 1; a := a + 70000;"),
+            out_of_range("70000", "Nat16")
+        );
+        assert_eq!(check("f(200);"), out_of_range("200", "Int8"));
+        assert_eq!(check("f(x => 200);"), out_of_range("200", "Int8"));
+        assert_eq!(
+            check("let a: Int32 := (300 : Int8) + 1;"),
+            out_of_range("300", "Int8")
+        );
+    }
+
+    #[test]
+    fn float_literals() {
+        assert_eq!(check("let a: Float64 := 3.14;"), Ok(()));
+        assert_eq!(check("let a: Float32 := -1.5;"), Ok(()));
+        assert_eq!(
+            check("let a: Int32 := 3.14;"),
+            Err(Error::Mismatch {
+                literal: "3.14".to_string(),
+                r#type: "Int32".to_string(),
+            })
+        );
+        assert_eq!(
+            check("let a: Float64 := 3;"),
+            Err(Error::Mismatch {
+                literal: "3".to_string(),
+                r#type: "Float64".to_string(),
+            })
+        );
+    }
+}
@@ -0,0 +1,382 @@
+use crate::{
+    ast::{AtomicExpr, Expression, FnCallArgs, FunctionDef, ModuleDef, ModuleDefItem, Pragma},
+    error::{PragmaError as Error, PragmaResult as Result},
+};
+use std::fmt::{self, Display, Formatter};
+
+pub const FOREIGN_IMPORT: &str = "Foreign_Import";
+pub const FOREIGN_EXPORT: &str = "Foreign_Export";
+pub const UNSAFE_MODULE: &str = "Unsafe_Module";
+
+const EXTERNAL_NAME: &str = "External_Name";
+
+/// The declarations a pragma can be attached to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Placement {
+    Module,
+    Function,
+    Constant,
+    Record,
+    Union,
+    Type,
+    TypeClass,
+    Instance,
+}
+
+impl Display for Placement {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Module => "module",
+            Self::Function => "function",
+            Self::Constant => "constant",
+            Self::Record => "record",
+            Self::Union => "union",
+            Self::Type => "type declaration",
+            Self::TypeClass => "typeclass",
+            Self::Instance => "instance",
+        })
+    }
+}
+
+/// A pragma which has been validated against the registry.
+#[derive(Clone, Debug, PartialEq)]
+pub enum KnownPragma {
+    ForeignImport { external_name: String },
+    ForeignExport { external_name: String },
+    UnsafeModule,
+}
+
+struct PragmaSpec {
+    name: &'static str,
+    placement: Placement,
+    arguments: &'static [&'static str],
+}
+
+/// Every pragma the compiler understands, where it can be used and which (named, string literal)
+/// arguments it requires.
+const REGISTRY: &[PragmaSpec] = &[
+    PragmaSpec {
+        name: FOREIGN_IMPORT,
+        placement: Placement::Function,
+        arguments: &[EXTERNAL_NAME],
+    },
+    PragmaSpec {
+        name: FOREIGN_EXPORT,
+        placement: Placement::Function,
+        arguments: &[EXTERNAL_NAME],
+    },
+    PragmaSpec {
+        name: UNSAFE_MODULE,
+        placement: Placement::Module,
+        arguments: &[],
+    },
+];
+
+/// Check every pragma in the module against the registry.
+pub fn check_module_def(module: &ModuleDef) -> Result<()> {
+    validate_pragmas(&module.pragmas, Placement::Module)?;
+
+    for item in &module.contents {
+        match item {
+            ModuleDefItem::Constant(def) => {
+                validate_pragmas(&def.pragmas, Placement::Constant)?;
+            }
+            ModuleDefItem::Function(def) => check_function(def)?,
+            ModuleDefItem::Instance(def) => {
+                validate_pragmas(&def.pragmas, Placement::Instance)?;
+            }
+            ModuleDefItem::Record(decl) => {
+                validate_pragmas(&decl.pragmas, Placement::Record)?;
+            }
+            ModuleDefItem::Type(decl) => {
+                validate_pragmas(&decl.pragmas, Placement::Type)?;
+            }
+            ModuleDefItem::TypeClass(def) => {
+                validate_pragmas(&def.pragmas, Placement::TypeClass)?;
+            }
+            ModuleDefItem::Union(decl) => {
+                validate_pragmas(&decl.pragmas, Placement::Union)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Validate a single pragma, returning its interpretation.
+pub fn validate_pragma(pragma: &Pragma, placement: Placement) -> Result<KnownPragma> {
+    let name = pragma.name.name.as_str();
+    let spec = REGISTRY
+        .iter()
+        .find(|spec| spec.name == name)
+        .ok_or_else(|| Error::UnknownPragma(name.to_string()))?;
+
+    if spec.placement != placement {
+        return Err(Error::MisplacedPragma {
+            name: name.to_string(),
+            placement,
+        });
+    }
+
+    let mut arguments = match &pragma.args {
+        FnCallArgs::Empty => Vec::new(),
+        FnCallArgs::Positional(_) => return Err(Error::PositionalArguments(name.to_string())),
+        FnCallArgs::Named(args) => args.iter().collect(),
+    };
+    arguments.sort_by(|(lhs, _), (rhs, _)| lhs.name.cmp(&rhs.name));
+
+    if let Some((argument, _)) = arguments
+        .iter()
+        .find(|(argument, _)| !spec.arguments.contains(&argument.name.as_str()))
+    {
+        return Err(Error::UnexpectedArgument {
+            pragma: name.to_string(),
+            argument: argument.name.clone(),
+        });
+    }
+
+    let string_argument = |argument: &str| {
+        let (_, value) = arguments
+            .iter()
+            .find(|(x, _)| x.name == argument)
+            .ok_or_else(|| Error::MissingArgument {
+                pragma: name.to_string(),
+                argument: argument.to_string(),
+            })?;
+
+        match value {
+            Expression::Atomic(AtomicExpr::ConstStr(value)) => Ok(value.clone()),
+            _ => Err(Error::InvalidArgument {
+                pragma: name.to_string(),
+                argument: argument.to_string(),
+            }),
+        }
+    };
+
+    Ok(match spec.name {
+        FOREIGN_IMPORT => KnownPragma::ForeignImport {
+            external_name: string_argument(EXTERNAL_NAME)?,
+        },
+        FOREIGN_EXPORT => KnownPragma::ForeignExport {
+            external_name: string_argument(EXTERNAL_NAME)?,
+        },
+        UNSAFE_MODULE => KnownPragma::UnsafeModule,
+        _ => unreachable!("pragma `{name}` is registered but not interpreted"),
+    })
+}
+
+/// Return the external name of a function marked with `pragma Foreign_Import`, if any.
+pub fn foreign_import(pragmas: &[Pragma]) -> Option<String> {
+    pragmas.iter().find_map(
+        |pragma| match validate_pragma(pragma, Placement::Function) {
+            Ok(KnownPragma::ForeignImport { external_name }) => Some(external_name),
+            _ => None,
+        },
+    )
+}
+
+/// Return the external name of a function marked with `pragma Foreign_Export`, if any.
+pub fn foreign_export(pragmas: &[Pragma]) -> Option<String> {
+    pragmas.iter().find_map(
+        |pragma| match validate_pragma(pragma, Placement::Function) {
+            Ok(KnownPragma::ForeignExport { external_name }) => Some(external_name),
+            _ => None,
+        },
+    )
+}
+
+fn validate_pragmas(pragmas: &[Pragma], placement: Placement) -> Result<Vec<KnownPragma>> {
+    let mut known = Vec::with_capacity(pragmas.len());
+    for pragma in pragmas {
+        let pragma_value = validate_pragma(pragma, placement)?;
+        if known
+            .iter()
+            .any(|x| std::mem::discriminant(x) == std::mem::discriminant(&pragma_value))
+        {
+            return Err(Error::DuplicatePragma(pragma.name.name.clone()));
+        }
+        known.push(pragma_value);
+    }
+
+    Ok(known)
+}
+
+fn check_function(def: &FunctionDef) -> Result<()> {
+    let pragmas = validate_pragmas(&def.pragmas, Placement::Function)?;
+    let is_import = pragmas
+        .iter()
+        .any(|x| matches!(x, KnownPragma::ForeignImport { .. }));
+    let is_export = pragmas
+        .iter()
+        .any(|x| matches!(x, KnownPragma::ForeignExport { .. }));
+
+    if is_import && is_export {
+        return Err(Error::ConflictingPragmas(def.name.name.clone()));
+    }
+    if (is_import || is_export) && !def.type_params.is_empty() {
+        return Err(Error::GenericForeignFunction(def.name.name.clone()));
+    }
+    if is_import && !def.body.is_empty() {
+        return Err(Error::ForeignImportBody(def.name.name.clone()));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::lexer::lex;
+    use chumsky::Parser;
+
+    fn check(source: &str) -> Result<()> {
+        let tokens = lex(source)
+            .map(|(token, _)| token.unwrap())
+            .collect::<Vec<_>>();
+        let module = ModuleDef::parser().parse(&tokens).into_result().unwrap();
+
+        check_module_def(&module)
+    }
+
+    fn function_with(pragmas: &str, body: &str) -> String {
+        format!(
+            "pragma Unsafe_Module;
+
+            module body Test is
+                {pragmas}
+                function f(x: Nat8): Int32 is
+                    {body}
+                end;
+            end module body."
+        )
+    }
+
+    #[test]
+    fn known_pragmas() {
+        let source = include_str!("../../../programs/suites/001-trivial/002-embed/Test.aum");
+        assert_eq!(check(source), Ok(()));
+
+        let source = function_with(
+            r#"pragma Foreign_Export(External_Name => "austral_f");"#,
+            "return 0;",
+        );
+        assert_eq!(check(&source), Ok(()));
+    }
+
+    #[test]
+    fn unknown_pragma() {
+        let source = function_with("pragma Inline;", "return 0;");
+        assert_eq!(
+            check(&source),
+            Err(Error::UnknownPragma("Inline".to_string()))
+        );
+    }
+
+    #[test]
+    fn misplaced_pragmas() {
+        let source = function_with("pragma Unsafe_Module;", "return 0;");
+        assert_eq!(
+            check(&source),
+            Err(Error::MisplacedPragma {
+                name: "Unsafe_Module".to_string(),
+                placement: Placement::Function,
+            })
+        );
+
+        let result = check(
+            r#"pragma Foreign_Import(External_Name => "putchar");
+
+            module body Test is
+            end module body."#,
+        );
+        assert_eq!(
+            result,
+            Err(Error::MisplacedPragma {
+                name: "Foreign_Import".to_string(),
+                placement: Placement::Module,
+            })
+        );
+    }
+
+    #[test]
+    fn pragma_arguments() {
+        let source = function_with("pragma Foreign_Import;", "");
+        assert_eq!(
+            check(&source),
+            Err(Error::MissingArgument {
+                pragma: "Foreign_Import".to_string(),
+                argument: "External_Name".to_string(),
+            })
+        );
+
+        let source = function_with(r#"pragma Foreign_Import("putchar");"#, "");
+        assert_eq!(
+            check(&source),
+            Err(Error::PositionalArguments("Foreign_Import".to_string()))
+        );
+
+        let source = function_with(r#"pragma Foreign_Import(Name => "putchar");"#, "");
+        assert_eq!(
+            check(&source),
+            Err(Error::UnexpectedArgument {
+                pragma: "Foreign_Import".to_string(),
+                argument: "Name".to_string(),
+            })
+        );
+
+        let source = function_with("pragma Foreign_Import(External_Name => 10);", "");
+        assert_eq!(
+            check(&source),
+            Err(Error::InvalidArgument {
+                pragma: "Foreign_Import".to_string(),
+                argument: "External_Name".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn foreign_functions() {
+        let source = function_with(
+            r#"pragma Foreign_Import(External_Name => "putchar");"#,
+            "return 0;",
+        );
+        assert_eq!(
+            check(&source),
+            Err(Error::ForeignImportBody("f".to_string()))
+        );
+
+        let source = function_with(
+            r#"pragma Foreign_Import(External_Name => "putchar");
+            pragma Foreign_Export(External_Name => "austral_f");"#,
+            "",
+        );
+        assert_eq!(
+            check(&source),
+            Err(Error::ConflictingPragmas("f".to_string()))
+        );
+
+        let source = function_with(
+            r#"pragma Foreign_Export(External_Name => "a");
+            pragma Foreign_Export(External_Name => "b");"#,
+            "return 0;",
+        );
+        assert_eq!(
+            check(&source),
+            Err(Error::DuplicatePragma("Foreign_Export".to_string()))
+        );
+    }
+
+    #[test]
+    fn external_names() {
+        let tokens = lex(r#"pragma Foreign_Import(External_Name => "putchar");"#)
+            .map(|(token, _)| token.unwrap())
+            .collect::<Vec<_>>();
+        let pragma = Pragma::parser().parse(&tokens).into_result().unwrap();
+
+        assert_eq!(
+            foreign_import(std::slice::from_ref(&pragma)),
+            Some("putchar".to_string())
+        );
+        assert_eq!(foreign_export(std::slice::from_ref(&pragma)), None);
+    }
+}
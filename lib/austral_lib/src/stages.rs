@@ -1,5 +1,8 @@
 use crate::{
-    common::{ComparisonOperator, DeclId, Identifier, Mutability, QIdent},
+    common::{
+        ArithmeticOperator, BorrowingMode, ComparisonOperator, DeclId, Identifier, Mutability,
+        QIdent,
+    },
     escape::EscapedString,
     r#type::{Ty, ValueParameter},
     span::Span,
@@ -44,7 +47,8 @@ pub enum TExpr {
     TLocalVar(Identifier, Ty),
     //TFunVar(DeclId, Ty, TypeBindings),
     TTemporary(Identifier, Ty),
-    //TFuncall(DeclId, QIdent, Vec<Box<TExpr>>, Ty, TypeBindings),
+    // TODO: add the TypeBindings once generic functions are supported.
+    TFuncall(DeclId, QIdent, Vec<Box<TExpr>>, Ty),
     //TMethodCall(InsMethId, QIdent, TyParams, Vec<Box<TExpr>>, Ty, TypeBindings),
     TVarMethodCall {
         source_module_name: ModuleName,
@@ -58,6 +62,7 @@ pub enum TExpr {
     },
     TFptrCall(Identifier, Vec<Box<TExpr>>, Ty),
     TCast(Box<TExpr>, Ty),
+    TArithmetic(ArithmeticOperator, Box<TExpr>, Box<TExpr>),
     TComparison(ComparisonOperator, Box<TExpr>, Box<TExpr>),
     TConjunction(Box<TExpr>, Box<TExpr>),
     TDisjunction(Box<TExpr>, Box<TExpr>),
@@ -70,6 +75,7 @@ pub enum TExpr {
     TArrayIndex(Box<TExpr>, Box<TExpr>, Ty),
     TSpanIndex(Box<TExpr>, Box<TExpr>, Ty),
     TEmbed(Ty, String, Vec<Box<TExpr>>),
+    TBorrowExpr(BorrowingMode, Identifier),
     TDeref(Box<TExpr>),
     TSizeOf(Ty),
 }
@@ -85,7 +91,7 @@ pub enum CaseRef {
     CaseRefValue,
 }
 
-pub struct TypedWhen(Identifier, Vec<TypedBinding>, Box<TStmt>);
+pub struct TypedWhen(pub Identifier, pub Vec<TypedBinding>, pub Box<TStmt>);
 
 pub enum BorrowStmtKind {
     Read,
@@ -1,6 +1,7 @@
-use crate::common::Identifier;
+use crate::common::{Identifier, QIdent};
+use std::fmt::{self, Display, Formatter};
 
-#[derive(PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum Universe {
     FreeUniverse,
     LinearUniverse,
@@ -8,13 +9,36 @@ pub enum Universe {
     RegionUniverse,
 }
 
+impl From<crate::lexer::Universe> for Universe {
+    fn from(value: crate::lexer::Universe) -> Self {
+        match value {
+            crate::lexer::Universe::Free => Self::FreeUniverse,
+            crate::lexer::Universe::Linear => Self::LinearUniverse,
+            crate::lexer::Universe::Type => Self::TypeUniverse,
+            crate::lexer::Universe::Region => Self::RegionUniverse,
+        }
+    }
+}
+
+impl Display for Universe {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::FreeUniverse => "Free",
+            Self::LinearUniverse => "Linear",
+            Self::TypeUniverse => "Type",
+            Self::RegionUniverse => "Region",
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
 pub enum Ty {
     Unit,
     Boolean,
     Integer(Signedness, IntegerWidth),
     SingleFloat,
     DoubleFloat,
-    // TODO NamedType(QIdent, Vec<Ty>, Universe),
+    NamedType(QIdent, Vec<Ty>, Universe),
     // TODO RegionTy(Region),
     ReadRef(Box<Ty>, Box<Ty>),
     WriteRef(Box<Ty>, Box<Ty>),
@@ -27,11 +51,13 @@ pub enum Ty {
     // TODO MonoTy(MonoId),
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum Signedness {
     Unsigned,
     Signed,
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum IntegerWidth {
     Width8,
     Width16,
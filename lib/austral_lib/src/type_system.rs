@@ -1,4 +1,4 @@
-use crate::r#type::{Ty, Universe};
+use crate::r#type::{IntegerWidth, Signedness, Ty, Universe};
 
 pub fn type_universe(ty: &Ty) -> Universe {
     match ty {
@@ -7,7 +7,7 @@ pub fn type_universe(ty: &Ty) -> Universe {
         Ty::Integer(_, _) => Universe::FreeUniverse,
         Ty::SingleFloat => Universe::FreeUniverse,
         Ty::DoubleFloat => Universe::FreeUniverse,
-        //Ty::NamedType(_, _, u) => u,
+        Ty::NamedType(_, _, u) => *u,
         //Ty::RegionTy(_) => Universe::RegionUniverse,
         Ty::ReadRef(_, _) => Universe::FreeUniverse,
         Ty::WriteRef(_, _) => Universe::LinearUniverse,
@@ -28,7 +28,7 @@ pub fn is_numeric(ty: &Ty) -> bool {
         Ty::Integer(_, _) => true,
         Ty::SingleFloat => true,
         Ty::DoubleFloat => true,
-        //Ty::NamedType(_, _, _) => false,
+        Ty::NamedType(_, _, _) => false,
         //Ty::RegionTy(_) => false,
         Ty::ReadRef(_, _) => false,
         Ty::WriteRef(_, _) => false,
@@ -49,7 +49,7 @@ pub fn is_integer(ty: &Ty) -> bool {
         Ty::Integer(_, _) => true,
         Ty::SingleFloat => false,
         Ty::DoubleFloat => false,
-        //Ty::NamedType(_, _, _) => false,
+        Ty::NamedType(_, _, _) => false,
         //Ty::RegionTy(_) => false,
         Ty::ReadRef(_, _) => false,
         Ty::WriteRef(_, _) => false,
@@ -62,3 +62,44 @@ pub fn is_integer(ty: &Ty) -> bool {
         //Ty::MonoTy(_) => unreachable!("You shouldn't be asking for the is_integer of a MonoTy"),
     }
 }
+
+/// Return the type of a primitive type name, if it is one.
+pub fn primitive_type(name: &str) -> Option<Ty> {
+    Some(match name {
+        "Unit" => Ty::Unit,
+        "Bool" => Ty::Boolean,
+        "Nat8" => Ty::Integer(Signedness::Unsigned, IntegerWidth::Width8),
+        "Nat16" => Ty::Integer(Signedness::Unsigned, IntegerWidth::Width16),
+        "Nat32" => Ty::Integer(Signedness::Unsigned, IntegerWidth::Width32),
+        "Nat64" => Ty::Integer(Signedness::Unsigned, IntegerWidth::Width64),
+        "Int8" => Ty::Integer(Signedness::Signed, IntegerWidth::Width8),
+        "Int16" => Ty::Integer(Signedness::Signed, IntegerWidth::Width16),
+        "Int32" => Ty::Integer(Signedness::Signed, IntegerWidth::Width32),
+        "Int64" => Ty::Integer(Signedness::Signed, IntegerWidth::Width64),
+        "Index" => Ty::Integer(Signedness::Unsigned, IntegerWidth::WidthIndex),
+        "ByteSize" => Ty::Integer(Signedness::Unsigned, IntegerWidth::WidthByteSize),
+        "Float32" => Ty::SingleFloat,
+        "Float64" => Ty::DoubleFloat,
+        _ => return None,
+    })
+}
+
+/// Return the number of bits of an integer type. `Index` and `ByteSize` are 64 bits wide on every
+/// supported target.
+pub fn integer_bits(width: IntegerWidth) -> u32 {
+    match width {
+        IntegerWidth::Width8 => 8,
+        IntegerWidth::Width16 => 16,
+        IntegerWidth::Width32 => 32,
+        IntegerWidth::Width64 | IntegerWidth::WidthByteSize | IntegerWidth::WidthIndex => 64,
+    }
+}
+
+/// Return the inclusive range of values an integer type can represent.
+pub fn integer_bounds(signedness: Signedness, width: IntegerWidth) -> (i128, i128) {
+    let bits = integer_bits(width);
+    match signedness {
+        Signedness::Unsigned => (0, (1 << bits) - 1),
+        Signedness::Signed => (-(1 << (bits - 1)), (1 << (bits - 1)) - 1),
+    }
+}
@@ -0,0 +1,742 @@
+use crate::{
+    ast::{
+        Ident, ImportStmt, MethodDecl, ModuleDecl, ModuleDeclItem, ModuleDef, ModuleDefItem, Param,
+        RecordDecl, TypeDecl, TypeParam, TypeSpec, UnionDecl,
+    },
+    error::{UniverseError as Error, UniverseResult as Result},
+    r#type::Universe,
+};
+use std::collections::{HashMap, HashSet};
+
+/// Primitive types, all of which live in the free universe.
+const PRIMITIVE_TYPES: &[&str] = &[
+    "Unit", "Bool", "Nat8", "Nat16", "Nat32", "Nat64", "Int8", "Int16", "Int32", "Int64", "Index",
+    "ByteSize", "Float32", "Float64", "ExitCode",
+];
+
+/// Regions that are always in scope.
+const BUILTIN_REGIONS: &[&str] = &["Static"];
+
+/// Typeclasses implemented by every primitive type.
+const PRIMITIVE_TYPECLASSES: &[&str] = &["Printable"];
+
+/// A type parameter as seen by the checker.
+#[derive(Clone, Debug)]
+struct ParamInfo {
+    name: String,
+    universe: Universe,
+    constraints: Vec<String>,
+}
+
+impl From<&TypeParam> for ParamInfo {
+    fn from(value: &TypeParam) -> Self {
+        Self {
+            name: value.name.name.clone(),
+            universe: value.universe.0.into(),
+            constraints: value.params.iter().map(|x| x.name.clone()).collect(),
+        }
+    }
+}
+
+/// A type constructor: its declared universe and its type parameters.
+#[derive(Clone, Debug)]
+struct TypeInfo {
+    universe: Universe,
+    params: Vec<ParamInfo>,
+}
+
+/// The type parameters in scope while checking a declaration.
+type Scope = HashMap<String, ParamInfo>;
+
+/// A module item, abstracting over interface and body declarations.
+enum Item<'a> {
+    Record(&'a RecordDecl),
+    Union(&'a UnionDecl),
+    Type(&'a TypeDecl),
+    Constant(&'a TypeSpec),
+    Function {
+        type_params: &'a [TypeParam],
+        params: &'a [Param],
+        ret_type: &'a TypeSpec,
+    },
+    TypeClass {
+        type_param: &'a TypeParam,
+        methods: Vec<&'a MethodDecl>,
+    },
+    Instance {
+        type_params: &'a [TypeParam],
+        name: &'a Ident,
+        arg: &'a TypeSpec,
+        methods: Vec<&'a MethodDecl>,
+    },
+}
+
+/// Check the universes of every declaration in a module body, looking up imported types in
+/// `interfaces`.
+pub fn check_module_def(module: &ModuleDef, interfaces: &[ModuleDecl]) -> Result<()> {
+    check_items(
+        interfaces,
+        &module.imports,
+        module.contents.iter().map(|item| match item {
+            ModuleDefItem::Constant(x) => Item::Constant(&x.r#type),
+            ModuleDefItem::Function(x) => Item::Function {
+                type_params: &x.type_params,
+                params: &x.params,
+                ret_type: &x.ret_type,
+            },
+            ModuleDefItem::Instance(x) => Item::Instance {
+                type_params: &x.type_params,
+                name: &x.name,
+                arg: &x.arg,
+                methods: x.methods.iter().map(|x| &x.decl).collect(),
+            },
+            ModuleDefItem::Record(x) => Item::Record(x),
+            ModuleDefItem::Type(x) => Item::Type(x),
+            ModuleDefItem::TypeClass(x) => Item::TypeClass {
+                type_param: &x.type_param,
+                methods: x.methods.iter().map(|x| &x.decl).collect(),
+            },
+            ModuleDefItem::Union(x) => Item::Union(x),
+        }),
+    )
+}
+
+/// Check the universes of every declaration in a module interface, looking up imported types in
+/// `interfaces`.
+pub fn check_module_decl(module: &ModuleDecl, interfaces: &[ModuleDecl]) -> Result<()> {
+    check_items(
+        interfaces,
+        &module.imports,
+        module.contents.iter().map(|item| match item {
+            ModuleDeclItem::Constant(x) => Item::Constant(&x.r#type),
+            ModuleDeclItem::Function(x) => Item::Function {
+                type_params: &x.type_params,
+                params: &x.params,
+                ret_type: &x.ret_type,
+            },
+            ModuleDeclItem::Instance(x) => Item::Instance {
+                type_params: &x.type_params,
+                name: &x.name,
+                arg: &x.arg,
+                methods: x.methods.iter().collect(),
+            },
+            ModuleDeclItem::Record(x) => Item::Record(x),
+            ModuleDeclItem::Type(x) => Item::Type(x),
+            ModuleDeclItem::TypeClass(x) => Item::TypeClass {
+                type_param: &x.type_param,
+                methods: x.methods.iter().collect(),
+            },
+            ModuleDeclItem::Union(x) => Item::Union(x),
+        }),
+    )
+}
+
+fn check_items<'a>(
+    interfaces: &[ModuleDecl],
+    imports: &[ImportStmt],
+    items: impl Iterator<Item = Item<'a>>,
+) -> Result<()> {
+    let items = items.collect::<Vec<_>>();
+    let env = Env::new(interfaces, imports, &items);
+
+    for item in &items {
+        match item {
+            Item::Record(decl) => {
+                let scope = make_scope(&decl.type_params);
+                let universe = env.declared_universe(&decl.name)?;
+                for slot in &decl.slots {
+                    let slot_universe = env.value_universe(&scope, &slot.r#type)?;
+                    check_slot(&decl.name, &slot.name, universe, slot_universe)?;
+                }
+            }
+            Item::Union(decl) => {
+                let scope = make_scope(&decl.type_params);
+                let universe = env.declared_universe(&decl.name)?;
+                for slot in decl.cases.iter().flat_map(|case| &case.fields) {
+                    let slot_universe = env.value_universe(&scope, &slot.r#type)?;
+                    check_slot(&decl.name, &slot.name, universe, slot_universe)?;
+                }
+            }
+            Item::Type(decl) => {
+                env.declared_universe(&decl.name)?;
+            }
+            Item::Constant(r#type) => {
+                env.value_universe(&Scope::new(), r#type)?;
+            }
+            Item::Function {
+                type_params,
+                params,
+                ret_type,
+            } => {
+                let scope = make_scope(type_params);
+                env.check_signature(&scope, params, ret_type)?;
+            }
+            Item::TypeClass {
+                type_param,
+                methods,
+            } => {
+                let scope = make_scope(std::slice::from_ref(*type_param));
+                env.check_methods(&scope, methods)?;
+            }
+            Item::Instance {
+                type_params,
+                name: _,
+                arg,
+                methods,
+            } => {
+                let scope = make_scope(type_params);
+                env.value_universe(&scope, arg)?;
+                env.check_methods(&scope, methods)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn make_scope(type_params: &[TypeParam]) -> Scope {
+    type_params
+        .iter()
+        .map(|param| (param.name.name.clone(), ParamInfo::from(param)))
+        .collect()
+}
+
+fn check_slot(
+    decl: &Ident,
+    slot: &Ident,
+    universe: Universe,
+    slot_universe: Universe,
+) -> Result<()> {
+    if universe == Universe::FreeUniverse && slot_universe != Universe::FreeUniverse {
+        return Err(Error::NonFreeSlot {
+            decl: decl.name.clone(),
+            slot: slot.name.clone(),
+            universe: slot_universe,
+        });
+    }
+
+    Ok(())
+}
+
+/// Find the declaration of the type `name` in a module interface.
+fn imported_type(interface: &ModuleDecl, name: &Ident) -> Option<TypeInfo> {
+    interface.contents.iter().find_map(|item| {
+        let (universe, type_params) = match item {
+            ModuleDeclItem::Record(x) if x.name == *name => (&x.universe, x.type_params.as_slice()),
+            ModuleDeclItem::Union(x) if x.name == *name => (&x.universe, x.type_params.as_slice()),
+            ModuleDeclItem::Type(x) if x.name == *name => (&x.universe, [].as_slice()),
+            _ => return None,
+        };
+
+        Some(TypeInfo {
+            universe: universe.0.into(),
+            params: type_params.iter().map(ParamInfo::from).collect(),
+        })
+    })
+}
+
+struct Env {
+    types: HashMap<String, TypeInfo>,
+    /// Pairs of (typeclass, type constructor) with a known instance.
+    instances: HashSet<(String, String)>,
+    /// Names brought into scope by an import whose interface isn't available. They're declared,
+    /// and checked, in the interface of another module, so only their arguments are checked here.
+    imported: HashSet<String>,
+}
+
+impl Env {
+    fn new(interfaces: &[ModuleDecl], imports: &[ImportStmt], items: &[Item]) -> Self {
+        let mut types = HashMap::new();
+        let mut instances = HashSet::new();
+
+        for name in PRIMITIVE_TYPES {
+            types.insert(
+                name.to_string(),
+                TypeInfo {
+                    universe: Universe::FreeUniverse,
+                    params: Vec::new(),
+                },
+            );
+            for typeclass in PRIMITIVE_TYPECLASSES {
+                instances.insert((typeclass.to_string(), name.to_string()));
+            }
+        }
+        types.insert(
+            "RootCapability".to_string(),
+            TypeInfo {
+                universe: Universe::LinearUniverse,
+                params: Vec::new(),
+            },
+        );
+
+        let type_param = |name: &str| ParamInfo {
+            name: name.to_string(),
+            universe: Universe::TypeUniverse,
+            constraints: Vec::new(),
+        };
+        for (name, universe, params) in [
+            ("Option", Universe::TypeUniverse, vec![type_param("T")]),
+            (
+                "Either",
+                Universe::TypeUniverse,
+                vec![type_param("L"), type_param("R")],
+            ),
+            ("Pointer", Universe::FreeUniverse, vec![type_param("T")]),
+            ("Address", Universe::FreeUniverse, vec![type_param("T")]),
+        ] {
+            types.insert(name.to_string(), TypeInfo { universe, params });
+        }
+
+        for item in items {
+            let (name, universe, type_params) = match item {
+                Item::Record(x) => (&x.name, &x.universe, x.type_params.as_slice()),
+                Item::Union(x) => (&x.name, &x.universe, x.type_params.as_slice()),
+                Item::Type(x) => (&x.name, &x.universe, [].as_slice()),
+                Item::Instance { name, arg, .. } => {
+                    if let Some(head) = type_head(arg) {
+                        instances.insert((name.name.clone(), head.name.clone()));
+                    }
+                    continue;
+                }
+                _ => continue,
+            };
+
+            types.insert(
+                name.name.clone(),
+                TypeInfo {
+                    universe: universe.0.into(),
+                    params: type_params.iter().map(ParamInfo::from).collect(),
+                },
+            );
+        }
+
+        let mut imported = HashSet::new();
+        for import in imports {
+            let module_name = import
+                .module
+                .iter()
+                .map(|x| x.name.as_str())
+                .collect::<Vec<_>>()
+                .join(".");
+            let interface = interfaces.iter().find(|x| x.name.name == module_name);
+
+            for symbol in &import.symbols {
+                let name = symbol.rename_into.as_ref().unwrap_or(&symbol.import_name);
+                match interface.and_then(|x| imported_type(x, &symbol.import_name)) {
+                    Some(info) => {
+                        types.insert(name.name.clone(), info);
+                    }
+                    None => {
+                        imported.insert(name.name.clone());
+                    }
+                }
+            }
+        }
+
+        Self {
+            types,
+            instances,
+            imported,
+        }
+    }
+
+    /// Return the declared universe of a type, rejecting declarations in the region universe.
+    fn declared_universe(&self, name: &Ident) -> Result<Universe> {
+        let universe = self.types[&name.name].universe;
+        if universe == Universe::RegionUniverse {
+            return Err(Error::RegionDeclaration(name.name.clone()));
+        }
+
+        Ok(universe)
+    }
+
+    fn check_signature(&self, scope: &Scope, params: &[Param], ret_type: &TypeSpec) -> Result<()> {
+        for param in params {
+            self.value_universe(scope, &param.r#type)?;
+        }
+        self.value_universe(scope, ret_type)?;
+
+        Ok(())
+    }
+
+    fn check_methods(&self, scope: &Scope, methods: &[&MethodDecl]) -> Result<()> {
+        for method in methods {
+            let mut scope = scope.clone();
+            scope.extend(make_scope(&method.type_params));
+            self.check_signature(&scope, &method.params, &method.ret_ty)?;
+        }
+
+        Ok(())
+    }
+
+    /// Return the universe of a type used as a value (slot, parameter, return type...), which
+    /// can't be a region.
+    fn value_universe(&self, scope: &Scope, r#type: &TypeSpec) -> Result<Universe> {
+        match self.universe_of(scope, r#type)? {
+            Universe::RegionUniverse => Err(Error::MisplacedRegion(r#type.to_string())),
+            universe => Ok(universe),
+        }
+    }
+
+    fn universe_of(&self, scope: &Scope, r#type: &TypeSpec) -> Result<Universe> {
+        match r#type {
+            TypeSpec::Simple { name } => self.apply(scope, name, &[]),
+            TypeSpec::Generic { name, type_params } => self.apply(scope, name, type_params),
+            TypeSpec::BorrowRead { lhs, rhs } | TypeSpec::SpanRead { lhs, rhs } => {
+                self.check_reference(scope, lhs, rhs)?;
+                Ok(Universe::FreeUniverse)
+            }
+            TypeSpec::BorrowWrite { lhs, rhs } | TypeSpec::SpanWrite { lhs, rhs } => {
+                self.check_reference(scope, lhs, rhs)?;
+                Ok(Universe::LinearUniverse)
+            }
+        }
+    }
+
+    fn check_reference(&self, scope: &Scope, r#type: &TypeSpec, region: &TypeSpec) -> Result<()> {
+        self.value_universe(scope, r#type)?;
+        match self.universe_of(scope, region)? {
+            Universe::RegionUniverse => Ok(()),
+            _ => Err(Error::ExpectedRegion(region.to_string())),
+        }
+    }
+
+    /// Compute the universe of a type constructor applied to some arguments, checking that every
+    /// argument is valid for its type parameter.
+    fn apply(&self, scope: &Scope, name: &Ident, args: &[TypeSpec]) -> Result<Universe> {
+        let wrong_arity = |expected| Error::WrongArity {
+            name: name.name.clone(),
+            expected,
+            actual: args.len(),
+        };
+
+        if let Some(param) = scope.get(&name.name) {
+            return match args.is_empty() {
+                true => Ok(param.universe),
+                false => Err(wrong_arity(0)),
+            };
+        }
+        if BUILTIN_REGIONS.contains(&name.name.as_str()) {
+            return match args.is_empty() {
+                true => Ok(Universe::RegionUniverse),
+                false => Err(wrong_arity(0)),
+            };
+        }
+
+        let info = match self.types.get(&name.name) {
+            Some(info) => info,
+            None if self.imported.contains(&name.name) => {
+                for arg in args {
+                    self.universe_of(scope, arg)?;
+                }
+                return Ok(Universe::FreeUniverse);
+            }
+            None => return Err(Error::UnknownType(name.name.clone())),
+        };
+        if info.params.len() != args.len() {
+            return Err(wrong_arity(info.params.len()));
+        }
+
+        let mut has_linear_arg = false;
+        let mut has_type_arg = false;
+        for (param, arg) in info.params.iter().zip(args) {
+            let arg_universe = self.universe_of(scope, arg)?;
+            match (param.universe, arg_universe) {
+                (Universe::RegionUniverse, Universe::RegionUniverse) => continue,
+                (Universe::RegionUniverse, _) => {
+                    return Err(Error::ExpectedRegion(arg.to_string()));
+                }
+                (_, Universe::RegionUniverse) => {
+                    return Err(Error::MisplacedRegion(arg.to_string()));
+                }
+                (Universe::TypeUniverse, _) => {}
+                (expected, actual) if expected == actual => {}
+                (expected, actual) => {
+                    return Err(Error::UniverseMismatch {
+                        name: name.name.clone(),
+                        param: param.name.clone(),
+                        argument: arg.to_string(),
+                        expected,
+                        actual,
+                    });
+                }
+            }
+
+            for typeclass in &param.constraints {
+                if !self.implements(scope, typeclass, arg) {
+                    return Err(Error::MissingInstance {
+                        name: name.name.clone(),
+                        param: param.name.clone(),
+                        argument: arg.to_string(),
+                        typeclass: typeclass.clone(),
+                    });
+                }
+            }
+
+            has_linear_arg |= arg_universe == Universe::LinearUniverse;
+            has_type_arg |= arg_universe == Universe::TypeUniverse;
+        }
+
+        Ok(match info.universe {
+            // Types declared in the `Type` universe take the universe of their arguments.
+            Universe::TypeUniverse if has_linear_arg => Universe::LinearUniverse,
+            Universe::TypeUniverse if has_type_arg => Universe::TypeUniverse,
+            Universe::TypeUniverse => Universe::FreeUniverse,
+            universe => universe,
+        })
+    }
+
+    fn implements(&self, scope: &Scope, typeclass: &str, r#type: &TypeSpec) -> bool {
+        match type_head(r#type) {
+            Some(head) => match scope.get(&head.name) {
+                Some(param) => param.constraints.iter().any(|x| x == typeclass),
+                None => self
+                    .instances
+                    .contains(&(typeclass.to_string(), head.name.clone())),
+            },
+            None => false,
+        }
+    }
+}
+
+/// Return the name of the type constructor of a type, if any.
+fn type_head(r#type: &TypeSpec) -> Option<&Ident> {
+    match r#type {
+        TypeSpec::Simple { name } | TypeSpec::Generic { name, .. } => Some(name),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::lexer::lex;
+    use chumsky::Parser;
+
+    fn check(source: &str) -> Result<()> {
+        check_with_interfaces(source, &[])
+    }
+
+    fn check_with_interfaces(source: &str, interfaces: &[&str]) -> Result<()> {
+        let tokens = lex(source)
+            .map(|(token, _)| token.unwrap())
+            .collect::<Vec<_>>();
+        let module = ModuleDef::parser().parse(&tokens).into_result().unwrap();
+        let interfaces = interfaces
+            .iter()
+            .map(|source| {
+                let tokens = lex(source)
+                    .map(|(token, _)| token.unwrap())
+                    .collect::<Vec<_>>();
+                let module = ModuleDecl::parser().parse(&tokens).into_result().unwrap();
+                module
+            })
+            .collect::<Vec<_>>();
+
+        check_module_def(&module, &interfaces)
+    }
+
+    #[test]
+    fn named_records() {
+        let result = check(
+            "module body Test is
+                record PollMap: Linear is
+                    b: B;
+                end;
+
+                record C[X: Type]: Type is
+                    x: X;
+                end;
+
+                record B: Linear is
+                    xs: C[Nat32];
+                end;
+            end module body.",
+        );
+        assert_eq!(result, Ok(()));
+    }
+
+    #[test]
+    fn free_record_with_linear_slot() {
+        let result = check(
+            "module body Test is
+                record R: Free is
+                    root: RootCapability;
+                end;
+            end module body.",
+        );
+        assert_eq!(
+            result,
+            Err(Error::NonFreeSlot {
+                decl: "R".to_string(),
+                slot: "root".to_string(),
+                universe: Universe::LinearUniverse,
+            })
+        );
+    }
+
+    #[test]
+    fn free_record_with_type_parameter_slot() {
+        let result = check(
+            "module body Test is
+                record R[T: Type]: Free is
+                    value: T;
+                end;
+            end module body.",
+        );
+        assert!(matches!(result, Err(Error::NonFreeSlot { .. })));
+    }
+
+    #[test]
+    fn free_param_instantiated_with_linear_type() {
+        let result = check(
+            "module body Test is
+                record Box[T: Free]: Free is
+                    value: T;
+                end;
+
+                function f(b: Box[RootCapability]): Unit is
+                    return nil;
+                end;
+            end module body.",
+        );
+        assert!(matches!(result, Err(Error::UniverseMismatch { .. })));
+    }
+
+    #[test]
+    fn type_record_takes_universe_of_arguments() {
+        let result = check(
+            "module body Test is
+                record Box[T: Type]: Type is
+                    value: T;
+                end;
+
+                record Holder: Free is
+                    value: Box[RootCapability];
+                end;
+            end module body.",
+        );
+        assert!(matches!(result, Err(Error::NonFreeSlot { .. })));
+    }
+
+    #[test]
+    fn region_parameters() {
+        let valid = check(
+            "module body Test is
+                record View[R: Region]: Free is
+                    value: &[Int32, R];
+                end;
+            end module body.",
+        );
+        assert_eq!(valid, Ok(()));
+
+        let invalid = check(
+            "module body Test is
+                record View[R: Region]: Free is
+                    value: R;
+                end;
+            end module body.",
+        );
+        assert_eq!(invalid, Err(Error::MisplacedRegion("R".to_string())));
+
+        let not_a_region = check(
+            "module body Test is
+                function f(x: &[Int32, Int32]): Unit is
+                    return nil;
+                end;
+            end module body.",
+        );
+        assert_eq!(
+            not_a_region,
+            Err(Error::ExpectedRegion("Int32".to_string()))
+        );
+    }
+
+    #[test]
+    fn typeclass_constraints() {
+        let source = |arg| {
+            format!(
+                "module body Test is
+                    record Point: Free is
+                        x: Int32;
+                    end;
+
+                    record Box[T: Free(Printable)]: Free is
+                        value: T;
+                    end;
+
+                    function f(b: Box[{arg}]): Unit is
+                        return nil;
+                    end;
+                end module body."
+            )
+        };
+
+        assert_eq!(check(&source("Int32")), Ok(()));
+        assert!(matches!(
+            check(&source("Point")),
+            Err(Error::MissingInstance { .. })
+        ));
+    }
+
+    #[test]
+    fn imported_types() {
+        let result = check(
+            "import Austral.Memory (Pointer, Buffer as Storage);
+            module body Test is
+                record Handle: Free is
+                    buffer: Storage[Nat8];
+                    raw: Pointer[Nat8];
+                end;
+            end module body.",
+        );
+        assert_eq!(result, Ok(()));
+
+        let not_renamed = check(
+            "import Austral.Memory (Buffer as Storage);
+            module body Test is
+                record Handle: Free is
+                    buffer: Buffer[Nat8];
+                end;
+            end module body.",
+        );
+        assert_eq!(not_renamed, Err(Error::UnknownType("Buffer".to_string())));
+    }
+
+    #[test]
+    fn imported_linear_type() {
+        let interface = "module Lib is
+            record Token: Linear is
+                id: Int32;
+            end;
+        end module.";
+        let result = check_with_interfaces(
+            "import Lib (Token as Ticket);
+            module body Test is
+                record Handle: Free is
+                    ticket: Ticket;
+                end;
+            end module body.",
+            &[interface],
+        );
+        assert_eq!(
+            result,
+            Err(Error::NonFreeSlot {
+                decl: "Handle".to_string(),
+                slot: "ticket".to_string(),
+                universe: Universe::LinearUniverse,
+            })
+        );
+
+        let linear = check_with_interfaces(
+            "import Lib (Token);
+            module body Test is
+                record Handle: Linear is
+                    token: Token;
+                end;
+            end module body.",
+            &[interface],
+        );
+        assert_eq!(linear, Ok(()));
+    }
+}
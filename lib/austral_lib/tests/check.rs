@@ -0,0 +1,48 @@
+use austral_lib::{ast::ModuleDef, compiler::check, lexer::lex};
+use chumsky::Parser;
+use std::fs;
+
+fn parse(source: &str) -> ModuleDef {
+    let tokens = lex(source)
+        .map(|(token, _)| token.unwrap())
+        .collect::<Vec<_>>();
+    let result = ModuleDef::parser().parse(&tokens).into_result();
+    result.unwrap()
+}
+
+#[test]
+fn root_capability_must_be_consumed() {
+    let source =
+        fs::read_to_string("../../programs/suites/001-trivial/007-root-cap/Test.aum").unwrap();
+    assert!(check(&parse(&source), &[]).is_ok());
+
+    let source = source.replacen("surrenderRoot(root);", "", 1);
+    let error = check(&parse(&source), &[]).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "Function `main` must consume each of its linear values (such as the `RootCapability`) exactly once"
+    );
+}
+
+#[test]
+fn memory_api_requires_unsafe_module() {
+    let source = "
+        pragma Unsafe_Module;
+
+        module body Test is
+            function main(): ExitCode is
+                let p: Pointer[Int32] := nullPointer();
+                store(p, 1);
+                return ExitSuccess();
+            end;
+        end module body.
+    ";
+    assert!(check(&parse(source), &[]).is_ok());
+
+    let source = source.replacen("pragma Unsafe_Module;", "", 1);
+    let error = check(&parse(&source), &[]).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "Function `main` accesses memory through a pointer, which is only allowed in modules marked with `pragma Unsafe_Module`"
+    );
+}
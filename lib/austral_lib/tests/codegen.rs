@@ -0,0 +1,697 @@
+use austral_lib::{
+    ast::ModuleDef,
+    compiler::{compile_for_target, TargetOptions},
+    error::{CodegenError, CodegenResult},
+    lexer::lex,
+};
+use chumsky::Parser;
+use melior::{dialect::DialectRegistry, utility::register_all_llvm_translations, Context};
+use test_case::test_case;
+
+fn parse(source: &str) -> ModuleDef {
+    let tokens = lex(source)
+        .map(|(token, _)| token)
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    ModuleDef::parser().parse(&tokens).into_result().unwrap()
+}
+
+fn context() -> Context {
+    let context = Context::new();
+    context.append_dialect_registry(&{
+        let dialect_registry = DialectRegistry::new();
+        melior::utility::register_all_dialects(&dialect_registry);
+        dialect_registry
+    });
+    register_all_llvm_translations(&context);
+    context.load_all_available_dialects();
+    context
+}
+
+/// The MLIR of a module body, before it is lowered to the LLVM dialect.
+fn compile_mlir(source: &str) -> CodegenResult<String> {
+    compile_mlir_for_target(source, &TargetOptions::host())
+}
+
+/// The MLIR of a module body laid out for `target`.
+fn compile_mlir_for_target(source: &str, target: &TargetOptions) -> CodegenResult<String> {
+    let context = context();
+    let module = compile_for_target(&context, &parse(source), &[], target)?;
+    Ok(module.as_operation().to_string())
+}
+
+/// The text of the function `symbol` within the MLIR of a module.
+fn function<'a>(ir: &'a str, symbol: &str) -> &'a str {
+    let header = format!("@{symbol}(");
+    let start = ir
+        .match_indices("func.func")
+        .map(|(i, _)| i)
+        .find(|&i| ir[i..].lines().next().unwrap().contains(&header))
+        .unwrap();
+    let end = ir[start + 1..]
+        .find("func.func")
+        .map_or(ir.len(), |end| start + 1 + end);
+    &ir[start..end]
+}
+
+#[test]
+fn calls_between_functions() {
+    let source = "
+        module body Test is
+            function add(a: Int32, b: Int32): Int32 is
+                return a + b;
+            end;
+
+            function forever(n: Int32): Int32 is
+                return forever(n + 1);
+            end;
+
+            function main(): ExitCode is
+                let x: Int32 := add(b => 2, a => 7);
+                return ExitSuccess();
+            end;
+        end module body.
+    ";
+    let ir = compile_mlir(source).unwrap();
+    assert!(ir.contains("func.func @Test__add(%arg0: i32, %arg1: i32) -> i32"));
+    assert!(function(&ir, "Test__forever").contains("call @Test__forever("));
+
+    // Named arguments are evaluated in the order of the parameters.
+    let main = function(&ir, "main");
+    assert!(main.contains("call @Test__add("));
+    assert!(
+        main.find("arith.constant 7 : i32").unwrap() < main.find("arith.constant 2 : i32").unwrap()
+    );
+}
+
+#[test]
+fn control_flow() {
+    let source = "
+        module body Test is
+            function clamp(n: Index): Index is
+                for i from 0 to n do
+                    if i = 100 then
+                        return 100;
+                    end if;
+                end for;
+                while n > 1000 do
+                    return 1000;
+                end while;
+                return if n = 0 then 1 else n;
+            end;
+
+            function main(): ExitCode is
+                return ExitSuccess();
+            end;
+        end module body.
+    ";
+    let ir = compile_mlir(source).unwrap();
+    let clamp = function(&ir, "Test__clamp");
+    assert!(clamp.contains("scf.while"));
+    // The select expression yields its value, the statements guarded by an early return don't.
+    assert!(clamp.contains("scf.if"));
+    assert!(clamp.contains("-> (i64)"));
+}
+
+#[test]
+fn mutable_variables() {
+    let source = "
+        module body Test is
+            function main(): ExitCode is
+                var n: Int64 := 10;
+                n := 20;
+                return ExitSuccess();
+            end;
+        end module body.
+    ";
+    let ir = compile_mlir(source).unwrap();
+    let main = function(&ir, "main");
+    assert!(main.contains("memref.alloca() : memref<i64>"));
+    assert!(main.contains("arith.constant 20 : i64"));
+    assert!(main.contains("memref.store"));
+}
+
+#[test]
+fn signedness_selects_operations() {
+    let source = "
+        module body Test is
+            function unsigned(a: Nat32, b: Nat32): Bool is
+                return (a / b) < b;
+            end;
+
+            function signed(a: Int32, b: Int32): Bool is
+                return (a / b) < b;
+            end;
+
+            function widths(a: Nat8, b: Int16, c: Index, d: Float32, e: Float64, f: Pointer[Int32]): Unit is
+                return nil;
+            end;
+
+            function main(): ExitCode is
+                return ExitSuccess();
+            end;
+        end module body.
+    ";
+    let ir = compile_mlir(source).unwrap();
+    let unsigned = function(&ir, "Test__unsigned");
+    assert!(unsigned.contains("arith.divui"));
+    assert!(unsigned.contains("arith.cmpi ult"));
+
+    let signed = function(&ir, "Test__signed");
+    assert!(signed.contains("arith.divsi"));
+    assert!(signed.contains("arith.cmpi slt"));
+
+    assert!(ir.contains(
+        "@Test__widths(%arg0: i8, %arg1: i16, %arg2: i64, %arg3: f32, %arg4: f64, %arg5: !llvm.ptr) -> i1"
+    ));
+}
+
+#[test]
+fn records() {
+    let source = "
+        module body Test is
+            record Pair: Free is
+                a: Int32;
+                b: Int64;
+            end;
+
+            record Outer: Free is
+                pair: Pair;
+            end;
+
+            function second(o: Outer): Int64 is
+                return o.pair.b;
+            end;
+
+            function first(p: Pair): Int32 is
+                let { a: Int32, b as c: Int64 } := p;
+                return a;
+            end;
+
+            function main(): ExitCode is
+                var p: Pair := Pair(b => 2, a => 1);
+                p.a := 3;
+                return ExitSuccess();
+            end;
+        end module body.
+    ";
+    let ir = compile_mlir(source).unwrap();
+    assert!(ir.contains("@Test__second(%arg0: !llvm.struct<(struct<(i32, i64)>)>) -> i64"));
+    assert_eq!(
+        function(&ir, "Test__second")
+            .matches("llvm.extractvalue")
+            .count(),
+        2
+    );
+    assert!(function(&ir, "Test__first").contains("llvm.extractvalue"));
+
+    // Assigning to a slot stores the record with the slot replaced.
+    let main = function(&ir, "main");
+    assert!(main.contains("llvm.mlir.undef : !llvm.struct<(i32, i64)>"));
+    assert_eq!(main.matches("llvm.insertvalue").count(), 3);
+}
+
+#[test]
+fn unions() {
+    let source = "
+        module body Test is
+            union Shape: Free is
+                case Circle is
+                    radius: Int32;
+                case Square is
+                    side: Int64;
+            end;
+
+            union Color: Free is
+                case Red;
+                case Green;
+            end;
+
+            union Handle: Free is
+                case Closed;
+                case Open is
+                    file: Pointer[Int32];
+            end;
+
+            function length(s: Shape): Int64 is
+                case s of
+                    when Circle(radius: Int32) do
+                        return 0;
+                    when Square(side: Int64) do
+                        return side;
+                end case;
+                return 0;
+            end;
+
+            function red(c: Color): Bool is
+                return true;
+            end;
+
+            function open(h: Handle): Bool is
+                return true;
+            end;
+
+            function main(): ExitCode is
+                let s: Shape := Square(side => 2);
+                return ExitSuccess();
+            end;
+        end module body.
+    ";
+    let ir = compile_mlir(source).unwrap();
+    // The tag is followed by storage for the largest case.
+    assert!(ir.contains("@Test__length(%arg0: !llvm.struct<(i8, array<1 x i64>)>) -> i64"));
+    assert!(function(&ir, "Test__length").contains("scf.index_switch"));
+
+    // Unions without fields are just a tag, and null stands for the empty case.
+    assert!(ir.contains("@Test__red(%arg0: i8) -> i1"));
+    assert!(ir.contains("@Test__open(%arg0: !llvm.ptr) -> i1"));
+}
+
+#[test]
+fn checked_arithmetic() {
+    let source = "
+        module body Test is
+            function checked(a: Int32, b: Int32): Int32 is
+                return a + b;
+            end;
+
+            function modular(a: Int32, b: Int32): Int32 is
+                return modularAdd(a, b);
+            end;
+
+            function saturating(a: Nat32, b: Nat32): Nat32 is
+                return saturatingAdd(a, b);
+            end;
+
+            function main(): ExitCode is
+                return ExitSuccess();
+            end;
+        end module body.
+    ";
+    let ir = compile_mlir(source).unwrap();
+    let checked = function(&ir, "Test__checked");
+    assert!(checked.contains("llvm.intr.sadd.with.overflow"));
+    assert!(checked.contains("call @au_abort("));
+
+    let modular = function(&ir, "Test__modular");
+    assert!(modular.contains("arith.addi"));
+    assert!(!modular.contains("call @au_abort("));
+
+    assert!(function(&ir, "Test__saturating").contains("llvm.intr.uadd.sat"));
+}
+
+#[test]
+fn numeric_conversions() {
+    let source = "
+        module body Test is
+            function narrow(x: Int32): Option[Int8] is
+                return toInt8(x);
+            end;
+
+            function widen(x: Int32): Option[Float64] is
+                return toFloat64(x);
+            end;
+
+            function truncate(x: Float64): Option[Int32] is
+                return toInt32(x);
+            end;
+
+            function main(): ExitCode is
+                let x: Int16 := 300 : Int16;
+                return ExitSuccess();
+            end;
+        end module body.
+    ";
+    let ir = compile_mlir(source).unwrap();
+    let narrow = function(&ir, "Test__narrow");
+    assert!(narrow.contains("arith.cmpi sge"));
+    assert!(narrow.contains("arith.cmpi sle"));
+    assert!(narrow.contains("arith.trunci"));
+
+    assert!(function(&ir, "Test__widen").contains("arith.sitofp"));
+
+    let truncate = function(&ir, "Test__truncate");
+    assert!(truncate.contains("arith.fptosi"));
+    assert!(truncate.contains("arith.cmpf oge"));
+
+    assert!(function(&ir, "main").contains("arith.constant 300 : i16"));
+}
+
+#[test]
+fn untyped_literals() {
+    let source = "
+        module body Test is
+            function main(): ExitCode is
+                let f: Option[Float64] := toFloat64(5);
+                if 3 = 4 then
+                    return ExitFailure();
+                end if;
+                return ExitSuccess();
+            end;
+        end module body.
+    ";
+    let ir = compile_mlir(source).unwrap();
+    let main = function(&ir, "main");
+    assert!(main.contains("arith.constant 5 : i32"));
+    assert!(main.contains("arith.sitofp"));
+    assert!(main.contains("arith.constant 3 : i32"));
+    assert!(main.contains("arith.constant 4 : i32"));
+}
+
+#[test]
+fn pointers_and_spans() {
+    let source = "
+        pragma Unsafe_Module;
+
+        module body Test is
+            function third(p: Pointer[Int32]): Int32 is
+                store(p, 1);
+                return load(positiveOffset(p, 2));
+            end;
+
+            function at(s: Span[Int32, R], i: Index): Int32 is
+                return s[i];
+            end;
+
+            function main(): ExitCode is
+                return ExitSuccess();
+            end;
+        end module body.
+    ";
+    let ir = compile_mlir(source).unwrap();
+    let third = function(&ir, "Test__third");
+    assert!(third.contains("llvm.store"));
+    assert!(third.contains("llvm.getelementptr"));
+    assert!(third.contains("llvm.load"));
+
+    // Indexing a span checks the index against its length.
+    let at = function(&ir, "Test__at");
+    assert!(at.contains("arith.cmpi uge"));
+    assert!(at.contains("call @au_abort("));
+    assert!(at.contains("llvm.getelementptr"));
+}
+
+#[test]
+fn borrows() {
+    let source = "
+        module body Test is
+            function main(): ExitCode is
+                let x: Int32 := 1;
+                borrow r: &[Int32, R] := &x is
+                    let y: Int32 := !r;
+                end
+                return ExitSuccess();
+            end;
+        end module body.
+    ";
+    let ir = compile_mlir(source).unwrap();
+    // The value is spilled to the stack so that it has an address.
+    let main = function(&ir, "main");
+    assert!(main.contains("llvm.alloca"));
+    assert!(main.contains("llvm.store"));
+    assert!(main.contains("llvm.load"));
+}
+
+#[test]
+fn embed() {
+    let source = "
+        pragma Unsafe_Module;
+
+        module body Test is
+            function equal(a: Int32, b: Int32): Bool is
+                return @embed(Bool, \"$1 = $2\", a, b);
+            end;
+
+            function main(): ExitCode is
+                return ExitSuccess();
+            end;
+        end module body.
+    ";
+    let ir = compile_mlir(source).unwrap();
+    assert!(function(&ir, "Test__equal").contains("arith.cmpi eq, %arg0, %arg1"));
+}
+
+#[test]
+fn sizeof_padding() {
+    let source = "
+        module body Test is
+            record Padded: Free is
+                a: Nat8;
+                b: Int32;
+            end;
+
+            function main(): ExitCode is
+                let size: ByteSize := sizeof(Padded);
+                return ExitSuccess();
+            end;
+        end module body.
+    ";
+    let ir = compile_mlir(source).unwrap();
+    assert!(function(&ir, "main").contains("arith.constant 8 : i64"));
+}
+
+#[test_case("x86_64-unknown-linux-gnu", 16)]
+#[test_case("i686-unknown-linux-gnu", 12)]
+fn sizeof_follows_target(triple: &str, size: u64) {
+    let source = "
+        module body Test is
+            record Padded: Free is
+                a: Nat8;
+                b: Int64;
+            end;
+
+            function main(): ExitCode is
+                let size: ByteSize := sizeof(Padded);
+                return ExitSuccess();
+            end;
+        end module body.
+    ";
+    let ir = compile_mlir_for_target(source, &TargetOptions::new(triple)).unwrap();
+    assert!(function(&ir, "main").contains(&format!("arith.constant {size} : i64")));
+}
+
+#[test]
+fn unsupported_target() {
+    let source = "
+        module body Test is
+            function main(): ExitCode is
+                return ExitSuccess();
+            end;
+        end module body.
+    ";
+    assert!(matches!(
+        compile_mlir_for_target(source, &TargetOptions::new("unknown-unknown-unknown")),
+        Err(CodegenError::Target(_))
+    ));
+}
+
+#[test]
+fn runtime() {
+    let source = "
+        module body Test is
+            function main(): ExitCode is
+                let n: Index := argumentCount();
+                if n = 0 then
+                    abort(\"no arguments\");
+                end if;
+                return ExitSuccess();
+            end;
+        end module body.
+    ";
+    let ir = compile_mlir(source).unwrap();
+    assert!(ir.contains("func.func private @au_argument_count() -> i64"));
+    assert!(ir.contains("func.func private @au_abort(%arg0: !llvm.ptr, %arg1: i64, %arg2: i32)"));
+
+    // `main` saves the arguments of the process for the runtime.
+    let main = function(&ir, "main");
+    assert!(main.contains("memref.get_global @au_argc"));
+    assert!(main.contains("memref.get_global @au_argv"));
+    assert!(main.contains("call @au_argument_count()"));
+    assert!(main.contains("call @au_abort("));
+}
+
+#[test]
+fn printable() {
+    let source = "
+        module body Test is
+            function main(): ExitCode is
+                let x: Int32 := 5;
+                let b: Bool := true;
+                let f: Float64 := 1.5;
+                printLn(x);
+                printLn(b);
+                printLn(f);
+                return ExitSuccess();
+            end;
+        end module body.
+    ";
+    let ir = compile_mlir(source).unwrap();
+    assert!(ir.contains("func.func private @au_Printable_print_Int64(%arg0: i64) -> i1"));
+
+    let main = function(&ir, "main");
+    assert!(main.contains("call @au_Printable_print_Int32("));
+    assert!(main.contains("call @au_Printable_print_Bool("));
+    assert!(main.contains("call @au_Printable_print_Float64("));
+}
+
+#[test]
+fn string_literals() {
+    let source = "
+        module body Test is
+            function main(): ExitCode is
+                printLn(\"hello\");
+                printLn(\"hello\");
+                return ExitSuccess();
+            end;
+        end module body.
+    ";
+    let ir = compile_mlir(source).unwrap();
+    // Literals are read-only, NUL-terminated and emitted once per module.
+    let hello = ": memref<6xi8> = dense<[104, 101, 108, 108, 111, 0]>";
+    assert_eq!(ir.matches(hello).count(), 1);
+    assert!(ir
+        .lines()
+        .any(|line| line.contains("constant @LiteralStr") && line.contains(hello)));
+}
+
+#[test]
+fn foreign_span_arguments() {
+    let source = "
+        module body Test is
+            pragma Foreign_Import(External_Name => \"puts\");
+            function Put(str: Span[Nat8, Static]): Int32 is
+            end;
+
+            function say(str: Span[Nat8, Static]): Int32 is
+                return Put(str);
+            end;
+
+            function main(): ExitCode is
+                Put(\"hello\");
+                return ExitSuccess();
+            end;
+        end module body.
+    ";
+    let ir = compile_mlir(source).unwrap();
+    // C takes spans as the pointer to their first element.
+    assert!(ir.contains("func.func private @puts(!llvm.ptr) -> i32"));
+
+    let say = function(&ir, "Test__say");
+    assert!(say.contains("llvm.extractvalue"));
+    assert!(say.contains("call @puts(") && say.contains(": (!llvm.ptr) -> i32"));
+
+    let main = function(&ir, "main");
+    assert!(!main.contains("llvm.insertvalue"));
+    assert!(main.contains("call @puts(") && main.contains(": (!llvm.ptr) -> i32"));
+}
+
+#[test]
+fn assignment_to_immutable_variable() {
+    let source = "
+        module body Test is
+            function main(): ExitCode is
+                let x: Int32 := 10;
+                x := 20;
+                return ExitSuccess();
+            end;
+        end module body.
+    ";
+    assert_eq!(
+        compile_mlir(source),
+        Err(CodegenError::ImmutableAssignment("x".to_string()))
+    );
+}
+
+#[test]
+fn for_loop_over_non_index_bounds() {
+    let source = "
+        module body Test is
+            function count(n: Int32): Unit is
+                for i from 0 to n do
+                    let j: Index := i;
+                end for;
+                return nil;
+            end;
+        end module body.
+    ";
+    assert_eq!(
+        compile_mlir(source),
+        Err(CodegenError::ForBound {
+            name: "i".to_string(),
+            r#type: "Int32".to_string(),
+        })
+    );
+}
+
+#[test]
+fn record_with_missing_slot() {
+    let source = "
+        module body Test is
+            record Point: Free is
+                x: Int32;
+                y: Int32;
+            end;
+
+            function main(): ExitCode is
+                let p: Point := Point(x => 1);
+                return ExitSuccess();
+            end;
+        end module body.
+    ";
+    assert_eq!(
+        compile_mlir(source),
+        Err(CodegenError::MissingArgument {
+            function: "Point".to_string(),
+            argument: "y".to_string(),
+        })
+    );
+}
+
+#[test]
+fn type_parameter() {
+    let source = "
+        module body Test is
+            generic [T: Free]
+            function identity(x: T): T is
+                return x;
+            end;
+        end module body.
+    ";
+    assert_eq!(
+        compile_mlir(source),
+        Err(CodegenError::UnknownType("T".to_string()))
+    );
+}
+
+#[test]
+fn unknown_function() {
+    let source = "
+        module body Test is
+            function main(): ExitCode is
+                let x: Int32 := missing(1);
+                return ExitSuccess();
+            end;
+        end module body.
+    ";
+    assert_eq!(
+        compile_mlir(source),
+        Err(CodegenError::UnknownFunction("missing".to_string()))
+    );
+}
+
+#[test]
+fn type_ascription_of_variable() {
+    let source = "
+        module body Test is
+            function main(): ExitCode is
+                let x: Int32 := 1;
+                let y: Int64 := x : Int64;
+                return ExitSuccess();
+            end;
+        end module body.
+    ";
+    assert_eq!(
+        compile_mlir(source),
+        Err(CodegenError::TypeAscription("Int64".to_string()))
+    );
+}
@@ -0,0 +1,70 @@
+use austral_lib::{
+    ast::ModuleDef,
+    backend::pass_manager::run_pass_manager,
+    compiler::{compile_for_target, compile_to_binary, module_to_object, TargetOptions},
+    error::CodegenError,
+    lexer::lex,
+};
+use chumsky::Parser;
+use melior::{dialect::DialectRegistry, utility::register_all_llvm_translations, Context};
+use std::fs;
+use test_case::test_case;
+
+const EM_X86_64: u16 = 62;
+const EM_AARCH64: u16 = 183;
+const EM_RISCV: u16 = 243;
+
+#[test_case("x86_64-unknown-linux-gnu", EM_X86_64)]
+#[test_case("aarch64-unknown-linux-gnu", EM_AARCH64)]
+#[test_case("riscv64-unknown-linux-gnu", EM_RISCV)]
+fn object_for_target(triple: &str, machine: u16) {
+    let source =
+        fs::read_to_string("../../programs/suites/001-trivial/001-null-program/Test.aum").unwrap();
+    let tokens = lex(&source)
+        .map(|(token, _)| token.unwrap())
+        .collect::<Vec<_>>();
+    let ast = ModuleDef::parser().parse(&tokens).into_result().unwrap();
+
+    let context = Context::new();
+    context.append_dialect_registry(&{
+        let dialect_registry = DialectRegistry::new();
+        melior::utility::register_all_dialects(&dialect_registry);
+        dialect_registry
+    });
+    register_all_llvm_translations(&context);
+    context.load_all_available_dialects();
+
+    let target = TargetOptions::new(triple);
+    let mut module = compile_for_target(&context, &ast, &[], &target).unwrap();
+    run_pass_manager(&context, &mut module).unwrap();
+    let object = module_to_object(&module, &target).unwrap();
+
+    // A 64-bit little-endian ELF object for the requested machine.
+    assert_eq!(&object[..4], b"\x7fELF");
+    assert_eq!(object[4], 2);
+    assert_eq!(object[5], 1);
+    assert_eq!(u16::from_le_bytes([object[18], object[19]]), machine);
+}
+
+#[test]
+fn host_target() {
+    assert!(TargetOptions::host().is_host());
+    assert!(TargetOptions::new(TargetOptions::host().triple).is_host());
+}
+
+#[test]
+fn binary_for_other_target() {
+    let source =
+        fs::read_to_string("../../programs/suites/001-trivial/001-null-program/Test.aum").unwrap();
+    let triple = "riscv64-unknown-linux-gnu";
+    let output = std::env::temp_dir().join("austral-binary-for-other-target");
+
+    // The host's linker can't link objects for another machine.
+    let error =
+        compile_to_binary(&source, &[], false, &output, &TargetOptions::new(triple)).unwrap_err();
+    assert_eq!(
+        error.downcast_ref::<CodegenError>(),
+        Some(&CodegenError::CrossLinking(triple.to_string()))
+    );
+    assert!(!output.exists());
+}